@@ -0,0 +1,21 @@
+/* preflop_table.rs
+ * Estimate every hold 'em starting hand's equity against every other from a large
+ * fixed sample of boards, and write out the table pokercalc ships with, eg:
+ *
+ *   cargo run --release --example preflop_table > src/preflop_equities.txt
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+extern crate pokercalc;
+
+use pokercalc::preflop::Equities;
+use pokercalc::preflop::TABLE_BOARDS;
+use pokercalc::preflop::TABLE_SEED;
+
+fn main()
+{
+  let equities = Equities::sample(TABLE_BOARDS, TABLE_SEED);
+  print!("{}", equities.to_text(&format!("equity of each starting hand class against each other, from {} boards dealt from seed {}",
+                                          TABLE_BOARDS, TABLE_SEED)));
+}
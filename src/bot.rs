@@ -0,0 +1,151 @@
+/* bot.rs
+ * Players for the betting engine to pit against each other: the Bot trait, which sees
+ * the hand as it goes and picks an action on its turn, and a few simple bots to measure
+ * others against.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use equity;
+use range::Range;
+use rng::Rng;
+use table::Action;
+use table::GameState;
+use table::Legal;
+
+/* a computer player. bots are handed the whole state of the hand, which includes everyone's
+   hole cards, so play fair and only look at your own */
+pub trait Bot
+{
+  /* observe
+     See the hand as it stands. Called after every action, and once more when the hand's over
+     => state = the hand so far
+        me = our index in the hand's players */
+  fn observe(&mut self, _state: &GameState, _me: usize)
+  {
+  }
+
+  /* choose
+     Pick what to do on our turn
+     => state = the hand so far
+        me = our index in the hand's players
+        legal = what we're allowed to do
+     <= our action. anything not allowed is taken as a check if possible, or a fold */
+  fn choose(&mut self, state: &GameState, me: usize, legal: &Legal) -> Action;
+}
+
+/* plays at random: folds, checks or calls, or bets or raises a random amount, with equal chance */
+pub struct RandomBot
+{
+  rng: Rng
+}
+
+impl RandomBot
+{
+  pub fn new(seed: u64) -> RandomBot
+  {
+    RandomBot { rng: Rng::new(seed) }
+  }
+}
+
+impl Bot for RandomBot
+{
+  fn choose(&mut self, state: &GameState, _me: usize, legal: &Legal) -> Action
+  {
+    let mut choices = vec![if legal.check { Action::Check } else { Action::Call }];
+    if !legal.check
+    {
+      choices.push(Action::Fold);
+    }
+    if let Some((least, most)) = legal.raise
+    {
+      let to = least + self.rng.below((most - least + 1) as usize) as u64;
+      choices.push(if state.current_bet == 0 { Action::Bet(to) } else { Action::Raise(to) });
+    }
+    choices[self.rng.below(choices.len())]
+  }
+}
+
+/* never folds, never raises: checks when it can, and calls anything */
+pub struct CallingStation;
+
+impl Bot for CallingStation
+{
+  fn choose(&mut self, _state: &GameState, _me: usize, legal: &Legal) -> Action
+  {
+    match legal.check
+    {
+      true => Action::Check,
+      false => Action::Call
+    }
+  }
+}
+
+/* bets by its equity against random hands: raises the size of the pot when its equity
+   is high enough, calls when its equity is enough to call and beats the pot odds, and
+   otherwise checks or folds */
+pub struct EquityBot
+{
+  pub call: f64,   /* least equity to call with, from 0.0 to 1.0 */
+  pub raise: f64,  /* least equity to bet or raise with */
+  samples: usize, /* deals to sample when estimating equity */
+  rng: Rng
+}
+
+impl EquityBot
+{
+  /* new
+     Create a bot that plays by its equity
+     => call = least equity to call with, from 0.0 to 1.0
+        raise = least equity to bet or raise with
+        samples = deals to sample each time it works out its equity
+        seed = starting point for its random numbers
+     <= EquityBot object */
+  pub fn new(call: f64, raise: f64, samples: usize, seed: u64) -> EquityBot
+  {
+    EquityBot { call, raise, samples, rng: Rng::new(seed) }
+  }
+
+  /* estimate our equity against every opponent still in, each holding a random hand */
+  fn equity(&mut self, state: &GameState, me: usize) -> Option<f64>
+  {
+    let hole = &state.players[me].hole;
+    let mut known: Vec<Card> = hole.clone();
+    known.extend_from_slice(&state.board);
+    let range = Range::uniform_for(&state.rules, &known);
+    let heads_up = equity::sample(&state.rules, hole, &state.board, &[], &range, self.samples, self.rng.next_u64())?;
+
+    /* beating several opponents is roughly like beating each of them in turn */
+    let opponents = state.players.iter().enumerate().filter(|(index, player)| *index != me && !player.folded).count();
+    Some(heads_up.equity.powi(opponents as i32))
+  }
+}
+
+impl Bot for EquityBot
+{
+  fn choose(&mut self, state: &GameState, me: usize, legal: &Legal) -> Action
+  {
+    let equity = self.equity(state, me).unwrap_or(0.0);
+    let owed = legal.call.unwrap_or(0);
+    let odds = owed as f64 / (state.pot() + owed) as f64;
+
+    if let Some((least, most)) = legal.raise
+    {
+      if equity >= self.raise
+      {
+        let to = (state.current_bet + owed + state.pot()).max(least).min(most);
+        return if state.current_bet == 0 { Action::Bet(to) } else { Action::Raise(to) };
+      }
+    }
+    if legal.check
+    {
+      return Action::Check;
+    }
+    if equity >= self.call && equity >= odds
+    {
+      return Action::Call;
+    }
+    Action::Fold
+  }
+}
@@ -0,0 +1,539 @@
+/* canon.rs
+ * Map hole cards and boards onto a canonical, suit-isomorphic form and give each
+ * distinct form a perfect index.
+ *
+ * Suits carry no rank in hold 'em, so Ah Kh on a Qh 7c 2d flop plays exactly like
+ * As Ks on Qs 7d 2c. An indexer is built for a sequence of rounds, such as [2, 3, 1, 1]
+ * for the hole cards, flop, turn and river, and numbers every suit-isomorphic set of
+ * cards dealt up to a given round from zero with no gaps, along the lines of Kevin
+ * Waugh's hand indexer. Cards within a round are unordered; cards in different rounds
+ * are not interchangeable. For example, the 22100 possible flops collapse into 1755
+ * distinct ones, and the 1326 starting hands into 169.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use card::Suit;
+use card::Value;
+use deck::Deck;
+use deck::combinations;
+
+use std::collections::HashMap;
+
+const RANKS: u32 = 13;
+const SUITS: usize = 4;
+
+/* choose
+   Calculate a binomial coefficient
+   => n = size of set to pick from
+      k = number of items to pick
+   <= number of ways to pick k items from n, or 0 if k > n */
+pub fn choose(n: u64, k: u64) -> u64
+{
+  if k > n
+  {
+    return 0;
+  }
+
+  let k = if k > n - k { n - k } else { k };
+  let mut result = 1;
+  for i in 0..k
+  {
+    result = result * (n - i) / (i + 1);
+  }
+  result
+}
+
+/* a layout of cards across the suits for a round, with the suits sorted so that the
+   suit holding the most cards in the earliest rounds comes first */
+struct Config
+{
+  suits: Vec<Vec<usize>>,          /* for each canonical suit, number of cards it holds per round */
+  groups: Vec<(usize, usize, u64)> /* runs of identical suits: (first suit, suits in run, combos per suit) */
+}
+
+/* everything needed to index the cards dealt up to and including a round */
+struct Round
+{
+  configs: Vec<Config>,               /* all possible layouts, in index order */
+  offsets: Vec<u64>,                  /* index of the first hand in each layout */
+  lookup: HashMap<Vec<usize>, usize>, /* flattened layout to its position in configs */
+  size: u64                           /* number of distinct hands this round */
+}
+
+pub struct Indexer
+{
+  rounds: Vec<usize>, /* number of cards dealt in each round */
+  tables: Vec<Round>  /* indexing tables for each round */
+}
+
+impl Indexer
+{
+  /* new
+     Create an indexer for a sequence of rounds
+     => rounds = number of cards dealt in each round, eg [2, 3, 1, 1] for hold 'em
+     <= Indexer object */
+  pub fn new(rounds: &[usize]) -> Indexer
+  {
+    let mut tables = Vec::<Round>::new();
+    for round in 0..rounds.len()
+    {
+      tables.push(build_round(&rounds[0..round + 1]));
+    }
+
+    Indexer
+    {
+      rounds: rounds.to_vec(),
+      tables
+    }
+  }
+
+  /* create an indexer for hold 'em: hole cards, flop, turn and river */
+  pub fn holdem() -> Indexer
+  {
+    Indexer::new(&[2, 3, 1, 1])
+  }
+
+  /* return the number of rounds this indexer covers */
+  pub fn rounds(&self) -> usize
+  {
+    self.rounds.len()
+  }
+
+  /* size
+     => round = round to inspect, counting from zero
+     <= number of distinct hands dealt up to and including this round */
+  pub fn size(&self, round: usize) -> u64
+  {
+    self.tables[round].size
+  }
+
+  /* work out which round a set of cards has reached, or None if the number of
+     cards doesn't line up with the end of a round */
+  fn round_of(&self, cards: usize) -> Option<usize>
+  {
+    let mut total = 0;
+    for (round, count) in self.rounds.iter().enumerate()
+    {
+      total += count;
+      if total == cards
+      {
+        return Some(round);
+      }
+    }
+    None
+  }
+
+  /* index
+     Calculate the index of a set of cards, identical for all suit-isomorphic sets
+     => cards = cards dealt so far, in round order: eg, two hole cards then the flop
+     <= index of the cards, or None if the card count doesn't end a round,
+        a card appears twice or a card is a joker */
+  pub fn index(&self, cards: &[Card]) -> Option<u64>
+  {
+    let round = self.round_of(cards.len())?;
+    let table = &self.tables[round];
+
+    /* build up a bitmask of ranks held in each suit for each round */
+    let mut masks = vec![vec![0u16; round + 1]; SUITS];
+    let mut seen = [0u16; SUITS];
+    let mut position = 0;
+    for r in 0..(round + 1)
+    {
+      for card in cards[position..position + self.rounds[r]].iter()
+      {
+        /* a joker has no rank among the thirteen, so can't be indexed */
+        if card.value() == Value::Joker
+        {
+          return None;
+        }
+        let suit = card.suit().to_u32() as usize;
+        let bit = 1 << (card.value().to_u32() - 2);
+        if seen[suit] & bit != 0
+        {
+          return None; /* same card dealt twice */
+        }
+        seen[suit] |= bit;
+        masks[suit][r] |= bit;
+      }
+      position += self.rounds[r];
+    }
+
+    /* sort the suits into canonical order by their layout */
+    let counts: Vec<Vec<usize>> = masks.iter()
+                                       .map(|m| m.iter().map(|b| b.count_ones() as usize).collect())
+                                       .collect();
+    let mut order: Vec<usize> = (0..SUITS).collect();
+    order.sort_by(|a, b| counts[*b].cmp(&counts[*a]));
+
+    let mut key = Vec::<usize>::new();
+    for suit in order.iter()
+    {
+      key.extend_from_slice(&counts[*suit]);
+    }
+    let config_nr = match table.lookup.get(&key)
+    {
+      Some(c) => *c,
+      None => return None /* more than thirteen cards in a suit */
+    };
+    let config = &table.configs[config_nr];
+
+    /* identical suits can be swapped freely, so treat each run of them
+       as a multiset of per-suit indexes */
+    let mut index = 0;
+    let mut multiplier = 1;
+    for &(first, count, combos) in config.groups.iter()
+    {
+      let mut members: Vec<u64> = order[first..first + count].iter()
+                                                             .map(|s| suit_index(&masks[*s]))
+                                                             .collect();
+      members.sort();
+
+      let mut group_index = 0;
+      for (i, member) in members.iter().enumerate()
+      {
+        group_index += choose(member + i as u64, i as u64 + 1);
+      }
+
+      index += multiplier * group_index;
+      multiplier *= choose(combos + count as u64 - 1, count as u64);
+    }
+
+    Some(table.offsets[config_nr] + index)
+  }
+
+  /* unindex
+     Turn an index back into a canonical set of cards
+     => round = round the index belongs to, counting from zero
+        index = index to decode
+     <= cards in round order, using hearts, diamonds, clubs then spades as the
+        canonical suits, or None if the index is out of range */
+  pub fn unindex(&self, round: usize, index: u64) -> Option<Vec<Card>>
+  {
+    if round >= self.tables.len() || index >= self.tables[round].size
+    {
+      return None;
+    }
+    let table = &self.tables[round];
+
+    /* find the layout this index falls into */
+    let config_nr = table.offsets.iter().take_while(|o| **o <= index).count() - 1;
+    let config = &table.configs[config_nr];
+    let mut remainder = index - table.offsets[config_nr];
+
+    /* unpick each run of identical suits into per-suit indexes */
+    let mut suit_indexes = [0u64; SUITS];
+    for &(first, count, combos) in config.groups.iter()
+    {
+      let group_size = choose(combos + count as u64 - 1, count as u64);
+      let mut group_index = remainder % group_size;
+      remainder /= group_size;
+
+      for i in (0..count).rev()
+      {
+        /* find the largest b with choose(b, k) <= group_index, by bisection as a suit
+           can be dealt thousands of ways */
+        let k = i as u64 + 1;
+        let (mut b, mut high) = (k - 1, combos + count as u64);
+        while b < high
+        {
+          let middle = (b + high).div_ceil(2);
+          if choose(middle, k) > group_index { high = middle - 1; } else { b = middle; }
+        }
+        group_index -= choose(b, k);
+        suit_indexes[first + i] = b - i as u64;
+      }
+    }
+
+    /* then turn each suit's index into its ranks per round */
+    let mut dealt = vec![Vec::<Card>::new(); round + 1];
+    for (suit_nr, suit_index) in suit_indexes.iter().enumerate()
+    {
+      let suit = Suit::from_u32(suit_nr as u32).unwrap();
+      let masks = suit_unindex(*suit_index, &config.suits[suit_nr]);
+      for (r, mask) in masks.iter().enumerate()
+      {
+        for rank in (0..RANKS).rev()
+        {
+          if mask & (1 << rank) != 0
+          {
+            dealt[r].push(Card { value: Value::from_u32(rank + 2).unwrap(), suit });
+          }
+        }
+      }
+    }
+
+    Some(dealt.concat())
+  }
+
+  /* canonical
+     Rewrite a set of cards in its canonical suit-isomorphic form
+     => cards = cards dealt so far, in round order
+     <= canonical cards, or None if the cards can't be indexed */
+  pub fn canonical(&self, cards: &[Card]) -> Option<Vec<Card>>
+  {
+    match (self.round_of(cards.len()), self.index(cards))
+    {
+      (Some(round), Some(index)) => self.unindex(round, index),
+      _ => None
+    }
+  }
+}
+
+/* distinct_boards
+   List every suit-isomorphic board of a given size once, with the number of real
+   boards it stands for. Weighting each board by its count gives the same results
+   as running through every real board
+   => size = number of board cards, eg 3 for the flop
+   <= canonical boards and their weights, which sum to the number of real boards */
+pub fn distinct_boards(size: usize) -> Vec<(Vec<Card>, u32)>
+{
+  let indexer = Indexer::new(&[size]);
+  let mut weights = vec![0u32; indexer.size(0) as usize];
+
+  let mut deck = Deck::new();
+  combinations(deck.cards(), size, |board|
+  {
+    let index = indexer.index(board).unwrap() as usize;
+    weights[index] += 1;
+  });
+
+  let mut boards = Vec::<(Vec<Card>, u32)>::new();
+  for (index, weight) in weights.iter().enumerate()
+  {
+    boards.push((indexer.unindex(0, index as u64).unwrap(), *weight));
+  }
+  boards
+}
+
+/* build the layouts and offsets needed to index cards up to the end of the given rounds */
+fn build_round(rounds: &[usize]) -> Round
+{
+  /* spread each round's cards across the suits in every possible way */
+  let mut layouts = vec![vec![Vec::<usize>::new(); SUITS]];
+  for count in rounds.iter()
+  {
+    let mut next = Vec::<Vec<Vec<usize>>>::new();
+    for layout in layouts.iter()
+    {
+      for split in splits(*count, SUITS).iter()
+      {
+        let mut extended = layout.clone();
+        for suit in 0..SUITS
+        {
+          extended[suit].push(split[suit]);
+        }
+        next.push(extended);
+      }
+    }
+    layouts = next;
+  }
+
+  /* keep only the canonical layouts, ie those with suits in descending order,
+     that fit into thirteen ranks per suit */
+  layouts.retain(|layout|
+  {
+    layout.iter().all(|suit| suit.iter().sum::<usize>() <= RANKS as usize) &&
+    layout.windows(2).all(|pair| pair[0] >= pair[1])
+  });
+  layouts.sort_by(|a, b| b.cmp(a));
+
+  let mut configs = Vec::<Config>::new();
+  let mut offsets = Vec::<u64>::new();
+  let mut lookup = HashMap::<Vec<usize>, usize>::new();
+  let mut size = 0;
+  for layout in layouts.into_iter()
+  {
+    /* find runs of identical suits */
+    let mut groups = Vec::<(usize, usize, u64)>::new();
+    let mut first = 0;
+    while first < SUITS
+    {
+      let mut count = 1;
+      while first + count < SUITS && layout[first + count] == layout[first]
+      {
+        count += 1;
+      }
+      groups.push((first, count, suit_size(&layout[first])));
+      first += count;
+    }
+
+    let mut config_size = 1;
+    for &(_, count, combos) in groups.iter()
+    {
+      config_size *= choose(combos + count as u64 - 1, count as u64);
+    }
+
+    lookup.insert(layout.concat(), configs.len());
+    offsets.push(size);
+    size += config_size;
+    configs.push(Config { suits: layout, groups });
+  }
+
+  Round
+  {
+    configs,
+    offsets,
+    lookup,
+    size
+  }
+}
+
+/* list every way of splitting a number of cards between a number of suits */
+fn splits(cards: usize, suits: usize) -> Vec<Vec<usize>>
+{
+  if suits == 1
+  {
+    return vec![vec![cards]];
+  }
+
+  let mut list = Vec::<Vec<usize>>::new();
+  for here in 0..(cards + 1)
+  {
+    for mut rest in splits(cards - here, suits - 1).into_iter()
+    {
+      rest.insert(0, here);
+      list.push(rest);
+    }
+  }
+  list
+}
+
+/* number of ways a single suit can be dealt the given number of cards per round */
+fn suit_size(counts: &[usize]) -> u64
+{
+  let mut used = 0;
+  let mut size = 1;
+  for count in counts.iter()
+  {
+    size *= choose((RANKS as usize - used) as u64, *count as u64);
+    used += count;
+  }
+  size
+}
+
+/* index the ranks dealt to a single suit across the rounds. each round's ranks are
+   numbered among the ranks not yet dealt, so the index is gap-free */
+fn suit_index(masks: &[u16]) -> u64
+{
+  let mut used = 0u16;
+  let mut index = 0;
+  let mut multiplier = 1;
+  for mask in masks.iter()
+  {
+    let mut round_index = 0;
+    let mut picked = 0;
+    for rank in 0..RANKS
+    {
+      if mask & (1 << rank) != 0
+      {
+        let position = rank - (used & ((1 << rank) - 1)).count_ones();
+        picked += 1;
+        round_index += choose(position as u64, picked);
+      }
+    }
+
+    index += multiplier * round_index;
+    multiplier *= choose((RANKS - used.count_ones()) as u64, mask.count_ones() as u64);
+    used |= mask;
+  }
+  index
+}
+
+/* reverse suit_index, returning the mask of ranks dealt in each round */
+fn suit_unindex(mut index: u64, counts: &[usize]) -> Vec<u16>
+{
+  let mut used = 0u16;
+  let mut masks = Vec::<u16>::new();
+  for count in counts.iter()
+  {
+    let available = RANKS - used.count_ones();
+    let round_size = choose(available as u64, *count as u64);
+    let mut round_index = index % round_size;
+    index /= round_size;
+
+    /* pick out the positions among the unused ranks, highest first */
+    let mut positions = Vec::<u32>::new();
+    let mut position = available;
+    for k in (1..(*count as u64 + 1)).rev()
+    {
+      position -= 1;
+      while choose(position as u64, k) > round_index
+      {
+        position -= 1;
+      }
+      round_index -= choose(position as u64, k);
+      positions.push(position);
+    }
+
+    /* map those positions back onto real ranks */
+    let mut mask = 0u16;
+    let mut free = 0;
+    for rank in 0..RANKS
+    {
+      if used & (1 << rank) == 0
+      {
+        if positions.contains(&free)
+        {
+          mask |= 1 << rank;
+        }
+        free += 1;
+      }
+    }
+
+    masks.push(mask);
+    used |= mask;
+  }
+  masks
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn holdem_class_counts()
+  {
+    let indexer = Indexer::holdem();
+    let sizes: Vec<u64> = (0..indexer.rounds()).map(|round| indexer.size(round)).collect();
+    assert_eq!(sizes, vec![169, 1286792, 55190538, 2428287420]);
+  }
+
+  #[test]
+  fn hole_cards_and_flop_round_trip_without_gaps()
+  {
+    /* every index from zero up to the size decodes to cards that index back to it,
+       so the indexes are all in use and none is shared */
+    let indexer = Indexer::new(&[2, 3]);
+    for index in 0..indexer.size(1)
+    {
+      let cards = indexer.unindex(1, index).unwrap();
+      assert_eq!(indexer.index(&cards), Some(index), "{:?}", cards);
+    }
+    assert_eq!(indexer.unindex(1, indexer.size(1)), None);
+
+    /* and suit-isomorphic hands share an index while ordering within a round doesn't matter */
+    let cards = |text: &str| -> Vec<Card> { text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect() };
+    let index = indexer.index(&cards("ah kh qh 7c 2d"));
+    assert_eq!(index, indexer.index(&cards("ks as 2c qs 7d")));
+    assert_ne!(index, indexer.index(&cards("ah kh qc 7h 2d")));
+  }
+
+  #[test]
+  fn flop_weights()
+  {
+    let flops = distinct_boards(3);
+    assert_eq!(flops.len(), 1755);
+    assert_eq!(flops.iter().map(|(_, weight)| weight).sum::<u32>(), 22100);
+  }
+
+  #[test]
+  fn jokers_are_not_indexed()
+  {
+    let indexer = Indexer::new(&[2]);
+    assert_eq!(indexer.index(&[Card::new("xh").unwrap(), Card::new("as").unwrap()]), None);
+    assert!(indexer.index(&[Card::new("ah").unwrap(), Card::new("as").unwrap()]).is_some());
+  }
+}
@@ -9,7 +9,8 @@
 pub enum Value
 {
   Two, Three, Four, Five, Six, Seven, Eight, Nine,
-  Ten, Jack, Queen, King, Ace
+  Ten, Jack, Queen, King, Ace,
+  Joker /* only in games played with jokers, which are always wild */
 }
 
 impl Value
@@ -32,7 +33,8 @@ impl Value
       Value::Jack  => "jack",
       Value::Queen => "queen",
       Value::King  => "king",
-      Value::Ace   => "ace"
+      Value::Ace   => "ace",
+      Value::Joker => "joker"
     }
   }
 
@@ -54,12 +56,13 @@ impl Value
       Value::Jack  => 'J',
       Value::Queen => 'Q',
       Value::King  => 'K',
-      Value::Ace   => 'A'
+      Value::Ace   => 'A',
+      Value::Joker => 'X'
     }
   }
 
   /* convert internal card value into an unsigned integer
-     <= integer between 2 (two) and 14 (ace) */
+     <= integer between 2 (two) and 14 (ace), or 15 for a joker */
   pub fn to_u32(self) -> u32
   {
     match self
@@ -76,7 +79,32 @@ impl Value
       Value::Jack  => 11,
       Value::Queen => 12,
       Value::King  => 13,
-      Value::Ace   => 14
+      Value::Ace   => 14,
+      Value::Joker => 15
+    }
+  }
+
+  /* convert an unsigned integer into an internal card value
+     => value = integer between 2 (two) and 14 (ace)
+     <= card value, or None if out of range */
+  pub fn from_u32(value: u32) -> Option<Value>
+  {
+    match value
+    {
+      2  => Some(Value::Two),
+      3  => Some(Value::Three),
+      4  => Some(Value::Four),
+      5  => Some(Value::Five),
+      6  => Some(Value::Six),
+      7  => Some(Value::Seven),
+      8  => Some(Value::Eight),
+      9  => Some(Value::Nine),
+      10 => Some(Value::Ten),
+      11 => Some(Value::Jack),
+      12 => Some(Value::Queen),
+      13 => Some(Value::King),
+      14 => Some(Value::Ace),
+      _  => None
     }
   }
 }
@@ -105,10 +133,51 @@ impl Suit
        Suit::Spade   => '\u{2660}',
      }
    }
+
+  /* convert card suit into a plain text letter, as used to enter cards
+     <= lowercase letter representing card suit */
+  pub fn to_letter(self) -> char
+  {
+    match self
+    {
+      Suit::Heart   => 'h',
+      Suit::Diamond => 'd',
+      Suit::Club    => 'c',
+      Suit::Spade   => 's'
+    }
+  }
+
+  /* convert card suit into an unsigned integer
+     <= integer between 0 (hearts) and 3 (spades) */
+  pub fn to_u32(self) -> u32
+  {
+    match self
+    {
+      Suit::Heart   => 0,
+      Suit::Diamond => 1,
+      Suit::Club    => 2,
+      Suit::Spade   => 3
+    }
+  }
+
+  /* convert an unsigned integer into a card suit
+     => suit = integer between 0 (hearts) and 3 (spades)
+     <= card suit, or None if out of range */
+  pub fn from_u32(suit: u32) -> Option<Suit>
+  {
+    match suit
+    {
+      0 => Some(Suit::Heart),
+      1 => Some(Suit::Diamond),
+      2 => Some(Suit::Club),
+      3 => Some(Suit::Spade),
+      _ => None
+    }
+  }
 }
 
 /* the card object */
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Card
 {
   pub value: Value, /* abstracted representation of the value */
@@ -119,7 +188,8 @@ impl Card
 {
   /* new
      create new card from card description
-     => desc = lowercase syntax string defining this card (2-9tjqka)(hdcs)
+     => desc = lowercase syntax string defining this card (2-9tjqka)(hdcs),
+               or xh or xs for the red and black jokers
      <= Card object or None if error
   */
   pub fn new(desc: &str) -> Option<Card>
@@ -141,6 +211,7 @@ impl Card
         'q' => Value::Queen,
         'k' => Value::King,
         'a' => Value::Ace,
+        'x' => Value::Joker,
         _ =>
         {
           println!("Invalid value '{}' in card '{}'", c, desc);
@@ -171,13 +242,19 @@ impl Card
       }
     };
 
+    if value == Value::Joker && suit != Suit::Heart && suit != Suit::Spade
+    {
+      println!("Invalid joker '{}': use xh or xs for the red and black jokers", desc);
+      return None;
+    }
+
     let card = Card
     {
-      value: value,
-      suit: suit
+      value,
+      suit
     };
 
-    return Some(card);
+    Some(card)
   }
 
   /* describe
@@ -187,7 +264,18 @@ impl Card
     let mut desc = String::new();
     desc.push(self.value.to_char());
     desc.push(self.suit.to_char());
-    return desc;
+    desc
+  }
+
+  /* code
+     Return the card in plain text, value then suit letter, eg "Ah" for the
+     ace of hearts. Card::new() accepts this once lowercased */
+  pub fn code(&self) -> String
+  {
+    let mut code = String::new();
+    code.push(self.value.to_char());
+    code.push(self.suit.to_letter());
+    code
   }
 
   /* return the suit of the card */
@@ -7,17 +7,57 @@
  use card::Card;
  use card::Suit;
  use card::Value;
+ use hand::Ranking;
+ use rules::Rules;
 
 pub struct Deck
 {
   cards: Vec<Card>
 }
 
+/* a full deck of 52 cards, the same as Deck::new() */
+impl Default for Deck
+{
+  fn default() -> Deck
+  {
+    Deck::new()
+  }
+}
+
 impl Deck
 {
-  /* create a full deck of cards. Rust won't easily iterate over
-     an enum so we have to do it this way... */
+  /* create a full deck of 52 cards */
   pub fn new() -> Deck
+  {
+    Deck::from_value(2)
+  }
+
+  /* create a short deck of 36 cards, with the twos to fives taken out */
+  pub fn short() -> Deck
+  {
+    Deck::from_value(6)
+  }
+
+  /* create the deck the given game is played with, including any jokers */
+  pub fn with_rules(rules: &Rules) -> Deck
+  {
+    let mut deck = match rules.ranking
+    {
+      Ranking::Standard => Deck::new(),
+      Ranking::ShortDeck { .. } => Deck::short()
+    };
+
+    /* the red joker, then the black one */
+    for suit in [Suit::Heart, Suit::Spade].iter().take(rules.wilds.jokers)
+    {
+      deck.cards.push(Card { value: Value::Joker, suit: *suit });
+    }
+    deck
+  }
+
+  /* create a deck holding every card from the given value up to the aces.
+     Rust won't easily iterate over an enum so we have to do it this way... */
+  fn from_value(lowest: u32) -> Deck
   {
     /* start off with an empty deck */
     let mut deck = Deck { cards: Vec::<Card>::new() };
@@ -35,7 +75,7 @@ impl Deck
       };
 
       /* for each suit, go through each card value */
-      for value_loop in 2..15
+      for value_loop in lowest..15
       {
         let value = match value_loop
         {
@@ -55,11 +95,11 @@ impl Deck
           _ => unreachable!()
         };
 
-        deck.cards.push(Card { value: value, suit: suit });
+        deck.cards.push(Card { value, suit });
       }
     }
 
-    return deck;
+    deck
   }
 
   pub fn remove(&mut self, victim: &Card)
@@ -75,17 +115,72 @@ impl Deck
         break;
       }
 
-      i = i + 1;
+      i += 1;
     }
 
-    if found == true
+    if found
     {
       self.cards.remove(i);
     }
   }
 
+  /* take a set of cards out of the deck, eg, everything seen and any dead cards */
+  pub fn remove_all(&mut self, victims: &[Card])
+  {
+    for victim in victims.iter()
+    {
+      self.remove(victim);
+    }
+  }
+
   pub fn cards(&mut self) -> &mut Vec<Card>
   {
     &mut self.cards
   }
 }
+
+/* combinations
+   Run through every unordered selection of cards from a slice, in a fixed order
+   => cards = cards to select from
+      size = number of cards in each selection
+      f = closure called with each selection in turn */
+pub fn combinations<F>(cards: &[Card], size: usize, mut f: F) where F: FnMut(&[Card])
+{
+  if size > cards.len()
+  {
+    return;
+  }
+
+  /* positions into cards of the current selection, starting with the first few */
+  let mut positions: Vec<usize> = (0..size).collect();
+  let mut selection: Vec<Card> = cards[0..size].to_vec();
+
+  loop
+  {
+    f(&selection);
+
+    /* find the rightmost position that can still move up */
+    let mut i = size;
+    loop
+    {
+      if i == 0
+      {
+        return; /* all selections made */
+      }
+      i -= 1;
+      if positions[i] < cards.len() - size + i
+      {
+        break;
+      }
+    }
+
+    /* bump it along and reset everything to its right */
+    positions[i] += 1;
+    selection[i] = cards[positions[i]];
+    for j in (i + 1)..size
+    {
+      positions[j] = positions[j - 1] + 1;
+      selection[j] = cards[positions[j]];
+    }
+  }
+}
@@ -0,0 +1,348 @@
+/* draw.rs
+ * Analyse a hand of a draw game, such as five-card draw or deuce-to-seven triple draw,
+ * where there's no board and the big decision is which cards to throw away. Every
+ * possible discard, from standing pat to throwing all five, is played out over a
+ * repeatable sample of deals to find the types of hand it ends up as, or the lows
+ * when playing for low, and its equity against an opponent, so the best one can be
+ * picked.
+ *
+ * After the first draw, the player keeps drawing as many cards as they first threw,
+ * holding on to the best of the rest, until they make a hand worth standing pat on:
+ * a straight or better when playing for high, or any hand without a pair, straight
+ * or flush when playing for low. The opponent, with a known hand or a random one,
+ * draws the same way on every draw, holding their paired cards when playing for
+ * high, or one of each card eight or lower when playing for low. Discarded cards are
+ * out of play.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use card::Value;
+use deck::Deck;
+use deck::combinations;
+use equity;
+use equity::Equity;
+use hand::Strength;
+use hand::STRENGTHS;
+use low;
+use low::Lowball;
+use parallel;
+use rng::Rng;
+use rules::Game;
+use rules::Rules;
+
+/* cards in each player's hand */
+const CARDS: usize = 5;
+
+/* one way of playing the hand */
+#[derive(Debug, Clone)]
+pub struct Discard
+{
+  pub discard: Vec<Card>,              /* cards thrown away on the first draw */
+  pub keep: Vec<Card>,                 /* cards held on to */
+  pub strengths: Vec<(Strength, f64)>, /* chance of ending up with each type of high hand, best
+                                          first, or none when playing for low */
+  pub lows: Vec<(Option<u32>, f64)>,   /* chance of ending up with each low by its top card, as
+                                          Lowball::top() gives it, best first, then of no made
+                                          low as None, or none when playing for high */
+  pub equity: Equity                   /* share of the pot against the opponent */
+}
+
+#[derive(Debug, Clone)]
+pub struct Draw
+{
+  pub discards: Vec<Discard> /* every way of playing the hand, best equity first */
+}
+
+impl Draw
+{
+  /* new
+     Play out every discard from a draw hand
+     => rules = draw game rules
+        hand = our five cards
+        dead = other cards known to be out of play
+        opponent = the opponent's five cards, if known, or None for a random hand
+        deals = number of random deals to sample for each discard
+        seed = starting point for the random numbers
+     <= Draw object, or None if the game isn't a draw game, a hand isn't five cards,
+        a card appears twice, or there aren't enough cards left to draw from */
+  pub fn new(rules: &Rules, hand: &[Card], dead: &[Card], opponent: Option<&[Card]>,
+             deals: usize, seed: u64) -> Option<Draw>
+  {
+    let draws = match rules.game
+    {
+      Game::Draw(draws) => draws,
+      _ => return None
+    };
+
+    if hand.len() != CARDS || deals == 0 || opponent.is_some_and(|cards| cards.len() != CARDS)
+    {
+      return None;
+    }
+
+    let mut known = hand.to_vec();
+    known.extend_from_slice(dead);
+    known.extend_from_slice(opponent.unwrap_or(&[]));
+    if equity::has_duplicates(&known)
+    {
+      return None;
+    }
+
+    let mut deck = Deck::with_rules(rules);
+    for card in known.iter()
+    {
+      deck.remove(card);
+    }
+    let unseen = deck.cards().clone();
+
+    /* there must be enough cards for a random opponent and the most both of us could draw */
+    let needed = 2 * draws * CARDS + if opponent.is_none() { CARDS } else { 0 };
+    if unseen.len() < needed
+    {
+      return None;
+    }
+
+    /* every set of cards we could throw, from none to all five */
+    let mut choices = Vec::<Vec<Card>>::new();
+    for size in 0..(CARDS + 1)
+    {
+      combinations(hand, size, |discard| choices.push(discard.to_vec()));
+    }
+
+    /* each thread takes one discard at a time. each discard is dealt from the same
+       seed, so the answer doesn't depend on the number of threads */
+    let mut discards = parallel::map(choices.len(), |choice|
+    {
+      let discard = &choices[choice];
+      let keep: Vec<Card> = hand.iter().filter(|card| !discard.contains(card)).cloned().collect();
+      let mut rng = Rng::new(seed);
+      let mut counts = [0usize; STRENGTHS.len()];
+      let mut tops = [0usize; 16]; /* by top card, with zero for no made low */
+      let mut tally = Equity::new();
+      for _ in 0..deals
+      {
+        let mut deck = unseen.clone();
+        let mut theirs = match opponent
+        {
+          Some(cards) => cards.to_vec(),
+          None => deal(&mut deck, CARDS, &mut rng)
+        };
+
+        let mut ours = keep.clone();
+        ours.extend(deal(&mut deck, discard.len(), &mut rng));
+        let holding = opponent_keep(rules, &theirs);
+        redraw(rules, &mut theirs, holding, &mut deck, &mut rng);
+        for _ in 1..draws
+        {
+          redraw(rules, &mut ours, keep.len(), &mut deck, &mut rng);
+          redraw(rules, &mut theirs, holding, &mut deck, &mut rng);
+        }
+
+        match rules.lowball
+        {
+          Some(lowball) => tops[lowball.top(rules.score(&ours, &[])).unwrap_or(0) as usize] += 1,
+          None =>
+          {
+            let strength = rules.hand(&ours, &[]).strength();
+            if let Some(position) = STRENGTHS.iter().position(|s| *s == strength)
+            {
+              counts[position] += 1;
+            }
+          }
+        }
+
+        let mut tallies = [Equity::new(); 2];
+        equity::award(rules, &[&ours, &theirs], &[], &mut tallies);
+        tally.add(&tallies[0], 1.0);
+      }
+      tally.scale(deals as f64);
+
+      let strengths = STRENGTHS.iter()
+                               .zip(counts.iter())
+                               .filter(|(_, count)| **count > 0)
+                               .map(|(strength, count)| (*strength, *count as f64 / deals as f64))
+                               .collect();
+      let lows = (1..tops.len()).chain(0..1)
+                                .filter(|top| tops[*top] > 0)
+                                .map(|top| (Some(top as u32).filter(|top| *top > 0), tops[top] as f64 / deals as f64))
+                                .collect();
+      Discard { discard: discard.clone(), keep, strengths, lows, equity: tally }
+    });
+
+    /* best first, with fewer cards thrown breaking ties */
+    discards.sort_by(|a, b| b.equity.equity.partial_cmp(&a.equity.equity).unwrap());
+    Some(Draw { discards })
+  }
+
+  /* return the discard with the best equity */
+  pub fn best(&self) -> &Discard
+  {
+    &self.discards[0]
+  }
+}
+
+impl Discard
+{
+  /* return a string describing the discard and how it plays out */
+  pub fn describe(&self) -> String
+  {
+    let thrown = match self.discard.len()
+    {
+      0 => "Stand pat".to_string(),
+      _ => format!("Discard {}", self.discard.iter().map(|c| c.describe()).collect::<Vec<String>>().join(" "))
+    };
+    let strengths = self.strengths.iter()
+                                  .map(|(strength, chance)| format!("{} {:.1}%", strength.name(), chance * 100.0))
+                                  .collect::<Vec<String>>();
+    let lows = self.lows.iter()
+                        .map(|(top, chance)| match top
+                        {
+                          Some(top) => format!("{} low {:.1}%", low::value_name(*top), chance * 100.0),
+                          None => format!("no low {:.1}%", chance * 100.0)
+                        })
+                        .collect::<Vec<String>>();
+    format!("{}: {}; {}", thrown, self.equity.describe(), [strengths, lows].concat().join(", "))
+  }
+}
+
+/* deal
+   Deal cards at random from a deck
+   => deck = cards to deal from, with the dealt cards taken out
+      count = number of cards to deal
+      rng = random number generator
+   <= cards dealt */
+fn deal(deck: &mut Vec<Card>, count: usize, rng: &mut Rng) -> Vec<Card>
+{
+  let mut cards = Vec::<Card>::with_capacity(count);
+  for _ in 0..count
+  {
+    let card = deck.swap_remove(rng.below(deck.len()));
+    cards.push(card);
+  }
+  cards
+}
+
+/* redraw
+   Take a draw after the first: stand pat on a made hand, otherwise hold on to the
+   best of the cards and draw the rest
+   => rules = game being played
+      hand = player's five cards, updated with the cards drawn
+      keep = number of cards to hold on to
+      deck = cards left to draw from
+      rng = random number generator */
+fn redraw(rules: &Rules, hand: &mut Vec<Card>, keep: usize, deck: &mut Vec<Card>, rng: &mut Rng)
+{
+  if made(rules, hand) || keep == CARDS
+  {
+    return;
+  }
+
+  let mut held = Vec::<Card>::new();
+  if keep > 0
+  {
+    let mut best = 0;
+    combinations(hand, keep, |cards|
+    {
+      let score = rules.score(cards, &[]);
+      if score > best || held.is_empty()
+      {
+        best = score;
+        held = cards.to_vec();
+      }
+    });
+  }
+
+  held.extend(deal(deck, CARDS - keep, rng));
+  *hand = held;
+}
+
+/* is a hand worth standing pat on: a straight or better for high, or no pair, straight
+   or flush for low? */
+fn made(rules: &Rules, hand: &[Card]) -> bool
+{
+  match rules.lowball
+  {
+    Some(lowball) => lowball.made(rules.score(hand, &[])),
+    None => !matches!(rules.hand(hand, &[]).strength(),
+                      Strength::HighCard | Strength::Pair | Strength::TwoPair | Strength::ThreeofaKind)
+  }
+}
+
+/* opponent_keep
+   Decide how many cards the opponent holds on to each draw: their paired cards, or
+   their highest card with no pair, playing for high, or one of each value eight or
+   lower playing for low, with aces low only in ace-to-five, and never all five
+   => rules = draw game rules
+      hand = opponent's five cards before the first draw
+   <= number of cards to hold */
+fn opponent_keep(rules: &Rules, hand: &[Card]) -> usize
+{
+  let value = |card: &Card| match (rules.lowball, card.value)
+  {
+    (Some(Lowball::AceToFive), Value::Ace) => 1,
+    _ => card.value.to_u32()
+  };
+  let held = match rules.lowball
+  {
+    Some(_) =>
+    {
+      let mut low: Vec<u32> = hand.iter().map(value).filter(|value| *value <= low::EIGHT_OR_BETTER).collect();
+      low.sort();
+      low.dedup();
+      low.len()
+    },
+    None =>
+    {
+      let paired = hand.iter().filter(|card| hand.iter().filter(|other| other.value == card.value).count() > 1).count();
+      paired.max(1)
+    }
+  };
+  held.min(CARDS - 1)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  #[test]
+  fn five_card_draw()
+  {
+    /* a made straight stands pat, and a four-card royal throws the odd card */
+    let draw = Draw::new(&Rules::draw(1, None), &cards("9h 8d 7c 6s 5h"), &[], None, 500, 1).unwrap();
+    assert!(draw.best().discard.is_empty());
+    assert_eq!(draw.best().strengths, vec![(Strength::Straight, 1.0)]);
+    assert!(draw.best().lows.is_empty());
+
+    let draw = Draw::new(&Rules::draw(1, None), &cards("ah kh qh jh 2c"), &[], None, 500, 1).unwrap();
+    assert_eq!(draw.best().discard, cards("2c"));
+    assert!(draw.discards[1].equity.equity < draw.best().equity.equity);
+  }
+
+  #[test]
+  fn deuce_to_seven_triple_draw()
+  {
+    let rules = Rules::draw(3, Some(Lowball::DeuceToSeven));
+
+    /* 8-8-4-3-2 breaks the pair and draws one to 8-4-3-2 */
+    let draw = Draw::new(&rules, &cards("8h 8d 2c 3s 4h"), &[], None, 500, 1).unwrap();
+    let best = draw.best();
+    assert_eq!(best.discard.len(), 1);
+    assert_eq!(best.discard[0].value, Value::Eight);
+    assert!(best.strengths.is_empty());
+    assert!((best.lows.iter().map(|(_, chance)| chance).sum::<f64>() - 1.0).abs() < 1e-9);
+    assert_eq!(best.lows[0].0, Some(8));
+
+    /* the number one stands pat on a seven low */
+    let draw = Draw::new(&rules, &cards("7h 5d 4c 3s 2h"), &[], None, 500, 1).unwrap();
+    assert!(draw.best().discard.is_empty());
+    assert_eq!(draw.best().lows, vec![(Some(7), 1.0)]);
+    assert!(draw.best().describe().ends_with("; seven low 100.0%"));
+  }
+}
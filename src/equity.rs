@@ -0,0 +1,571 @@
+/* equity.rs
+ * Work out each player's share of the pot, on average, by running through every way
+ * the rest of the board could fall: either for known hands, two or more ways, or for
+ * our hand against an opponent's range. Runs across all cores, see parallel.rs.
+ * Where that's too many runouts, equity can be estimated from a repeatable sample.
+ *
+ * In hi-lo games the pot is split between the best high hand and the best qualifying
+ * low, with each half split again between tied hands. If nobody qualifies for low,
+ * the high hand takes the whole pot.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use deck::Deck;
+use deck::combinations;
+use parallel;
+use range::Range;
+use rng::Rng;
+use rules::Rules;
+
+/* opponent holdings handed to each thread at a time */
+const COMBOS_PER_PIECE: usize = 16;
+
+/* sampled deals handed to each thread at a time */
+const SAMPLES_PER_PIECE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Equity
+{
+  pub win: f64,       /* chance of winning the whole pot: a scoop, in hi-lo games */
+  pub tie: f64,       /* chance of taking some, but not all, of the pot */
+  pub equity: f64,    /* average share of the pot, counting a split N ways as 1/N */
+
+  /* in hi-lo games, how the ties break down */
+  pub high_only: f64, /* chance of a share of the high half and none of the low half, including
+                         splitting a high-only pot when no low qualifies */
+  pub low_only: f64,  /* chance of winning the low half outright and none of the high half */
+  pub quartered: f64  /* chance of splitting either half with someone else when there's a low */
+}
+
+impl Equity
+{
+  /* create an empty tally */
+  pub(crate) fn new() -> Equity
+  {
+    Equity { win: 0.0, tie: 0.0, equity: 0.0, high_only: 0.0, low_only: 0.0, quartered: 0.0 }
+  }
+
+  /* add another tally to this one, scaled by a weight */
+  pub(crate) fn add(&mut self, other: &Equity, weight: f64)
+  {
+    self.win += other.win * weight;
+    self.tie += other.tie * weight;
+    self.equity += other.equity * weight;
+    self.high_only += other.high_only * weight;
+    self.low_only += other.low_only * weight;
+    self.quartered += other.quartered * weight;
+  }
+
+  /* turn a tally into chances by dividing through by the total */
+  pub(crate) fn scale(&mut self, total: f64)
+  {
+    self.win /= total;
+    self.tie /= total;
+    self.equity /= total;
+    self.high_only /= total;
+    self.low_only /= total;
+    self.quartered /= total;
+  }
+
+  /* return a string describing the equity */
+  pub fn describe(&self) -> String
+  {
+    format!("{:.2}% equity ({:.2}% win, {:.2}% tie)",
+            self.equity * 100.0, self.win * 100.0, self.tie * 100.0)
+  }
+
+  /* return a string describing the equity in a hi-lo game */
+  pub fn describe_hilo(&self) -> String
+  {
+    format!("{:.2}% equity ({:.2}% scoop, {:.2}% high only, {:.2}% low only, {:.2}% quartered)",
+            self.equity * 100.0, self.win * 100.0, self.high_only * 100.0,
+            self.low_only * 100.0, self.quartered * 100.0)
+  }
+}
+
+/* runouts
+   Run through every way the rest of the board could fall, in pieces that can be
+   handed to different threads, and add up a tally for each player
+   => unseen = cards that could still come
+      to_come = number of board cards still to come
+      players = number of players being tallied
+      tally = closure given a runout and the tallies to add to
+   <= tallies for each player, and the number of runouts */
+fn runouts<F>(unseen: &[Card], to_come: usize, players: usize, tally: F) -> (Vec<Equity>, usize)
+  where F: Fn(&[Card], &mut Vec<Equity>) + Sync
+{
+  if to_come > unseen.len()
+  {
+    return (vec![Equity::new(); players], 0);
+  }
+
+  /* split the runouts up by the first unseen card they use. on the river there's
+     just the one, empty, runout */
+  let pieces = if to_come == 0 { 1 } else { unseen.len() - to_come + 1 };
+  let results = parallel::map(pieces, |first|
+  {
+    let mut tallies = vec![Equity::new(); players];
+    let mut count = 0;
+    if to_come == 0
+    {
+      tally(&[], &mut tallies);
+      count = 1;
+    }
+    else
+    {
+      let mut runout = vec![unseen[first]];
+      combinations(&unseen[first + 1..], to_come - 1, |rest|
+      {
+        runout.truncate(1);
+        runout.extend_from_slice(rest);
+        tally(&runout, &mut tallies);
+        count += 1;
+      });
+    }
+    (tallies, count)
+  });
+
+  /* combine the pieces in order so the result doesn't depend on the thread count */
+  let mut totals = vec![Equity::new(); players];
+  let mut count = 0;
+  for (tallies, runouts) in results.iter()
+  {
+    for (total, piece) in totals.iter_mut().zip(tallies.iter())
+    {
+      total.add(piece, 1.0);
+    }
+    count += runouts;
+  }
+  (totals, count)
+}
+
+/* enumerate
+   Calculate each player's equity when all their hole cards are known
+   => rules = game being played
+      hands = each player's hole cards
+      board = community cards dealt so far, up to five
+      dead = other cards known not to be in the deck, eg, folded or burned
+   <= equity of each player in the order given, or None if no players were given,
+      the board is too big, or a card appears twice */
+pub fn enumerate(rules: &Rules, hands: &[Vec<Card>], board: &[Card], dead: &[Card])
+  -> Option<Vec<Equity>>
+{
+  if hands.is_empty() || board.len() > 5
+  {
+    return None;
+  }
+
+  let mut known = board.to_vec();
+  known.extend_from_slice(dead);
+  for hand in hands.iter()
+  {
+    known.extend_from_slice(hand);
+  }
+  if has_duplicates(&known)
+  {
+    return None;
+  }
+
+  let unseen = unseen(rules, &known);
+  let (mut tallies, count) = runouts(&unseen, 5 - board.len(), hands.len(), |runout, tallies|
+  {
+    let mut full = board.to_vec();
+    full.extend_from_slice(runout);
+    let players: Vec<&[Card]> = hands.iter().map(|hand| hand.as_slice()).collect();
+    award(rules, &players, &full, tallies);
+  });
+
+  if count == 0
+  {
+    return None;
+  }
+  for tally in tallies.iter_mut()
+  {
+    tally.scale(count as f64);
+  }
+  Some(tallies)
+}
+
+/* versus_range
+   Calculate our equity against an opponent's range
+   => rules = game being played
+      hole = our hole cards
+      board = community cards dealt so far, up to five. preflop this runs through
+              over a billion boards against a full range, so expect to wait
+      dead = other cards known not to be in the deck
+      range = opponent's possible holdings
+   <= our equity, or None if the board is too big, a card appears twice,
+      or the opponent's range has no holdings left in it */
+pub fn versus_range(rules: &Rules, hole: &[Card], board: &[Card], dead: &[Card], range: &Range)
+  -> Option<Equity>
+{
+  if board.len() > 5
+  {
+    return None;
+  }
+
+  let mut known = hole.to_vec();
+  known.extend_from_slice(board);
+  known.extend_from_slice(dead);
+  if has_duplicates(&known)
+  {
+    return None;
+  }
+
+  let opponents = range.live(&known);
+  if opponents.is_empty()
+  {
+    return None;
+  }
+
+  /* hand out the opponent's holdings a chunk at a time, and within each holding
+     run through every runout. each runout weighs the same, so a holding counts
+     in proportion to its weight */
+  let to_come = 5 - board.len();
+  let pieces = parallel::chunks(opponents.len(), COMBOS_PER_PIECE);
+  let results = parallel::map(pieces, |piece|
+  {
+    let mut tally = Equity::new();
+    let mut total = 0.0;
+    let first = piece * COMBOS_PER_PIECE;
+    let last = if first + COMBOS_PER_PIECE < opponents.len()
+               { first + COMBOS_PER_PIECE } else { opponents.len() };
+    for combo in opponents[first..last].iter()
+    {
+      let mut taken = known.clone();
+      taken.extend_from_slice(&combo.cards);
+      let unseen = unseen(rules, &taken);
+
+      let mut holding = Equity::new();
+      let mut count = 0;
+      let mut full = board.to_vec();
+      combinations(&unseen, to_come, |runout|
+      {
+        full.truncate(board.len());
+        full.extend_from_slice(runout);
+
+        let mut pair = [Equity::new(); 2];
+        award(rules, &[hole, &combo.cards], &full, &mut pair);
+        holding.add(&pair[0], 1.0);
+        count += 1;
+      });
+
+      if count > 0
+      {
+        holding.scale(count as f64);
+        tally.add(&holding, combo.weight);
+        total += combo.weight;
+      }
+    }
+    (tally, total)
+  });
+
+  let mut equity = Equity::new();
+  let mut total = 0.0;
+  for (tally, weight) in results.iter()
+  {
+    equity.add(tally, 1.0);
+    total += weight;
+  }
+
+  if total == 0.0
+  {
+    return None;
+  }
+  equity.scale(total);
+  Some(equity)
+}
+
+/* sample
+   Estimate our equity against an opponent's range from a sample of random deals, for
+   when there are too many to run through. The deals depend only on the seed, not on
+   the number of threads, so the same seed always gives the same answer
+   => rules = game being played
+      hole = our hole cards
+      board = community cards dealt so far, up to five
+      dead = other cards known not to be in the deck
+      range = opponent's possible holdings, picked in proportion to their weights
+      deals = number of random deals to sample
+      seed = starting point for the random numbers
+   <= our estimated equity, or None if the board is too big, a card appears twice,
+      or the opponent's range has no holdings left in it */
+pub fn sample(rules: &Rules, hole: &[Card], board: &[Card], dead: &[Card], range: &Range,
+              deals: usize, seed: u64) -> Option<Equity>
+{
+  if board.len() > 5 || deals == 0
+  {
+    return None;
+  }
+
+  let mut known = hole.to_vec();
+  known.extend_from_slice(board);
+  known.extend_from_slice(dead);
+  if has_duplicates(&known)
+  {
+    return None;
+  }
+
+  let opponents = range.live(&known);
+  if opponents.is_empty()
+  {
+    return None;
+  }
+
+  /* running totals of the weights, to pick holdings in proportion to them */
+  let mut weights = Vec::<f64>::with_capacity(opponents.len());
+  let mut total_weight = 0.0;
+  for combo in opponents.iter()
+  {
+    total_weight += combo.weight;
+    weights.push(total_weight);
+  }
+
+  let unseen = unseen(rules, &known);
+  let to_come = 5 - board.len();
+  let pieces = parallel::chunks(deals, SAMPLES_PER_PIECE);
+  let results = parallel::map(pieces, |piece|
+  {
+    /* each piece has its own generator, seeded from its number */
+    let mut rng = Rng::new(seed ^ (piece as u64).wrapping_mul(0x2545f4914f6cdd1d));
+    let first = piece * SAMPLES_PER_PIECE;
+    let last = if first + SAMPLES_PER_PIECE < deals { first + SAMPLES_PER_PIECE } else { deals };
+
+    let mut tally = Equity::new();
+    let mut count = 0;
+    let mut full = board.to_vec();
+    for _ in first..last
+    {
+      let target = rng.fraction() * total_weight;
+      let pick = weights.partition_point(|w| *w <= target);
+      let combo = opponents[if pick < opponents.len() { pick } else { opponents.len() - 1 }];
+
+      /* deal the rest of the board from what's left */
+      let mut deck: Vec<Card> = unseen.iter().filter(|c| !combo.cards.contains(c)).cloned().collect();
+      if deck.len() < to_come
+      {
+        continue;
+      }
+      full.truncate(board.len());
+      for _ in 0..to_come
+      {
+        let card = deck.swap_remove(rng.below(deck.len()));
+        full.push(card);
+      }
+
+      let mut pair = [Equity::new(); 2];
+      award(rules, &[hole, &combo.cards], &full, &mut pair);
+      tally.add(&pair[0], 1.0);
+      count += 1;
+    }
+    (tally, count)
+  });
+
+  let mut equity = Equity::new();
+  let mut count = 0;
+  for (tally, dealt) in results.iter()
+  {
+    equity.add(tally, 1.0);
+    count += dealt;
+  }
+
+  if count == 0
+  {
+    return None;
+  }
+  equity.scale(count as f64);
+  Some(equity)
+}
+
+/* pot_shares
+   Work out how a pot is split between players at showdown
+   => high = each player's high hand score
+      low = each player's qualifying low score in a hi-lo game, or None if they have none
+   <= each player's share of the pot, from 0.0 to 1.0 */
+pub fn pot_shares(high: &[u32], low: &[Option<u32>]) -> Vec<f64>
+{
+  let (high_shares, low_shares) = half_shares(high, low);
+  high_shares.iter().zip(low_shares.iter()).map(|(h, l)| h + l).collect()
+}
+
+/* split a pot into each player's shares of the high and low halves. without a
+   qualifying low, the high hand takes it all */
+fn half_shares(high: &[u32], low: &[Option<u32>]) -> (Vec<f64>, Vec<f64>)
+{
+  let best_low = low.iter().cloned().max().unwrap_or(None);
+  let high_pot = if best_low.is_some() { 0.5 } else { 1.0 };
+
+  let best_high = *high.iter().max().unwrap();
+  let high_winners = high.iter().filter(|s| **s == best_high).count();
+  let high_shares = high.iter()
+                        .map(|s| if *s == best_high { high_pot / high_winners as f64 } else { 0.0 })
+                        .collect();
+
+  let low_winners = low.iter().filter(|s| best_low.is_some() && **s == best_low).count();
+  let low_shares = low.iter()
+                      .map(|s| if best_low.is_some() && *s == best_low { 0.5 / low_winners as f64 }
+                               else { 0.0 })
+                      .collect();
+
+  (high_shares, low_shares)
+}
+
+/* award a pot between players at showdown, adding each player's result to their tally
+   => rules = game being played
+      hands = each player's hole cards, or all seven of their cards in stud
+      board = all five community cards, or none in stud
+      tallies = each player's tally */
+pub(crate) fn award(rules: &Rules, hands: &[&[Card]], board: &[Card], tallies: &mut [Equity])
+{
+  let high: Vec<u32> = hands.iter().map(|hand| rules.score(hand, board)).collect();
+  let low: Vec<Option<u32>> = hands.iter().map(|hand| rules.low_score(hand, board)).collect();
+  let (high_shares, low_shares) = half_shares(&high, &low);
+
+  /* with no qualifying low, a split pot is just a split high, not a quartering */
+  let high_winners = high_shares.iter().filter(|s| **s > 0.0).count();
+  let low_winners = low_shares.iter().filter(|s| **s > 0.0).count();
+  let shared = |high_share: f64, low_share: f64| low_winners > 0 &&
+                                                 ((high_share > 0.0 && high_winners > 1) ||
+                                                  (low_share > 0.0 && low_winners > 1));
+  for (i, tally) in tallies.iter_mut().enumerate()
+  {
+    let share = high_shares[i] + low_shares[i];
+    tally.equity += share;
+    if share >= 1.0
+    {
+      tally.win += 1.0;
+    }
+    else if share > 0.0
+    {
+      tally.tie += 1.0;
+      if rules.hilo
+      {
+        if shared(high_shares[i], low_shares[i])
+        {
+          tally.quartered += 1.0;
+        }
+        else if high_shares[i] > 0.0
+        {
+          tally.high_only += 1.0;
+        }
+        else
+        {
+          tally.low_only += 1.0;
+        }
+      }
+    }
+  }
+}
+
+/* scoops
+   Does one hand take the whole pot from another as the cards stand: the high, and in
+   hi-lo games the low too, unless neither of them has one
+   => rules = game being played
+      theirs = hole cards that might take the pot
+      ours = hole cards they're up against
+      board = community cards dealt so far
+   <= true if theirs wins it all */
+pub fn scoops(rules: &Rules, theirs: &[Card], ours: &[Card], board: &[Card]) -> bool
+{
+  let mut tallies = [Equity::new(); 2];
+  award(rules, &[theirs, ours], board, &mut tallies);
+  tallies[0].win > 0.0
+}
+
+/* list the cards left in the game's deck once the known cards are taken out */
+fn unseen(rules: &Rules, known: &[Card]) -> Vec<Card>
+{
+  let mut deck = Deck::with_rules(rules);
+  for card in known.iter()
+  {
+    deck.remove(card);
+  }
+  deck.cards().clone()
+}
+
+/* does the same card appear more than once? */
+pub fn has_duplicates(cards: &[Card]) -> bool
+{
+  for (i, card) in cards.iter().enumerate()
+  {
+    if cards[i + 1..].contains(card)
+    {
+      return true;
+    }
+  }
+  false
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  /* award one pot and return each player's tally */
+  fn showdown(hands: &[&str], board: &str) -> Vec<Equity>
+  {
+    let hands: Vec<Vec<Card>> = hands.iter().map(|hand| cards(hand)).collect();
+    let hands: Vec<&[Card]> = hands.iter().map(|hand| hand.as_slice()).collect();
+    let mut tallies = vec![Equity::new(); hands.len()];
+    award(&Rules::omaha_hilo(4), &hands, &cards(board), &mut tallies);
+    tallies
+  }
+
+  #[test]
+  fn split_high_without_a_low_is_not_quartered()
+  {
+    for tally in showdown(&["as ad 3c 4c", "ah ac 5d 6d"], "kh qd 9s tc 2h").iter()
+    {
+      assert_eq!((tally.equity, tally.tie, tally.high_only, tally.quartered), (0.5, 1.0, 1.0, 0.0));
+    }
+  }
+
+  #[test]
+  fn split_low_is_quartered()
+  {
+    let tallies = showdown(&["ac 4c 9h 9d", "ad 4d 9s 9c", "kh ks 8h 8s"], "2h 3d 7s kc qd");
+    for tally in tallies[0..2].iter()
+    {
+      assert_eq!((tally.equity, tally.quartered, tally.low_only), (0.25, 1.0, 0.0));
+    }
+    assert_eq!((tallies[2].equity, tallies[2].high_only, tallies[2].quartered), (0.5, 1.0, 0.0));
+  }
+
+  #[test]
+  fn split_equity_with_no_low_to_come()
+  {
+    /* no river can give a K-Q-T-9 turn three cards eight or lower, so both aces split
+       the high every time and nobody is ever quartered */
+    let hands = vec![cards("as ad 3c 4c"), cards("ah ac 5d 6d")];
+    let equities = enumerate(&Rules::omaha_hilo(4), &hands, &cards("kh qd 9s tc"), &[]).unwrap();
+    for equity in equities.iter()
+    {
+      assert_eq!((equity.equity, equity.win, equity.tie), (0.5, 0.0, 1.0));
+      assert_eq!((equity.high_only, equity.low_only, equity.quartered), (1.0, 0.0, 0.0));
+    }
+  }
+
+  #[test]
+  fn only_a_scoop_beats_us_in_hi_lo()
+  {
+    let rules = Rules::omaha_hilo(4);
+    let board = cards("2h 5d 7s kc qd");
+
+    /* their set of kings takes the high, but we keep the low half, or split it */
+    assert!(!scoops(&rules, &cards("kh ks 9c 9d"), &cards("ac 3d 8h 8s"), &board));
+    assert!(!scoops(&rules, &cards("kh ks ah 3h"), &cards("ac 3d 8h 8s"), &board));
+
+    /* unless their low is better too */
+    assert!(scoops(&rules, &cards("kh ks ah 4h"), &cards("ac 6d 8h 8s"), &board));
+
+    /* played for high only, the set is enough */
+    assert!(scoops(&Rules::omaha(4), &cards("kh ks 9c 9d"), &cards("ac 3d 8h 8s"), &board));
+    assert!(!scoops(&Rules::omaha(4), &cards("ac 3d 8h 8s"), &cards("kh ks 9c 9d"), &board));
+  }
+}
@@ -2,6 +2,9 @@
  * Store a hand as an object and calculate its strength from its five best cards.
  * A hand can be 2 or 5 to 7 cards in size, although only its best five matter.
  *
+ * In games with wild cards, each wild card stands in for whichever card makes the
+ * best hand, including five of a kind if the game allows it.
+ *
  * (c) Chris Williams, 2018. Open-source software: see LICENSE
  */
 
@@ -11,23 +14,25 @@ use card::Value;
 
 use std::collections::HashMap;
 use std::cmp::Ordering;
+use std::cmp::Reverse;
 
 /* describe number of cards with the same particular value are in the hand */
-struct ValueTotal
+pub(crate) struct ValueTotal
 {
-  value: Value,
-  total: usize
+  pub(crate) value: Value,
+  pub(crate) total: usize
 }
 
 /* describe number of cards with the same particular suit are in the hand */
-struct SuitTotal
+pub(crate) struct SuitTotal
 {
-  total: usize
+  pub(crate) total: usize
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Strength
 {
+  FiveofaKind, /* only with wild cards */
   RoyalFlush,
   StraightFlush,
   FourofaKind,
@@ -41,13 +46,46 @@ pub enum Strength
   Empty
 }
 
+impl Strength
+{
+  /* return the name of this type of hand */
+  pub fn name(self) -> &'static str
+  {
+    match self
+    {
+      Strength::FiveofaKind   => "five of a kind",
+      Strength::RoyalFlush    => "royal flush",
+      Strength::StraightFlush => "straight flush",
+      Strength::FourofaKind   => "four of a kind",
+      Strength::FullHouse     => "full house",
+      Strength::Flush         => "flush",
+      Strength::Straight      => "straight",
+      Strength::ThreeofaKind  => "three of a kind",
+      Strength::TwoPair       => "two pair",
+      Strength::Pair          => "pair",
+      Strength::HighCard      => "high card",
+      Strength::Empty         => "empty"
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct Hand
 {
   cards: Vec<Card>,    /* all the cards in this hand (2 to 7) */
   best: Vec<Value>,    /* all the relevant cards (2-5) to calculate the score */
   score: u32,          /* final score from best 5 cards */
-  strength: Strength   /* type of hand from best 5 cards */
+  strength: Strength,  /* type of hand from best 5 cards */
+  substitutes: Vec<(Card, Card)> /* each wild card, and the card it stands in for */
+}
+
+/* a hand with no cards yet */
+impl Default for Hand
+{
+  fn default() -> Hand
+  {
+    Hand::new()
+  }
 }
 
 impl Hand
@@ -60,7 +98,8 @@ impl Hand
       cards: Vec::<Card>::new(),
       best: Vec::<Value>::new(),
       score: 0,
-      strength: Strength::Empty
+      strength: Strength::Empty,
+      substitutes: Vec::<(Card, Card)>::new()
     }
   }
 
@@ -81,12 +120,28 @@ impl Hand
     self.score
   }
 
-  /* return string describing the hard */
+  /* return string describing the hand, and what any wild cards stand in for */
   pub fn describe(&self) -> String
+  {
+    let desc = self.describe_strength();
+    if self.substitutes.is_empty()
+    {
+      return desc;
+    }
+
+    let wilds = self.substitutes.iter()
+                                .map(|(wild, card)| format!("{} as {}", wild.describe(), card.describe()))
+                                .collect::<Vec<String>>();
+    format!("{} ({})", desc, wilds.join(", "))
+  }
+
+  /* return string describing the type of hand and its deciding values */
+  fn describe_strength(&self) -> String
   {
     match self.strength
     {
-      Strength::RoyalFlush    => format!("royal flush"),
+      Strength::FiveofaKind   => format!("five of a kind, {}s", self.best[0].to_str()),
+      Strength::RoyalFlush    => "royal flush".to_string(),
       Strength::StraightFlush => format!("straight flush, {} high", self.best[0].to_str()),
       Strength::FourofaKind   => format!("four of a kind, {}s", self.best[0].to_str()),
       Strength::FullHouse     => format!("full house, {}s over {}s",
@@ -100,13 +155,13 @@ impl Hand
                                          self.best[1].to_str()),
       Strength::Pair          => format!("pair of {}s", self.best[0].to_str()),
       Strength::HighCard      => format!("{} high", self.best[0].to_str()),
-      Strength::Empty         => format!("empty")
+      Strength::Empty         => "empty".to_string()
     }
   }
 
   /* count up number of times each suit appears in the hand, and return a list
      of the suits and their total, sorted by suit count descending */
-  fn count_suits(&mut self) -> Vec<SuitTotal>
+  pub(crate) fn count_suits(&self) -> Vec<SuitTotal>
   {
     let mut map = HashMap::<Suit, usize>::new();
     for card in self.cards.iter()
@@ -127,13 +182,13 @@ impl Hand
     {
       list.push(SuitTotal { total: *count });
     }
-    list.sort_by(|a, b| b.total.cmp(&a.total));
-    return list;
+    list.sort_by_key(|suit| Reverse(suit.total));
+    list
   }
 
   /* count up number of times each card value appears in the hand, and return a list
      of the values present and their totals, sorted by value count descending */
-  fn count_values(&mut self) -> Vec<ValueTotal>
+  pub(crate) fn count_values(&self) -> Vec<ValueTotal>
   {
     let mut map = HashMap::<Value, usize>::new();
     for card in self.cards.iter()
@@ -161,209 +216,787 @@ impl Hand
       Ordering::Equal => b.value.to_u32().cmp(&a.value.to_u32()),
       Ordering::Greater => Ordering::Greater
     });
-    return list;
+    list
+  }
+
+  /* return the type of hand from the best five cards, once calculated */
+  pub fn strength(&self) -> Strength
+  {
+    self.strength
+  }
+
+  /* return each wild card in the hand and the card it stands in for, once calculated */
+  pub fn substitutes(&self) -> &Vec<(Card, Card)>
+  {
+    &self.substitutes
+  }
+
+  /* return the values that decide the score, highest priority first, once calculated */
+  pub fn best(&self) -> &Vec<Value>
+  {
+    &self.best
   }
 
   /* work out the strength of the cards so far, and calculate a score for
      the hand */
   pub fn calc(&mut self)
   {
-    /* default to high card */
-    self.strength = Strength::HighCard;
+    self.calc_with(&Ranking::Standard);
+  }
 
+  /* calc_with
+     Work out the strength of the cards so far under a given hand ranking, such as
+     short deck's, and calculate a score for the hand
+     => ranking = order of hand types, and which straights count */
+  pub fn calc_with(&mut self, ranking: &Ranking)
+  {
     /* sort cards in order, highest to lowest, treating ace as high */
     self.cards.sort_by(|a, b| { b.value.to_u32().cmp(&a.value.to_u32()) });
 
-    /* count up number of instances of each card value in this hand into a
-       sorted list - high to low */
-    let values = self.count_values();
+    let (strength, best, count) = rank(&self.cards, ranking);
+    self.strength = strength;
+    self.best = best[0..count].to_vec();
+    self.score = score(strength, &best[0..count], ranking);
+    self.substitutes.clear();
+  }
+
+  /* calc_wild
+     Work out the strength of the cards so far in a game with wild cards, picking
+     the card each wild stands in for to make the best hand, and calculate a score
+     => ranking = order of hand types, and which straights count
+        wilds = which cards are wild */
+  pub fn calc_wild(&mut self, ranking: &Ranking, wilds: &Wilds)
+  {
+    let substitutes = substitute(&self.cards, ranking, wilds);
+    if substitutes.is_empty()
+    {
+      self.calc_with(ranking);
+      return;
+    }
+
+    /* sort cards in order, highest to lowest, treating ace as high and jokers higher still */
+    self.cards.sort_by(|a, b| { b.value.to_u32().cmp(&a.value.to_u32()) });
+
+    let cards = stand_in(&self.cards, &substitutes);
+    let (strength, best, count) = rank(&cards, ranking);
+    self.strength = strength;
+    self.best = best[0..count].to_vec();
+    self.score = score(strength, &best[0..count], ranking);
+    self.substitutes = substitutes;
+  }
+
+  /* score_cards
+     Calculate the score of a set of cards without building a Hand object. This
+     is for the analysis loops that compare millions of hands and only need the score
+     => cards = two to seven cards
+     <= score, identical to that of a Hand holding the same cards */
+  pub fn score_cards(cards: &[Card]) -> u32
+  {
+    Hand::score_cards_with(cards, &Ranking::Standard)
+  }
+
+  /* score_cards_with
+     Calculate the score of a set of cards under a given hand ranking without
+     building a Hand object
+     => cards = two to seven cards
+        ranking = order of hand types, and which straights count
+     <= score, identical to that of a Hand holding the same cards */
+  pub fn score_cards_with(cards: &[Card], ranking: &Ranking) -> u32
+  {
+    let (strength, best, count) = rank(cards, ranking);
+    score(strength, &best[0..count], ranking)
+  }
+
+  /* score_cards_wild
+     Calculate the score of a set of cards in a game with wild cards without
+     building a Hand object
+     => cards = two to seven cards
+        ranking = order of hand types, and which straights count
+        wilds = which cards are wild
+     <= score, identical to that of a Hand holding the same cards */
+  pub fn score_cards_wild(cards: &[Card], ranking: &Ranking, wilds: &Wilds) -> u32
+  {
+    let substitutes = substitute(cards, ranking, wilds);
+    if substitutes.is_empty()
+    {
+      return Hand::score_cards_with(cards, ranking);
+    }
+    Hand::score_cards_with(&stand_in(cards, &substitutes), ranking)
+  }
+}
+
+/* which cards are wild, in games played with them */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wilds
+{
+  pub jokers: usize,       /* jokers in the deck, up to two. jokers are always wild */
+  pub rank: Option<Value>, /* every card of this value is wild too, eg, deuces */
+  pub five_of_a_kind: bool /* wild cards can make five of a kind, the best hand of all */
+}
+
+impl Wilds
+{
+  /* create the rules for a game without wild cards */
+  pub fn none() -> Wilds
+  {
+    Wilds { jokers: 0, rank: None, five_of_a_kind: false }
+  }
+
+  /* are any cards wild in this game? */
+  pub fn any(&self) -> bool
+  {
+    self.jokers > 0 || self.rank.is_some()
+  }
+
+  /* is the given card wild in this game? */
+  pub fn is_wild(&self, card: &Card) -> bool
+  {
+    card.value == Value::Joker || Some(card.value) == self.rank
+  }
+}
+
+/* how hands are ranked against each other */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ranking
+{
+  /* the usual ranking, with A-2-3-4-5 as the lowest straight */
+  Standard,
+
+  /* short deck (6+) hold 'em: with the twos to fives gone, A-6-7-8-9 is the lowest
+     straight and flushes are harder to make, so they beat full houses. some games
+     also rank three of a kind above straights */
+  ShortDeck { trips_beat_straights: bool }
+}
+
+impl Ranking
+{
+  /* base
+     => strength = type of hand
+     <= rank of the hand type, from 0 (high card) to 9 (royal flush) */
+  fn base(&self, strength: Strength) -> u32
+  {
+    let trips_beat_straights = match *self
+    {
+      Ranking::ShortDeck { trips_beat_straights } => trips_beat_straights,
+      Ranking::Standard => false
+    };
+    let short_deck = *self != Ranking::Standard;
+
+    match strength
+    {
+      Strength::FiveofaKind   => 10,
+      Strength::RoyalFlush    => 9,
+      Strength::StraightFlush => 8,
+      Strength::FourofaKind   => 7,
+      Strength::FullHouse     => if short_deck { 5 } else { 6 },
+      Strength::Flush         => if short_deck { 6 } else { 5 },
+      Strength::Straight      => if trips_beat_straights { 3 } else { 4 },
+      Strength::ThreeofaKind  => if trips_beat_straights { 4 } else { 3 },
+      Strength::TwoPair       => 2,
+      Strength::Pair          => 1,
+      Strength::HighCard      => 0,
+      Strength::Empty         => 0
+    }
+  }
+
+  /* return the lowest value in the deck these hands are dealt from */
+  fn lowest_value(&self) -> u32
+  {
+    match *self
+    {
+      Ranking::Standard => 2,
+      Ranking::ShortDeck { .. } => 6
+    }
+  }
+
+  /* return the value an ace stands in for at the bottom of a straight */
+  fn low_ace(&self) -> u32
+  {
+    match *self
+    {
+      Ranking::Standard => 1,
+      Ranking::ShortDeck { .. } => 5
+    }
+  }
+}
+
+/* find the top card of the highest five-card straight in a value mask, if any. an ace
+   also counts at the bottom of the lowest straight: as a one in A-2-3-4-5, or as a
+   five in short deck's A-6-7-8-9 */
+fn straight_top(mask: u32, ranking: &Ranking) -> Option<u32>
+{
+  let mask = match mask & (1 << 14)
+  {
+    0 => mask,
+    _ => mask | (1 << ranking.low_ace())
+  };
+
+  for top in (5..15).rev()
+  {
+    let run = 0x1f << (top - 4);
+    if mask & run == run
+    {
+      return Some(top);
+    }
+  }
+  None
+}
+
+/* the deciding values picked out so far, highest priority first. kept on the stack
+   so ranking a hand doesn't touch the heap */
+struct Best
+{
+  values: [u32; 5],
+  count: usize
+}
+
+impl Best
+{
+  fn push(&mut self, value: u32)
+  {
+    self.values[self.count] = value;
+    self.count += 1;
+  }
+
+  fn len(&self) -> usize
+  {
+    self.count
+  }
+}
 
-    /* did we find four of a kind in top slot? */
-    if values.first().unwrap().total == 4
+/* every hand type, best first under the standard ranking */
+pub(crate) const STRENGTHS: [Strength; 11] =
+[
+  Strength::FiveofaKind, Strength::RoyalFlush, Strength::StraightFlush, Strength::FourofaKind, Strength::FullHouse,
+  Strength::Flush, Strength::Straight, Strength::ThreeofaKind, Strength::TwoPair,
+  Strength::Pair, Strength::HighCard
+];
+
+/* rank
+   Work out the type of hand the best five of the given cards make, and the card values
+   that separate it from other hands of the same type
+   => cards = up to seven cards
+      ranking = order of hand types, and which straights count
+   <= type of hand, the deciding values highest priority first, and how many values count.
+      these are the hand type's cards followed by the kickers, eg:
+      four of a kind:  XXXXY (X, Y = 2 values)
+      full house:      XXXYY (X, Y = 2 values)
+      three of a kind: XXXYZ (X, Y, Z = 3 values)
+      two pair:        XXYYZ (X, Y, Z = 3 values)
+      pair:            XXYZV (X, Y, Z, V = 4 values)
+      flush and high card take the 5 highest values, and straights just the top card */
+fn rank(cards: &[Card], ranking: &Ranking) -> (Strength, [Value; 5], usize)
+{
+  /* tally up each value, and which values appear in each suit */
+  let mut totals = [0usize; 15];
+  let mut suit_masks = [0u32; 4];
+  let mut mask = 0;
+  for card in cards.iter()
+  {
+    /* jokers should have been swapped for the cards they stand in for by now. any left
+       over don't count towards anything */
+    if card.value == Value::Joker
+    {
+      continue;
+    }
+    let value = card.value.to_u32();
+    totals[value as usize] += 1;
+    let suit = card.suit.to_u32() as usize;
+    suit_masks[suit] |= 1 << value;
+    mask |= 1 << value;
+  }
+
+  /* group the values by how many times they appear, highest value first. only
+     wild cards can make more than four of a value */
+  let mut groups = [[0u32; 7]; 6];
+  let mut group_sizes = [0usize; 6];
+  for value in (2..15).rev()
+  {
+    let total = if totals[value] > 5 { 5 } else { totals[value] };
+    if total > 0
+    {
+      groups[total][group_sizes[total]] = value as u32;
+      group_sizes[total] += 1;
+    }
+  }
+
+  /* find a flush, and any straights, which only count as straight flushes if
+     they're all one suit */
+  let mut flush_mask = None;
+  for suit_mask in suit_masks.iter()
+  {
+    if suit_mask.count_ones() >= 5
     {
-      self.strength = Strength::FourofaKind;
+      flush_mask = Some(*suit_mask);
     }
+  }
+  let straight = straight_top(mask, ranking);
+  let straight_flush = flush_mask.and_then(|suit_mask| straight_top(suit_mask, ranking));
+
+  /* a second set of trips can stand in as the pair of a full house */
+  let full_house_pair = if group_sizes[3] > 1 && (group_sizes[2] == 0 || groups[3][1] > groups[2][0])
+                        { groups[3][1] } else { groups[2][0] };
 
-    /* did we find three of a kind? */
-    if values.first().unwrap().total == 3
+  /* pick the best type of hand these cards make under this ranking */
+  let mut order = STRENGTHS;
+  order.sort_by_key(|strength| Reverse(ranking.base(*strength)));
+  let strength = *order.iter().find(|strength| match **strength
+  {
+    Strength::FiveofaKind   => group_sizes[5] > 0,
+    Strength::RoyalFlush    => straight_flush == Some(14),
+    Strength::StraightFlush => straight_flush.is_some(),
+    Strength::FourofaKind   => group_sizes[4] > 0,
+    Strength::FullHouse     => group_sizes[3] > 0 && (group_sizes[3] > 1 || group_sizes[2] > 0),
+    Strength::Flush         => flush_mask.is_some(),
+    Strength::Straight      => straight.is_some(),
+    Strength::ThreeofaKind  => group_sizes[3] > 0,
+    Strength::TwoPair       => group_sizes[2] > 1,
+    Strength::Pair          => group_sizes[2] > 0,
+    Strength::HighCard      => group_sizes[1] > 0,
+    Strength::Empty         => false
+  }).unwrap_or(&Strength::Empty);
+
+  /* pick the highest values present that haven't already been used */
+  let kickers = |used: &[u32], wanted: usize, best: &mut Best|
+  {
+    for value in (2..15).rev()
     {
-      /* is the value in the next slot a pair?
-         if so, that's a full house */
-      if values.len() > 1 && values[1].total == 2
+      if best.len() >= wanted
       {
-        self.strength = Strength::FullHouse;
+        break;
       }
-      /* if not, then we have a normal 3 of a kind */
-      else
+      if totals[value] > 0 && !used.contains(&(value as u32))
       {
-        self.strength = Strength::ThreeofaKind;
+        best.push(value as u32);
       }
     }
+  };
 
-    /* did we find a pair? */
-    if values.first().unwrap().total == 2
+  /* then pick out the values that decide between hands of that type */
+  let mut best = Best { values: [0; 5], count: 0 };
+  match strength
+  {
+    Strength::FiveofaKind => best.push(groups[5][0]),
+    Strength::RoyalFlush | Strength::StraightFlush => best.push(straight_flush.unwrap()),
+    Strength::FourofaKind =>
+    {
+      best.push(groups[4][0]);
+      kickers(&[groups[4][0]], 2, &mut best);
+    },
+    Strength::FullHouse =>
+    {
+      best.push(groups[3][0]);
+      best.push(full_house_pair);
+    },
+    Strength::Flush =>
     {
-      /* did we find another pair in next slot? */
-      if values.len() > 1 && values[1].total == 2
+      let suit_mask = flush_mask.unwrap();
+      for value in (2..15).rev()
       {
-        self.strength = Strength::TwoPair;
+        if best.len() < 5 && suit_mask & (1 << value) != 0
+        {
+          best.push(value);
+        }
       }
-      /* nope, just one pair */
-      else
+    },
+    Strength::Straight => best.push(straight.unwrap()),
+    Strength::ThreeofaKind =>
+    {
+      best.push(groups[3][0]);
+      kickers(&[groups[3][0]], 3, &mut best);
+    },
+    Strength::TwoPair =>
+    {
+      best.push(groups[2][0]);
+      best.push(groups[2][1]);
+      kickers(&[groups[2][0], groups[2][1]], 3, &mut best);
+    },
+    Strength::Pair =>
+    {
+      best.push(groups[2][0]);
+      kickers(&[groups[2][0]], 4, &mut best);
+    },
+    Strength::HighCard => kickers(&[], 5, &mut best),
+    Strength::Empty => ()
+  }
+
+  let mut values = [Value::Two; 5];
+  for (value, packed) in values.iter_mut().zip(best.values[0..best.len()].iter())
+  {
+    *value = Value::from_u32(*packed).unwrap();
+  }
+  (strength, values, best.len())
+}
+
+/* substitute
+   Pick the card each wild card should stand in for to make the best hand. For each
+   type of hand, the wild cards go towards the best one of that type the other cards
+   can make, with any left over as high kickers, and the best of these wins
+   => cards = cards in the hand
+      ranking = order of hand types, and which straights count
+      wilds = which cards are wild
+   <= each wild card in the hand, paired with the card it stands in for */
+fn substitute(cards: &[Card], ranking: &Ranking, wilds: &Wilds) -> Vec<(Card, Card)>
+{
+  let wild: Vec<Card> = cards.iter().filter(|card| wilds.is_wild(card)).cloned().collect();
+  if wild.is_empty()
+  {
+    return Vec::new();
+  }
+
+  let natural: Vec<Card> = cards.iter().filter(|card| !wilds.is_wild(card)).cloned().collect();
+  let mut counts = [0usize; 15];
+  let mut held = [[false; 15]; 4];
+  for card in natural.iter()
+  {
+    let value = card.value.to_u32() as usize;
+    counts[value] += 1;
+    held[card.suit.to_u32() as usize][value] = true;
+  }
+
+  /* wild cards can only stand in for cards that are in the deck */
+  let lowest = ranking.lowest_value() as usize;
+  let available = wild.len();
+  let card = |value: usize, suit: usize| Card { value: Value::from_u32(value as u32).unwrap(),
+                                                suit: Suit::from_u32(suit as u32).unwrap() };
+
+  /* a card of the given value that isn't already in the hand, if there is one */
+  let fresh = |value: usize, taken: &[Card]|
+  {
+    for (suit, values) in held.iter().enumerate()
+    {
+      let candidate = card(value, suit);
+      if !values[value] && !taken.contains(&candidate)
       {
-        self.strength = Strength::Pair;
+        return candidate;
       }
     }
+    card(value, 0)
+  };
+
+  let mut candidates = Vec::<Vec<Card>>::new();
 
-    /* detect five-card flush */
-    let suits = self.count_suits();
-    if suits.first().unwrap().total >= 5
+  /* the highest five, four or three of a kind, or pair, the wild cards can make */
+  for size in [5, 4, 3, 2].iter().cloned()
+  {
+    if size == 5 && !wilds.five_of_a_kind
+    {
+      continue;
+    }
+    if let Some(value) = (lowest..15).rev().find(|v| counts[*v] < size && counts[*v] + available >= size)
     {
-      self.strength = Strength::Flush;
+      let mut stand_ins = Vec::<Card>::new();
+      for _ in counts[value]..size
+      {
+        let next = fresh(value, &stand_ins);
+        stand_ins.push(next);
+      }
+      candidates.push(stand_ins);
     }
+  }
 
-    /* detect five-card straight, counting down ace to two (2) */
-    let mut prev_value = None;
-    let mut straight_count = 0;
-    for card in self.cards.iter()
+  /* the highest full house, as trips and a pair of different values */
+  'full_house: for trips in (lowest..15).rev()
+  {
+    for pair in (lowest..15).rev()
     {
-      /* inspect the current card to compare with previous value */
-      let value = card.value.to_u32();
-      match prev_value
+      let needed = 3 - counts[trips].min(3) + 2 - counts[pair].min(2);
+      if pair != trips && needed <= available
       {
-        Some(v) =>
+        let mut stand_ins = Vec::<Card>::new();
+        for (value, size) in [(trips, 3), (pair, 2)].iter().cloned()
         {
-          /* if it's a consecutively lower number then increase the count */
-          if value == (v - 1)
+          for _ in counts[value].min(size)..size
           {
-            straight_count = straight_count + 1;
+            let next = fresh(value, &stand_ins);
+            stand_ins.push(next);
           }
-          else
-          {
-            /* ..or start over, with this being the new first card in the straight */
-            straight_count = 1;
-          }
-
-          /* if the fourth card in a straight is a two (2) and the highest card
-             is an ace, then this is a wheel (low ace to five) so deal with this edge case */
-          if straight_count == 4 && card.value() == Value::Two &&
-             self.cards[0].value() == Value::Ace
-          {
-            straight_count = straight_count + 1;
-          }
-
-          /* stop right now if we've found five straight cards */
-          if straight_count == 5
-          {
-            break;
-          }
-        },
-
-        None => straight_count = straight_count + 1
+        }
+        candidates.push(stand_ins);
+        break 'full_house;
       }
-
-      prev_value = Some(value);
     }
+  }
 
-    /* handle straight, straight flushes, and royals */
-    if straight_count >= 5
+  /* the highest straight flush and flush in each suit, and the highest straight.
+     the cards in a straight run from top - 4 to top, with an ace at the bottom
+     of the lowest */
+  let run = |top: usize| (top - 4..top + 1).map(move |v| if v == ranking.low_ace() as usize { 14 } else { v });
+  for (suit, values) in held.iter().enumerate()
+  {
+    for top in (ranking.low_ace() as usize + 4..15).rev()
     {
-      if self.strength == Strength::Flush
+      let missing: Vec<usize> = run(top).filter(|v| !values[*v]).collect();
+      if missing.len() <= available
       {
-        if self.cards.first().unwrap().value() == Value::Ace
-        {
-          self.strength = Strength::RoyalFlush;
-        }
-        else
-        {
-          self.strength = Strength::StraightFlush;
-        }
+        candidates.push(missing.iter().map(|v| card(*v, suit)).collect());
+        break;
       }
-      else
+    }
+
+    let missing: Vec<usize> = (lowest..15).rev().filter(|v| !values[*v]).take(available).collect();
+    if natural.iter().filter(|c| c.suit.to_u32() as usize == suit).count() + missing.len() >= 5
+    {
+      candidates.push(missing.iter().map(|v| card(*v, suit)).collect());
+    }
+  }
+  for top in (ranking.low_ace() as usize + 4..15).rev()
+  {
+    let missing: Vec<usize> = run(top).filter(|v| counts[*v] == 0).collect();
+    if missing.len() <= available
+    {
+      let mut stand_ins = Vec::<Card>::new();
+      for value in missing.iter()
       {
-        self.strength = Strength::Straight;
+        let next = fresh(*value, &stand_ins);
+        stand_ins.push(next);
       }
+      candidates.push(stand_ins);
+      break;
     }
+  }
 
-    /* select the values of the best cards from the hand */
-    match self.strength
+  /* failing all that, the wild cards are simply high cards */
+  candidates.push(Vec::new());
+
+  /* any wild cards left over become the highest cards not already in the hand */
+  let mut best = (0, Vec::<Card>::new());
+  for mut stand_ins in candidates.into_iter()
+  {
+    stand_ins.truncate(available);
+    for value in (lowest..15).rev()
     {
-      /* take the highest card's value, there is no
-         kicker or tie card for these made five-card hands */
-      Strength::RoyalFlush | Strength::StraightFlush | Strength::Flush | Strength::Straight =>
-      {
-        let value = self.cards[0].value();
-        self.best.push(value);
-      },
-
-      /* take 2 or more cards from the hand, depending on type.
-         four of a kind:  XXXXY (X, Y = 2 cards)
-         full house:      XXXYY (X, Y = 2 cards)
-         three of a kind: XXXYZ (X, Y, Z = 3 cards)
-         two pair:        XXYYZ (X, Y, Z = 3 cards)
-         pair:            XXYZV (X, Y, Z, V = 4 cards)
-         high card takes 5 highest cards */
-      _ =>
+      if stand_ins.len() < available && counts[value] == 0 &&
+         !stand_ins.iter().any(|c| c.value.to_u32() as usize == value)
       {
-        let mut select = match self.strength
-        {
-          Strength::FourofaKind  => 2,
-          Strength::FullHouse    => 2,
-          Strength::ThreeofaKind => 3,
-          Strength::TwoPair      => 3,
-          Strength::Pair         => 4,
-          Strength::HighCard     => 5,
-          _ => unreachable!()
-        };
-
-        /* cap select if there aren't enough cards to take */
-        if select > values.len()
-        {
-          select = values.len();
-        }
-
-        /* now select those cards for the best pile */
-        for i in 0..select
-        {
-          self.best.push(values[i].value);
-        }
+        let next = fresh(value, &stand_ins);
+        stand_ins.push(next);
       }
     }
+    while stand_ins.len() < available
+    {
+      let next = fresh(14, &stand_ins);
+      stand_ins.push(next);
+    }
 
-    /* here's how we score each hand from its cards.
-       each card value runs from 2 (two) to 14 (ace).
-       that range fits neatly in four bits. so use the lower bits to
-       compute the per-hand score, and high bits for the per-type base.
+    let mut all = natural.clone();
+    all.extend_from_slice(&stand_ins);
+    let score = Hand::score_cards_with(&all, ranking);
+    if score > best.0 || best.1.is_empty()
+    {
+      best = (score, stand_ins);
+    }
+  }
+
+  wild.into_iter().zip(best.1).collect()
+}
 
-       bbbb xxxx      xxxx      xxxx      xxxx      xxxx      <-- bits 0 to 23
-            1st best  2nd best  3rd best  4th best  5th best  <-- cards
+/* return the cards in a hand with each wild card swapped for the card it stands in for */
+fn stand_in(cards: &[Card], substitutes: &[(Card, Card)]) -> Vec<Card>
+{
+  cards.iter()
+       .map(|card| match substitutes.iter().find(|(wild, _)| wild == card)
+       {
+         Some((_, stand_in)) => *stand_in,
+         None => *card
+       })
+       .collect()
+}
+
+/* here's how we score each hand from its cards.
+   each card value runs from 2 (two) to 14 (ace).
+   that range fits neatly in four bits. so use the lower bits to
+   compute the per-hand score, and high bits for the per-type base.
+
+   bbbb xxxx      xxxx      xxxx      xxxx      xxxx      <-- bits 0 to 23
+        1st best  2nd best  3rd best  4th best  5th best  <-- cards
+
+   the bbbb bits (bits 20-23) select the hand type (0-9), in the order
+   set by the ranking */
+fn score(strength: Strength, best: &[Value], ranking: &Ranking) -> u32
+{
+  if strength == Strength::Empty
+  {
+    return 0;
+  }
 
-       the bbbb bits (bits 20-23) select the hand type (0-9) */
+  /* compute base score from type */
+  let base_score = ranking.base(strength) << 20;
 
-    /* compute base score from type */
-    let base_score = match self.strength
+  let mut value_score = 0;
+  let mut value_position = 5;
+  for value in best.iter()
+  {
+    value_position -= 1;
+    value_score += value.to_u32() << (value_position * 4);
+  }
+
+  base_score + value_score
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  const SHORT_DECK: Ranking = Ranking::ShortDeck { trips_beat_straights: false };
+
+  /* rank a hand written as card descriptions, eg: ah kd 7c 7s 2h */
+  fn ranked(text: &str, ranking: &Ranking) -> Hand
+  {
+    let mut hand = Hand::new();
+    for desc in text.split_whitespace()
     {
-      Strength::RoyalFlush    => 9,
-      Strength::StraightFlush => 8,
-      Strength::FourofaKind   => 7,
-      Strength::FullHouse     => 6,
-      Strength::Flush         => 5,
-      Strength::Straight      => 4,
-      Strength::ThreeofaKind  => 3,
-      Strength::TwoPair       => 2,
-      Strength::Pair          => 1,
-      Strength::HighCard      => 0,
-      _ => unreachable!()
-    } << 20;
+      hand.add(Card::new(desc).unwrap());
+    }
+    hand.calc_with(ranking);
+    hand
+  }
 
-    let mut value_score = 0;
-    let mut value_position = 5;
-    for value in self.best.iter()
+  /* check each hand has the given type and scores below the one before it */
+  fn assert_descending(hands: &[(&str, Strength)], ranking: &Ranking)
+  {
+    let mut above = u32::MAX;
+    for (text, strength) in hands.iter()
     {
-      value_position = value_position - 1;
-      value_score = value_score + (value.to_u32() << (value_position * 4));
+      let hand = ranked(text, ranking);
+      assert_eq!(hand.strength(), *strength, "{}", text);
+      assert!(hand.score() < above, "{} should rank below the hand before it", text);
+      above = hand.score();
     }
+  }
 
-    self.score = base_score + value_score;
+  #[test]
+  fn hand_types_rank_in_order()
+  {
+    assert_descending(&[("ah kh qh jh th 2c 3d", Strength::RoyalFlush),
+                        ("9s 8s 7s 6s 5s ah ad", Strength::StraightFlush),
+                        ("2c 2d 2h 2s 3c 4d 6h", Strength::FourofaKind),
+                        ("ac ad ah kc kd 2s 3s", Strength::FullHouse),
+                        ("ad jd 9d 6d 4d kc ks", Strength::Flush),
+                        ("tc 9d 8h 7s 6c ac ad", Strength::Straight),
+                        ("qc qd qh 2s 3s 5c 7d", Strength::ThreeofaKind),
+                        ("kc kd 3h 3s 2c 5d 7h", Strength::TwoPair),
+                        ("ac ad 2h 4s 6c 8d th", Strength::Pair),
+                        ("ac kd jh 9s 7c 5d 4h", Strength::HighCard)], &Ranking::Standard);
+  }
+
+  #[test]
+  fn ties_break_on_the_deciding_cards()
+  {
+    /* each pair of hands differs only in what should separate them */
+    let pairs = [("9c 9d 9h 9s ah", "9c 9d 9h 9s kh"),  /* quads kicker */
+                 ("4c 4d 4h 2s 2c", "3c 3d 3h as ac"),  /* full house trips first */
+                 ("kc kd kh 3s 3c", "kc kd kh 2s 2c"),  /* then the pair */
+                 ("ah th 8h 6h 5h", "ah th 8h 6h 4h"),  /* flush down to the fifth card */
+                 ("tc 9d 8h 7s 6c", "9d 8h 7s 6c 5c"),  /* straight top card */
+                 ("7c 7d 7h ks 2c", "7c 7d 7h qs jc"),  /* trips first kicker */
+                 ("jc jd 4h 4s 2c", "jc jd 3h 3s ac"),  /* two pair second pair */
+                 ("jc jd 4h 4s 3c", "jc jd 4h 4s 2c"),  /* then the kicker */
+                 ("8c 8d ah 6s 4c", "8c 8d ah 6s 3c"),  /* pair third kicker */
+                 ("ac kd jh 9s 3c", "ac kd jh 9s 2c")]; /* high card fifth card */
+    for (better, worse) in pairs.iter()
+    {
+      assert!(ranked(better, &Ranking::Standard).score() > ranked(worse, &Ranking::Standard).score(),
+              "{} should beat {}", better, worse);
+    }
+
+    /* suits never break a tie, and cards beyond the best five don't count */
+    assert_eq!(ranked("ac kd jh 9s 3c", &Ranking::Standard).score(),
+               ranked("as kc jd 9h 3s", &Ranking::Standard).score());
+    assert_eq!(ranked("qc qd 8h 6s 4c 3d 2h", &Ranking::Standard).score(),
+               ranked("qh qs 8c 6d 4h 2s", &Ranking::Standard).score());
+  }
+
+  #[test]
+  fn wheel_is_the_lowest_straight()
+  {
+    let wheel = ranked("ac 2d 3h 4s 5c kd kh", &Ranking::Standard);
+    assert_eq!(wheel.strength(), Strength::Straight);
+    assert_eq!(wheel.best()[0], Value::Five);
+    assert!(wheel.score() < ranked("2d 3h 4s 5c 6d", &Ranking::Standard).score());
+    assert!(wheel.score() > ranked("kc kd kh 5s 2c", &Ranking::Standard).score());
+  }
+
+  #[test]
+  fn steel_wheel_is_the_lowest_straight_flush()
+  {
+    let steel_wheel = ranked("ah 2h 3h 4h 5h", &Ranking::Standard);
+    assert_eq!(steel_wheel.strength(), Strength::StraightFlush);
+    assert_eq!(steel_wheel.best()[0], Value::Five);
+    assert!(steel_wheel.score() < ranked("2h 3h 4h 5h 6h", &Ranking::Standard).score());
+    assert!(steel_wheel.score() > ranked("ac ad ah as kc", &Ranking::Standard).score());
+  }
+
+  #[test]
+  fn short_deck_ranks_flushes_over_full_houses()
+  {
+    assert_descending(&[("ac ad ah as kc", Strength::FourofaKind),
+                        ("kh jh 9h 7h 6h", Strength::Flush),
+                        ("ac ad ah kc kd", Strength::FullHouse),
+                        ("tc 9d 8h 7s 6c", Strength::Straight),
+                        ("ac 6d 7h 8s 9c", Strength::Straight),
+                        ("qc qd qh 7s 6c", Strength::ThreeofaKind)], &SHORT_DECK);
+
+    /* the ace plays low under the six, so A-6-7-8-9 is nine high */
+    assert_eq!(ranked("ac 6d 7h 8s 9c", &SHORT_DECK).best()[0], Value::Nine);
+
+    /* some games put three of a kind above straights too */
+    let trips_beat_straights = Ranking::ShortDeck { trips_beat_straights: true };
+    assert!(ranked("6c 6d 6h 8s 9c", &trips_beat_straights).score() >
+            ranked("ac kd qh js tc", &trips_beat_straights).score());
+  }
+
+  /* rank a hand with wild cards, written as card descriptions, eg: xh ah kh qh jh */
+  fn wild(text: &str, wilds: &Wilds) -> Hand
+  {
+    let mut hand = Hand::new();
+    for desc in text.split_whitespace()
+    {
+      hand.add(Card::new(desc).unwrap());
+    }
+    hand.calc_wild(&Ranking::Standard, wilds);
+    hand
+  }
+
+  #[test]
+  fn joker_completes_a_royal_flush()
+  {
+    let jokers = Wilds { jokers: 1, rank: None, five_of_a_kind: false };
+    let royal = wild("xh ah kh qh jh 2c 3d", &jokers);
+    assert_eq!(royal.strength(), Strength::RoyalFlush);
+    assert_eq!(royal.substitutes(), &vec![(Card::new("xh").unwrap(), Card::new("th").unwrap())]);
+    assert_eq!(royal.describe(), "royal flush (X\u{2665} as T\u{2665})");
+    assert_eq!(royal.score(), ranked("ah kh qh jh th 2c 3d", &Ranking::Standard).score());
+  }
+
+  #[test]
+  fn five_of_a_kind_only_when_allowed()
+  {
+    let five = Wilds { jokers: 2, rank: None, five_of_a_kind: true };
+    let hand = wild("xh xs as ad ac", &five);
+    assert_eq!(hand.strength(), Strength::FiveofaKind);
+    assert_eq!(hand.describe_strength(), "five of a kind, aces");
+    assert!(hand.score() > wild("xh ah kh qh jh", &five).score());
+
+    /* without it, the spare wild card makes the best kicker it can */
+    let four = Wilds { jokers: 2, rank: None, five_of_a_kind: false };
+    let hand = wild("xh xs as ad ac", &four);
+    assert_eq!(hand.strength(), Strength::FourofaKind);
+    assert!(hand.score() < wild("xh kh qh jh th", &four).score());
+  }
+
+  #[test]
+  fn deuces_wild()
+  {
+    let deuces = Wilds { jokers: 0, rank: Some(Value::Two), five_of_a_kind: false };
+    assert_eq!(wild("2c 2d 9h 9s 5c", &deuces).strength(), Strength::FourofaKind);
+    assert_eq!(wild("2c kh qh jh th", &deuces).strength(), Strength::RoyalFlush);
+    assert_eq!(wild("2c 2d 6h 7s 9c", &deuces).strength(), Strength::Straight);
+
+    /* a lone deuce pairs the top card, and four of them fill a royal flush around a king */
+    let hand = wild("2c kd 9h 7s 4c", &deuces);
+    assert_eq!((hand.strength(), hand.best()[0]), (Strength::Pair, Value::King));
+    assert_eq!(wild("2c 2d 2h 2s kc", &deuces).strength(), Strength::RoyalFlush);
   }
 }
@@ -0,0 +1,413 @@
+/* histogram.rs
+ * Calculate how a hold 'em hand's equity is spread over all the ways the rest of the
+ * board could fall, rather than boiling it down to one number, and cluster hands with
+ * similar spreads into buckets using k-means and the earth mover's distance.
+ *
+ * For example, a flush draw and a weak made hand might both have 40% equity on the flop,
+ * but the draw's equity ends up near 0% or 100% by the river while the made hand's
+ * stays in the middle. Their histograms show the difference.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use deck::Deck;
+use deck::combinations;
+use hand::Hand;
+use json::Json;
+use parallel;
+use range::Range;
+
+/* widest bar drawn by ascii() */
+const BAR_WIDTH: usize = 40;
+
+/* runouts handed to each thread at a time */
+const RUNOUTS_PER_PIECE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram
+{
+  bins: Vec<f64>, /* fraction of runouts with equity in each bin, lowest equity first */
+  equity: f64     /* mean equity over all runouts */
+}
+
+impl Histogram
+{
+  /* new
+     Calculate the distribution of our equity at the river over every turn and river
+     still to come, against an opponent's range
+     => hole = our two hole cards
+        board = three to five community cards
+        dead = other cards known to be out of play, which neither the opponent
+               nor the rest of the board can hold
+        range = opponent's possible holdings
+        bins = number of equal-width equity bins between 0% and 100%
+     <= Histogram object, or None if the board isn't three to five cards, there are
+        no bins, or the opponent's range has no holdings left in it */
+  pub fn new(hole: &[Card], board: &[Card], dead: &[Card], range: &Range, bins: usize)
+    -> Option<Histogram>
+  {
+    if board.len() < 3 || board.len() > 5 || bins == 0
+    {
+      return None;
+    }
+
+    let mut known = hole.to_vec();
+    known.extend_from_slice(board);
+    let mut out = known.clone();
+    out.extend_from_slice(dead);
+    let opponents = range.live(&out);
+    if opponents.is_empty()
+    {
+      return None;
+    }
+
+    let mut deck = Deck::new();
+    deck.remove_all(&out);
+
+    let mut runouts = Vec::<Vec<Card>>::new();
+    combinations(deck.cards(), 5 - board.len(), |runout| runouts.push(runout.to_vec()));
+
+    /* work out the equity on each runout, a chunk of runouts at a time across the threads */
+    let pieces = parallel::chunks(runouts.len(), RUNOUTS_PER_PIECE);
+    let results = parallel::map(pieces, |piece|
+    {
+      let mut tally = Histogram { bins: vec![0.0; bins], equity: 0.0 };
+      let mut count = 0;
+      let mut ours = known.clone();
+      let mut theirs = Vec::<Card>::with_capacity(7);
+      let first = piece * RUNOUTS_PER_PIECE;
+      let last = if first + RUNOUTS_PER_PIECE < runouts.len()
+                 { first + RUNOUTS_PER_PIECE } else { runouts.len() };
+      for runout in runouts[first..last].iter()
+      {
+        ours.truncate(known.len());
+        ours.extend_from_slice(runout);
+        let our_score = Hand::score_cards(&ours);
+
+        /* equity against the holdings the opponent could still have on this runout */
+        let mut won = 0.0;
+        let mut total = 0.0;
+        for combo in opponents.iter()
+        {
+          if runout.iter().any(|card| combo.cards.contains(card))
+          {
+            continue;
+          }
+
+          theirs.clear();
+          theirs.extend_from_slice(&combo.cards);
+          theirs.extend_from_slice(board);
+          theirs.extend_from_slice(runout);
+          let their_score = Hand::score_cards(&theirs);
+          if our_score > their_score
+          {
+            won += combo.weight;
+          }
+          else if our_score == their_score
+          {
+            won += combo.weight / 2.0;
+          }
+          total += combo.weight;
+        }
+
+        if total > 0.0
+        {
+          let equity = won / total;
+          let bin = bin_of(equity, bins);
+          tally.bins[bin] += 1.0;
+          tally.equity += equity;
+          count += 1;
+        }
+      }
+      (tally, count)
+    });
+
+    /* combine the chunks in order, so the result doesn't depend on the thread count */
+    let mut histogram = Histogram { bins: vec![0.0; bins], equity: 0.0 };
+    let mut runouts = 0;
+    for (tally, count) in results.iter()
+    {
+      for (total, fraction) in histogram.bins.iter_mut().zip(tally.bins.iter())
+      {
+        *total += fraction;
+      }
+      histogram.equity += tally.equity;
+      runouts += count;
+    }
+
+    if runouts == 0
+    {
+      return None;
+    }
+
+    for bin in histogram.bins.iter_mut()
+    {
+      *bin /= runouts as f64;
+    }
+    histogram.equity /= runouts as f64;
+    Some(histogram)
+  }
+
+  /* return the fraction of runouts in each bin, lowest equity first */
+  pub fn bins(&self) -> &Vec<f64>
+  {
+    &self.bins
+  }
+
+  /* return the mean equity over all runouts */
+  pub fn equity(&self) -> f64
+  {
+    self.equity
+  }
+
+  /* emd
+     Calculate the earth mover's distance between two histograms: how much of one
+     has to be shifted, and how far, to turn it into the other
+     => other = histogram with the same number of bins
+     <= distance from 0.0 (identical) to 1.0 (all at 0% versus all at 100%) */
+  pub fn emd(&self, other: &Histogram) -> f64
+  {
+    /* in one dimension this is the area between the two cumulative distributions */
+    let mut ours = 0.0;
+    let mut theirs = 0.0;
+    let mut distance = 0.0;
+    for (a, b) in self.bins.iter().zip(other.bins.iter())
+    {
+      ours += a;
+      theirs += b;
+      distance += (ours - theirs).abs();
+    }
+
+    if self.bins.len() > 1
+    {
+      distance /= (self.bins.len() - 1) as f64;
+    }
+    distance
+  }
+
+  /* return the histogram drawn as text, one line per bin */
+  pub fn ascii(&self) -> String
+  {
+    let bins = self.bins.len();
+    let tallest = self.bins.iter().cloned().fold(0.0, f64::max);
+    let mut text = String::new();
+    for (i, fraction) in self.bins.iter().enumerate()
+    {
+      let width = match tallest > 0.0
+      {
+        true => (fraction / tallest * BAR_WIDTH as f64).round() as usize,
+        false => 0
+      };
+      text.push_str(&format!("{:>3}-{:>3}% |{:<width$}| {:5.1}%\n",
+                             i * 100 / bins, (i + 1) * 100 / bins,
+                             "#".repeat(width), fraction * 100.0, width = BAR_WIDTH));
+    }
+    text
+  }
+
+  /* return the histogram as a JSON object */
+  pub fn to_json(&self) -> Json
+  {
+    Json::object(vec![
+      ("equity", Json::Number(self.equity)),
+      ("bins", Json::Array(self.bins.iter().map(|b| Json::Number(*b)).collect()))
+    ])
+  }
+
+  /* work out the mean of a set of histograms, bin by bin, each counting as much as its weight */
+  fn mean(histograms: &[(&Histogram, f64)], bins: usize) -> Histogram
+  {
+    let mut mean = Histogram { bins: vec![0.0; bins], equity: 0.0 };
+    let mut total_weight = 0.0;
+    for (histogram, weight) in histograms.iter()
+    {
+      for (total, fraction) in mean.bins.iter_mut().zip(histogram.bins.iter())
+      {
+        *total += fraction * weight;
+      }
+      mean.equity += histogram.equity * weight;
+      total_weight += weight;
+    }
+
+    for total in mean.bins.iter_mut()
+    {
+      *total /= total_weight;
+    }
+    mean.equity /= total_weight;
+    mean
+  }
+}
+
+/* the result of clustering histograms into buckets */
+#[derive(Debug, Clone)]
+pub struct Buckets
+{
+  pub assignments: Vec<usize>,  /* bucket of each histogram, in the order given */
+  pub centroids: Vec<Histogram> /* center of each bucket, weakest mean equity first */
+}
+
+impl Buckets
+{
+  /* new
+     Cluster histograms into buckets with k-means, measuring the distance between them
+     with the earth mover's distance. Each histogram can stand for a number of hands,
+     and counts that many times over. The starting centers are picked deterministically,
+     beginning with the weakest hand and repeatedly adding the hand that costs the most
+     to leave where it is, its weight times its distance from all the centers so far,
+     so the same input always gives the same buckets
+     => histograms = histograms to cluster, all with the same number of bins
+        weights = how many hands each histogram stands for
+        buckets = number of buckets wanted
+        iterations = most rounds of k-means to run before settling for the result
+     <= Buckets object. there are fewer buckets than requested if there are fewer
+        distinct histograms */
+  pub fn new(histograms: &[Histogram], weights: &[f64], buckets: usize, iterations: usize) -> Buckets
+  {
+    if histograms.is_empty() || buckets == 0
+    {
+      return Buckets { assignments: vec![0; histograms.len()], centroids: Vec::new() };
+    }
+    let bins = histograms[0].bins.len();
+
+    /* pick the starting centers */
+    let mut weakest = 0;
+    for (i, histogram) in histograms.iter().enumerate()
+    {
+      if histogram.equity < histograms[weakest].equity
+      {
+        weakest = i;
+      }
+    }
+    let mut centroids = vec![histograms[weakest].clone()];
+    while centroids.len() < buckets
+    {
+      let mut furthest = None;
+      let mut furthest_distance = 0.0;
+      for (i, (histogram, weight)) in histograms.iter().zip(weights.iter()).enumerate()
+      {
+        let distance = nearest(histogram, &centroids).1 * weight;
+        if distance > furthest_distance
+        {
+          furthest = Some(i);
+          furthest_distance = distance;
+        }
+      }
+
+      match furthest
+      {
+        Some(i) => centroids.push(histograms[i].clone()),
+        None => break /* everything left sits on a center already */
+      }
+    }
+
+    /* then move the centers until the buckets settle */
+    let mut assignments = vec![usize::MAX; histograms.len()];
+    for _ in 0..iterations
+    {
+      let mut changed = false;
+      for (i, histogram) in histograms.iter().enumerate()
+      {
+        let bucket = nearest(histogram, &centroids).0;
+        if assignments[i] != bucket
+        {
+          assignments[i] = bucket;
+          changed = true;
+        }
+      }
+
+      if !changed
+      {
+        break;
+      }
+
+      for (bucket, centroid) in centroids.iter_mut().enumerate()
+      {
+        let members: Vec<(&Histogram, f64)> = histograms.iter()
+                                                        .zip(weights.iter())
+                                                        .zip(assignments.iter())
+                                                        .filter(|&(_, a)| *a == bucket)
+                                                        .map(|((h, w), _)| (h, *w))
+                                                        .collect();
+        if !members.is_empty()
+        {
+          *centroid = Histogram::mean(&members, bins);
+        }
+      }
+    }
+
+    /* finally, number the buckets from weakest to strongest */
+    let mut order: Vec<usize> = (0..centroids.len()).collect();
+    order.sort_by(|a, b| centroids[*a].equity.partial_cmp(&centroids[*b].equity).unwrap());
+    let mut renumber = vec![0; centroids.len()];
+    for (new, old) in order.iter().enumerate()
+    {
+      renumber[*old] = new;
+    }
+
+    Buckets
+    {
+      assignments: assignments.iter().map(|a| renumber[*a]).collect(),
+      centroids: order.iter().map(|old| centroids[*old].clone()).collect()
+    }
+  }
+
+  /* return the buckets as a JSON array of their centers */
+  pub fn to_json(&self) -> Json
+  {
+    Json::Array(self.centroids.iter().map(|c| c.to_json()).collect())
+  }
+}
+
+/* work out which bin an equity falls into, putting 100% in the top bin */
+fn bin_of(equity: f64, bins: usize) -> usize
+{
+  let bin = (equity * bins as f64) as usize;
+  if bin >= bins { bins - 1 } else { bin }
+}
+
+/* find the closest center to a histogram
+   <= position of the center in the list, and its distance */
+fn nearest(histogram: &Histogram, centroids: &[Histogram]) -> (usize, f64)
+{
+  let mut best = (0, f64::MAX);
+  for (i, centroid) in centroids.iter().enumerate()
+  {
+    let distance = histogram.emd(centroid);
+    if distance < best.1
+    {
+      best = (i, distance);
+    }
+  }
+  best
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  /* a histogram with all its runouts in one bin */
+  fn spike(bin: usize, bins: usize) -> Histogram
+  {
+    let mut histogram = Histogram { bins: vec![0.0; bins], equity: bin as f64 / (bins - 1) as f64 };
+    histogram.bins[bin] = 1.0;
+    histogram
+  }
+
+  #[test]
+  fn weights_decide_the_buckets()
+  {
+    /* the middle hand is as far from the weakest as the strongest is from it, so on
+       its own it joins the weakest. standing for ten times the hands, it gets a bucket
+       of its own first, and pulls the strongest in with it */
+    let histograms = vec![spike(0, 11), spike(5, 11), spike(10, 11)];
+    let even = Buckets::new(&histograms, &[1.0, 1.0, 1.0], 2, 10);
+    assert_eq!(even.assignments, vec![0, 0, 1]);
+    assert!((even.centroids[0].equity() - 0.25).abs() < 1e-9);
+
+    let weighted = Buckets::new(&histograms, &[1.0, 10.0, 1.0], 2, 10);
+    assert_eq!(weighted.assignments, vec![0, 1, 1]);
+    assert!((weighted.centroids[1].equity() - 6.0 / 11.0).abs() < 1e-9);
+  }
+}
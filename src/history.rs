@@ -0,0 +1,1277 @@
+/* history.rs
+ * Read the hand histories the PokerStars client saves as .txt files: the stakes, seats,
+ * blinds, hole cards, the betting on each street, the board, showdown and summary.
+ * Each hand is read into a HandHistory, and those that went to showdown can be run
+ * through the hand evaluator and equity calculator to see how the money went in.
+ *
+ * Hands can be written back out, as PokerStars text or as JSON, and both read back in
+ * to the same HandHistory. The JSON is described by HandHistory::to_json().
+ *
+ * A hand history looks like this, with hands in a file separated by blank lines:
+ *
+ * PokerStars Hand #123456789:  Hold'em No Limit ($0.05/$0.10 USD) - 2018/01/01 12:00:00 ET
+ * Table 'Alpha' 6-max Seat #1 is the button
+ * Seat 1: Alice ($10 in chips)
+ * Seat 2: Bob ($10 in chips)
+ * Alice: posts small blind $0.05
+ * Bob: posts big blind $0.10
+ * *** HOLE CARDS ***
+ * Dealt to Alice [Ah Kh]
+ * Alice: raises $0.20 to $0.30
+ * Bob: calls $0.20
+ * *** FLOP *** [2c 7d 9s]
+ * ...
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use equity;
+use equity::Equity;
+use hand::Hand;
+use json::Json;
+use rules::Rules;
+
+/* the betting rounds of a hand, in order */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Street
+{
+  Preflop,
+  Flop,
+  Turn,
+  River
+}
+
+pub const STREETS: [Street; 4] = [Street::Preflop, Street::Flop, Street::Turn, Street::River];
+
+impl Street
+{
+  /* return the name of the street */
+  pub fn name(self) -> &'static str
+  {
+    match self
+    {
+      Street::Preflop => "preflop",
+      Street::Flop => "flop",
+      Street::Turn => "turn",
+      Street::River => "river"
+    }
+  }
+
+  /* return the number of community cards out during this street */
+  pub fn board_cards(self) -> usize
+  {
+    match self
+    {
+      Street::Preflop => 0,
+      Street::Flop => 3,
+      Street::Turn => 4,
+      Street::River => 5
+    }
+  }
+}
+
+/* something a player did. amounts are in the hand's currency, or chips */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Act
+{
+  Ante(f64),
+  SmallBlind(f64),
+  BigBlind(f64),
+  BothBlinds(f64),          /* small and big blinds posted together, eg, on joining a table */
+  Fold,
+  Check,
+  Call(f64),
+  Bet(f64),
+  Raise { by: f64, to: f64 } /* raise by an amount, to a total bet for the street */
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action
+{
+  pub player: String, /* who acted */
+  pub street: Street, /* which betting round they acted in */
+  pub act: Act,       /* what they did */
+  pub all_in: bool    /* true if this put the rest of their stack in */
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seat
+{
+  pub number: usize,     /* seat number at the table, from 1 */
+  pub player: String,    /* player's screen name */
+  pub stack: f64,        /* chips in front of them at the start of the hand */
+  pub sitting_out: bool  /* true if they kept their seat but weren't dealt in */
+}
+
+/* hole cards we know a player held */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holding
+{
+  pub player: String,  /* whose cards they were */
+  pub cards: Vec<Card>, /* the hole cards */
+  pub shown: bool,     /* true if shown to the table, false if only dealt to us */
+  pub hand: String     /* what the site called the hand when shown, eg, a pair of Kings, or empty */
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tournament
+{
+  pub id: String,     /* tournament number */
+  pub buy_in: String, /* buy-in as written, eg, $0.98+$0.12 USD */
+  pub level: String   /* blind level as written, eg, Level I (10/20) */
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandHistory
+{
+  pub site: String,                   /* who dealt it, eg, PokerStars or PokerStars Zoom */
+  pub id: String,                     /* hand number */
+  pub tournament: Option<Tournament>, /* tournament details, or None for a cash game */
+  pub game: String,                   /* game and betting structure, eg, Hold'em No Limit */
+  pub currency: String,               /* currency code, eg, USD, or empty for chips */
+  pub small_blind: f64,
+  pub big_blind: f64,
+  pub date: String,                   /* when the hand was played, as written */
+  pub table: String,                  /* table name */
+  pub max_seats: usize,               /* most players the table seats */
+  pub button: usize,                  /* seat number of the dealer button */
+  pub seats: Vec<Seat>,               /* everyone seated, in seat order */
+  pub hero: Option<String>,           /* whose hole cards were dealt face up to us */
+  pub holdings: Vec<Holding>,         /* every player's hole cards we got to see */
+  pub actions: Vec<Action>,           /* blinds, antes and betting, in order */
+  pub board: Vec<Card>,               /* community cards dealt */
+  pub showdown: bool,                 /* true if the hand went to showdown */
+  pub uncalled: Vec<(String, f64)>,   /* bets no one called, handed back to who made them */
+  pub collected: Vec<(String, f64)>,  /* pots and parts of pots won, and by whom */
+  pub total_pot: f64,
+  pub rake: f64
+}
+
+/* where the players who showed their cards stood as the hand went on */
+#[derive(Debug, Clone)]
+pub struct Showdown
+{
+  pub players: Vec<String>,               /* players who showed down, with their cards seen */
+  pub cards: Vec<Vec<Card>>,              /* each player's hole cards */
+  pub hands: Vec<Hand>,                   /* each player's best hand on the final board */
+  pub equity: Vec<(Street, Vec<Equity>)>  /* each player's equity as each street was dealt */
+}
+
+/* which part of a hand history we're reading */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section
+{
+  Seats,
+  Betting(Street),
+  Showdown,
+  Summary
+}
+
+/* read
+   Read every hand in a hand history file, in either PokerStars text or JSON
+   => text = contents of the file. JSON files hold one hand, or an array of them
+   <= each hand in turn, or a description of what was wrong with it */
+pub fn read(text: &str) -> Vec<Result<HandHistory, String>>
+{
+  let text = text.trim_start_matches('\u{feff}').trim_start();
+  if !text.starts_with('{') && !text.starts_with('[')
+  {
+    return parse_all(text);
+  }
+
+  match Json::parse(text)
+  {
+    Some(Json::Array(hands)) => hands.iter().map(HandHistory::from_json).collect(),
+    Some(hand) => vec![HandHistory::from_json(&hand)],
+    None => vec![Err(String::from("hand history file isn't valid JSON"))]
+  }
+}
+
+/* parse_all
+   Read every hand in the text of a hand history file
+   => text = contents of the file
+   <= each hand in turn, or a description of what was wrong with it */
+pub fn parse_all(text: &str) -> Vec<Result<HandHistory, String>>
+{
+  let mut hands = Vec::<String>::new();
+  for line in text.trim_start_matches('\u{feff}').lines()
+  {
+    if line.starts_with("PokerStars ") || hands.is_empty()
+    {
+      hands.push(String::new());
+    }
+    if let Some(hand) = hands.last_mut()
+    {
+      hand.push_str(line);
+      hand.push('\n');
+    }
+  }
+
+  hands.iter()
+              .filter(|hand| !hand.trim().is_empty())
+              .map(|hand| HandHistory::parse(hand))
+              .collect()
+}
+
+impl HandHistory
+{
+  /* parse
+     Read one hand from its hand history
+     => text = the hand history, from its PokerStars Hand # line on
+     <= HandHistory object, or a description of what was wrong with it */
+  pub fn parse(text: &str) -> Result<HandHistory, String>
+  {
+    let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+    let header = lines.next().ok_or("empty hand history")?;
+    let mut hand = parse_header(header)?;
+
+    let table = lines.next().ok_or(format!("hand #{} ends after its first line", hand.id))?;
+    parse_table(table, &mut hand)?;
+
+    let mut section = Section::Seats;
+    for line in lines
+    {
+      if line.starts_with("*** ")
+      {
+        section = match line.split("***").nth(1).map(|name| name.trim())
+        {
+          Some("HOLE CARDS") => Section::Betting(Street::Preflop),
+          Some("FLOP") => Section::Betting(Street::Flop),
+          Some("TURN") => Section::Betting(Street::Turn),
+          Some("RIVER") => Section::Betting(Street::River),
+          Some("SHOW DOWN") => Section::Showdown,
+          Some("SUMMARY") => Section::Summary,
+          _ => return Err(format!("hand #{} has a section pokercalc can't read: {}", hand.id, line))
+        };
+
+        /* each street's new cards are in the last set of brackets */
+        if let Section::Betting(street) = section
+        {
+          if street != Street::Preflop
+          {
+            let dealt = line.rfind('[').and_then(|start| parse_cards(&line[start..]))
+                            .ok_or(format!("hand #{} has an unreadable board: {}", hand.id, line))?;
+            hand.board.extend(dealt);
+          }
+        }
+        if section == Section::Showdown
+        {
+          hand.showdown = true;
+        }
+        continue;
+      }
+
+      match section
+      {
+        Section::Seats if line.starts_with("Seat ") => hand.seats.push(parse_seat(line, &hand.id)?),
+        Section::Seats => hand.parse_action(line, Street::Preflop)?,
+        Section::Betting(street) => hand.parse_action(line, street)?,
+        Section::Showdown => hand.parse_action(line, Street::River)?,
+        Section::Summary => hand.parse_summary(line)?
+      }
+    }
+
+    if hand.seats.iter().filter(|seat| !seat.sitting_out).count() < 2
+    {
+      return Err(format!("hand #{} has fewer than two players", hand.id));
+    }
+    Ok(hand)
+  }
+
+  /* rules
+     Work out the rules of the game played, for the games pokercalc can analyse
+     <= Rules object, or None if the game isn't supported */
+  pub fn rules(&self) -> Option<Rules>
+  {
+    let game = self.game.as_str();
+    let hilo = game.contains("Hi/Lo");
+    if game.starts_with("Hold'em")
+    {
+      return Some(Rules::holdem());
+    }
+    for (name, hole_cards) in [("Omaha", 4), ("5 Card Omaha", 5), ("6 Card Omaha", 6)].iter()
+    {
+      if game.starts_with(&format!("{} ", name))
+      {
+        return Some(if hilo { Rules::omaha_hilo(*hole_cards) } else { Rules::omaha(*hole_cards) });
+      }
+    }
+    None
+  }
+
+  /* return the hole cards we know a player held, if any */
+  pub fn cards_of(&self, player: &str) -> Option<&Vec<Card>>
+  {
+    self.holdings.iter().find(|holding| holding.player == player).map(|holding| &holding.cards)
+  }
+
+  /* return true if the player folded at some point in the hand */
+  pub fn folded(&self, player: &str) -> bool
+  {
+    self.actions.iter().any(|action| action.player == player && action.act == Act::Fold)
+  }
+
+  /* invested
+     Work out how much a player put into the pot, not counting any bet handed back uncalled
+     => player = screen name
+     <= total of their antes, blinds, calls, bets and raises */
+  pub fn invested(&self, player: &str) -> f64
+  {
+    let mut total = 0.0;
+    for street in STREETS.iter()
+    {
+      /* a raise is to a total for the street, which includes any blind posted */
+      let mut this_street = 0.0;
+      for action in self.actions.iter().filter(|action| action.player == player && action.street == *street)
+      {
+        match action.act
+        {
+          Act::Ante(amount) => total += amount,
+          Act::BothBlinds(amount) =>
+          {
+            /* only the big blind counts towards calling, the small blind is dead */
+            total = total + amount - self.big_blind;
+            this_street += self.big_blind;
+          },
+          Act::SmallBlind(amount) | Act::BigBlind(amount) | Act::Call(amount) | Act::Bet(amount) =>
+            this_street += amount,
+          Act::Raise { to, .. } => this_street = to,
+          Act::Fold | Act::Check => ()
+        }
+      }
+      total += this_street;
+    }
+
+    let returned: f64 = self.uncalled.iter().filter(|(name, _)| name == player).map(|(_, amount)| amount).sum();
+    total - returned
+  }
+
+  /* return how much a player collected from the pot */
+  pub fn won(&self, player: &str) -> f64
+  {
+    self.collected.iter().filter(|(name, _)| name == player).map(|(_, amount)| amount).sum()
+  }
+
+  /* return how much a player won or lost over the hand */
+  pub fn net(&self, player: &str) -> f64
+  {
+    self.won(player) - self.invested(player)
+  }
+
+  /* amount
+     Write out an amount the way hand histories do, in the hand's currency, with
+     cents only when needed, eg, $0.25 or $10, or plain chips in tournaments
+     => value = amount of money or chips
+     <= amount as text */
+  pub fn amount(&self, value: f64) -> String
+  {
+    let symbol = match self.currency.as_str()
+    {
+      "" => "",
+      "EUR" => "€",
+      "GBP" => "£",
+      _ => "$"
+    };
+
+    let cents = (value * 100.0).round() as i64;
+    match cents % 100
+    {
+      0 => format!("{}{}", symbol, cents / 100),
+      _ => format!("{}{}.{:02}", symbol, cents / 100, cents % 100)
+    }
+  }
+
+  /* analyse
+     Run a hand that went to showdown through the hand evaluator and equity calculator
+     <= Showdown object, or None if the hand didn't go to showdown with at least two
+        players' cards seen and a full board, or the game isn't supported */
+  pub fn analyse(&self) -> Option<Showdown>
+  {
+    let rules = self.rules()?;
+    if !self.showdown || self.board.len() != 5
+    {
+      return None;
+    }
+
+    let mut players = Vec::<String>::new();
+    let mut cards = Vec::<Vec<Card>>::new();
+    for holding in self.holdings.iter()
+    {
+      if !self.folded(&holding.player) && holding.cards.len() == rules.hole_cards()
+      {
+        players.push(holding.player.clone());
+        cards.push(holding.cards.clone());
+      }
+    }
+    if players.len() < 2
+    {
+      return None;
+    }
+
+    let hands = cards.iter().map(|hole| rules.hand(hole, &self.board)).collect();
+    let mut equity = Vec::<(Street, Vec<Equity>)>::new();
+    for street in STREETS.iter()
+    {
+      let board = &self.board[0..street.board_cards()];
+      equity.push((*street, equity::enumerate(&rules, &cards, board, &[])?));
+    }
+
+    Some(Showdown { players, cards, hands, equity })
+  }
+
+  /* to_text
+     Write the hand out as a PokerStars hand history, which parse() reads back in
+     <= the hand history, one line after another */
+  pub fn to_text(&self) -> String
+  {
+    let mut lines = Vec::<String>::new();
+    let stakes = format!("{}/{}", self.amount(self.small_blind), self.amount(self.big_blind));
+    lines.push(match self.tournament
+    {
+      Some(ref tournament) => format!("{} Hand #{}: Tournament #{}, {} {} - {} - {}", self.site, self.id, tournament.id,
+                                      tournament.buy_in, self.game, tournament.level, self.date),
+      None if self.currency.is_empty() => format!("{} Hand #{}:  {} ({}) - {}", self.site, self.id, self.game, stakes, self.date),
+      None => format!("{} Hand #{}:  {} ({} {}) - {}", self.site, self.id, self.game, stakes, self.currency, self.date)
+    });
+    lines.push(format!("Table '{}' {}-max Seat #{} is the button", self.table, self.max_seats, self.button));
+    for seat in self.seats.iter()
+    {
+      let sitting_out = if seat.sitting_out { " is sitting out" } else { "" };
+      lines.push(format!("Seat {}: {} ({} in chips){}", seat.number, seat.player, self.amount(seat.stack), sitting_out));
+    }
+
+    /* blinds and antes go in before the hole cards are dealt, the rest after */
+    let posts = self.actions.iter()
+                            .take_while(|action| matches!(action.act, Act::Ante(_) | Act::SmallBlind(_) |
+                                                                      Act::BigBlind(_) | Act::BothBlinds(_)))
+                            .count();
+    for action in self.actions[0..posts].iter()
+    {
+      lines.push(self.action_text(action));
+    }
+    lines.push(String::from("*** HOLE CARDS ***"));
+
+    /* whoever we were dealt in as comes last, as the last cards dealt to us are ours */
+    let mut dealt: Vec<&Holding> = self.holdings.iter()
+                                                .filter(|holding| !holding.shown || self.hero.as_ref() == Some(&holding.player))
+                                                .collect();
+    dealt.sort_by_key(|holding| self.hero.as_ref() == Some(&holding.player));
+    for holding in dealt.iter()
+    {
+      lines.push(format!("Dealt to {} {}", holding.player, cards_text(&holding.cards)));
+    }
+
+    /* a bet comes back at the end of the street it was made on, which is the last street
+       its player acted on */
+    let returned_on = |player: &str| self.actions.iter().rev().find(|action| action.player == player).map(|action| action.street);
+    let returned = |lines: &mut Vec<String>, street: Option<Street>|
+    {
+      for (player, amount) in self.uncalled.iter().filter(|(player, _)| returned_on(player) == street)
+      {
+        lines.push(format!("Uncalled bet ({}) returned to {}", self.amount(*amount), player));
+      }
+    };
+
+    for street in STREETS.iter()
+    {
+      let cards = street.board_cards();
+      if *street != Street::Preflop && self.board.len() >= cards
+      {
+        let marker = format!("*** {} ***", street.name().to_uppercase());
+        lines.push(match street
+        {
+          Street::Flop => format!("{} {}", marker, cards_text(&self.board[0..3])),
+          _ => format!("{} {} {}", marker, cards_text(&self.board[0..cards - 1]), cards_text(&self.board[cards - 1..cards]))
+        });
+      }
+      for action in self.actions.iter().skip(posts).filter(|action| action.street == *street)
+      {
+        lines.push(self.action_text(action));
+      }
+      returned(&mut lines, Some(*street));
+    }
+
+    returned(&mut lines, None);
+    if self.showdown
+    {
+      lines.push(String::from("*** SHOW DOWN ***"));
+    }
+    for holding in self.holdings.iter().filter(|holding| holding.shown)
+    {
+      lines.push(match holding.hand.is_empty()
+      {
+        true => format!("{}: shows {}", holding.player, cards_text(&holding.cards)),
+        false => format!("{}: shows {} ({})", holding.player, cards_text(&holding.cards), holding.hand)
+      });
+    }
+    for (player, amount) in self.collected.iter()
+    {
+      lines.push(format!("{} collected {} from pot", player, self.amount(*amount)));
+    }
+
+    lines.push(String::from("*** SUMMARY ***"));
+    lines.push(format!("Total pot {} | Rake {}", self.amount(self.total_pot), self.amount(self.rake)));
+    if !self.board.is_empty()
+    {
+      lines.push(format!("Board {}", cards_text(&self.board)));
+    }
+    for seat in self.seats.iter().filter(|seat| !seat.sitting_out)
+    {
+      lines.push(self.summary_text(seat));
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+  }
+
+  /* to_json
+     Describe the hand as JSON, which from_json() reads back in. The hand is an object with:
+       site, id, game, date, table = strings, as in the hand history
+       currency = currency code, eg, "USD", or "" for chips
+       tournament = null for cash games, or an object with strings id, buy_in and level
+       small_blind, big_blind, total_pot, rake = amounts, as numbers
+       max_seats, button = numbers
+       seats = array of objects, each with number, player, stack, and sitting_out: true if
+               they kept their seat but weren't dealt in
+       hero = name of the player whose cards were dealt to us, or null
+       holdings = array of objects, each with player, cards, shown: true if the cards
+                  were shown to the table, false if they were only dealt to us, and hand:
+                  what the site called the hand when shown, or ""
+       actions = array of objects, each with player, street (preflop, flop, turn or river),
+                 act (ante, small_blind, big_blind, both_blinds, fold, check, call, bet or
+                 raise), amount (for all but fold and check; for a raise, how much it was
+                 raised by), to (for a raise only, the total bet for the street), and all_in
+       board = array of cards
+       showdown = true if the hand went to showdown
+       uncalled, collected = arrays of objects, each with player and amount
+     Cards are strings of value then suit, eg, "Ah", "Td"
+     <= Json object */
+  pub fn to_json(&self) -> Json
+  {
+    let cards = |cards: &[Card]| Json::Array(cards.iter().map(|card| Json::String(card.code())).collect());
+    let payments = |list: &[(String, f64)]| Json::Array(list.iter().map(|(player, amount)| Json::object(vec![
+      ("player", Json::string(player)),
+      ("amount", Json::Number(*amount))
+    ])).collect());
+
+    let actions = self.actions.iter().map(|action|
+    {
+      let (act, amount, to) = match action.act
+      {
+        Act::Ante(amount) => ("ante", Some(amount), None),
+        Act::SmallBlind(amount) => ("small_blind", Some(amount), None),
+        Act::BigBlind(amount) => ("big_blind", Some(amount), None),
+        Act::BothBlinds(amount) => ("both_blinds", Some(amount), None),
+        Act::Fold => ("fold", None, None),
+        Act::Check => ("check", None, None),
+        Act::Call(amount) => ("call", Some(amount), None),
+        Act::Bet(amount) => ("bet", Some(amount), None),
+        Act::Raise { by, to } => ("raise", Some(by), Some(to))
+      };
+
+      let mut fields = vec![
+        ("player", Json::string(&action.player)),
+        ("street", Json::string(action.street.name())),
+        ("act", Json::string(act))
+      ];
+      if let Some(amount) = amount
+      {
+        fields.push(("amount", Json::Number(amount)));
+      }
+      if let Some(to) = to
+      {
+        fields.push(("to", Json::Number(to)));
+      }
+      fields.push(("all_in", Json::Bool(action.all_in)));
+      Json::object(fields)
+    }).collect();
+
+    Json::object(vec![
+      ("site", Json::string(&self.site)),
+      ("id", Json::string(&self.id)),
+      ("tournament", match self.tournament
+      {
+        Some(ref tournament) => Json::object(vec![
+          ("id", Json::string(&tournament.id)),
+          ("buy_in", Json::string(&tournament.buy_in)),
+          ("level", Json::string(&tournament.level))
+        ]),
+        None => Json::Null
+      }),
+      ("game", Json::string(&self.game)),
+      ("currency", Json::string(&self.currency)),
+      ("small_blind", Json::Number(self.small_blind)),
+      ("big_blind", Json::Number(self.big_blind)),
+      ("date", Json::string(&self.date)),
+      ("table", Json::string(&self.table)),
+      ("max_seats", Json::Number(self.max_seats as f64)),
+      ("button", Json::Number(self.button as f64)),
+      ("seats", Json::Array(self.seats.iter().map(|seat| Json::object(vec![
+        ("number", Json::Number(seat.number as f64)),
+        ("player", Json::string(&seat.player)),
+        ("stack", Json::Number(seat.stack)),
+        ("sitting_out", Json::Bool(seat.sitting_out))
+      ])).collect())),
+      ("hero", match self.hero
+      {
+        Some(ref hero) => Json::string(hero),
+        None => Json::Null
+      }),
+      ("holdings", Json::Array(self.holdings.iter().map(|holding| Json::object(vec![
+        ("player", Json::string(&holding.player)),
+        ("cards", cards(&holding.cards)),
+        ("shown", Json::Bool(holding.shown)),
+        ("hand", Json::string(&holding.hand))
+      ])).collect())),
+      ("actions", Json::Array(actions)),
+      ("board", cards(&self.board)),
+      ("showdown", Json::Bool(self.showdown)),
+      ("uncalled", payments(&self.uncalled)),
+      ("collected", payments(&self.collected)),
+      ("total_pot", Json::Number(self.total_pot)),
+      ("rake", Json::Number(self.rake))
+    ])
+  }
+
+  /* from_json
+     Read a hand from its JSON description, as written by to_json()
+     => json = the hand as a JSON object
+     <= HandHistory object, or a description of what was wrong with it */
+  pub fn from_json(json: &Json) -> Result<HandHistory, String>
+  {
+    let id = json.get("id").and_then(|id| id.as_str()).ok_or("hand has no id")?.to_string();
+    let missing = |field: &str| format!("hand #{} has a missing or invalid {}", id, field);
+    let text = |json: &Json, field: &str| json.get(field).and_then(|value| value.as_str()).map(|value| value.to_string())
+                                              .ok_or_else(|| missing(field));
+    let number = |json: &Json, field: &str| json.get(field).and_then(|value| value.as_f64()).ok_or_else(|| missing(field));
+    let flag = |json: &Json, field: &str| json.get(field).and_then(|value| value.as_bool()).ok_or_else(|| missing(field));
+    let list = |json: &'_ Json, field: &str| json.get(field).and_then(|value| value.as_array()).cloned()
+                                                .ok_or_else(|| missing(field));
+    let cards = |json: &Json, field: &str| -> Result<Vec<Card>, String>
+    {
+      list(json, field)?.iter()
+                        .map(|card| card.as_str().and_then(|code| Card::new(&code.to_lowercase())))
+                        .collect::<Option<Vec<Card>>>()
+                        .ok_or_else(|| missing(field))
+    };
+    let payments = |field: &str| -> Result<Vec<(String, f64)>, String>
+    {
+      list(json, field)?.iter().map(|payment| Ok((text(payment, "player")?, number(payment, "amount")?))).collect()
+    };
+
+    let tournament = match json.get("tournament")
+    {
+      None | Some(Json::Null) => None,
+      Some(tournament) => Some(Tournament
+      {
+        id: text(tournament, "id")?,
+        buy_in: text(tournament, "buy_in")?,
+        level: text(tournament, "level")?
+      })
+    };
+
+    let mut seats = Vec::<Seat>::new();
+    for seat in list(json, "seats")?.iter()
+    {
+      seats.push(Seat { number: number(seat, "number")? as usize, player: text(seat, "player")?, stack: number(seat, "stack")?,
+                        sitting_out: flag(seat, "sitting_out")? });
+    }
+
+    let mut holdings = Vec::<Holding>::new();
+    for holding in list(json, "holdings")?.iter()
+    {
+      holdings.push(Holding { player: text(holding, "player")?, cards: cards(holding, "cards")?, shown: flag(holding, "shown")?,
+                              hand: text(holding, "hand")? });
+    }
+
+    let mut actions = Vec::<Action>::new();
+    for action in list(json, "actions")?.iter()
+    {
+      let street = match text(action, "street")?.as_str()
+      {
+        "preflop" => Street::Preflop,
+        "flop" => Street::Flop,
+        "turn" => Street::Turn,
+        "river" => Street::River,
+        _ => return Err(missing("street"))
+      };
+      let amount = || number(action, "amount");
+      let act = match text(action, "act")?.as_str()
+      {
+        "ante" => Act::Ante(amount()?),
+        "small_blind" => Act::SmallBlind(amount()?),
+        "big_blind" => Act::BigBlind(amount()?),
+        "both_blinds" => Act::BothBlinds(amount()?),
+        "fold" => Act::Fold,
+        "check" => Act::Check,
+        "call" => Act::Call(amount()?),
+        "bet" => Act::Bet(amount()?),
+        "raise" => Act::Raise { by: amount()?, to: number(action, "to")? },
+        _ => return Err(missing("act"))
+      };
+      actions.push(Action { player: text(action, "player")?, street, act, all_in: flag(action, "all_in")? });
+    }
+
+    Ok(HandHistory
+    {
+      site: text(json, "site")?,
+      id: id.clone(),
+      tournament,
+      game: text(json, "game")?,
+      currency: text(json, "currency")?,
+      small_blind: number(json, "small_blind")?,
+      big_blind: number(json, "big_blind")?,
+      date: text(json, "date")?,
+      table: text(json, "table")?,
+      max_seats: number(json, "max_seats")? as usize,
+      button: number(json, "button")? as usize,
+      seats,
+      hero: json.get("hero").and_then(|hero| hero.as_str()).map(|hero| hero.to_string()),
+      holdings,
+      actions,
+      board: cards(json, "board")?,
+      showdown: flag(json, "showdown")?,
+      uncalled: payments("uncalled")?,
+      collected: payments("collected")?,
+      total_pot: number(json, "total_pot")?,
+      rake: number(json, "rake")?
+    })
+  }
+
+  /* write out one line of betting */
+  fn action_text(&self, action: &Action) -> String
+  {
+    let act = match action.act
+    {
+      Act::Ante(amount) => format!("posts the ante {}", self.amount(amount)),
+      Act::SmallBlind(amount) => format!("posts small blind {}", self.amount(amount)),
+      Act::BigBlind(amount) => format!("posts big blind {}", self.amount(amount)),
+      Act::BothBlinds(amount) => format!("posts small & big blinds {}", self.amount(amount)),
+      Act::Fold => String::from("folds"),
+      Act::Check => String::from("checks"),
+      Act::Call(amount) => format!("calls {}", self.amount(amount)),
+      Act::Bet(amount) => format!("bets {}", self.amount(amount)),
+      Act::Raise { by, to } => format!("raises {} to {}", self.amount(by), self.amount(to))
+    };
+    match action.all_in
+    {
+      true => format!("{}: {} and is all-in", action.player, act),
+      false => format!("{}: {}", action.player, act)
+    }
+  }
+
+  /* write out how a seat fared, for the summary */
+  fn summary_text(&self, seat: &Seat) -> String
+  {
+    let player = seat.player.as_str();
+    let mut position = String::new();
+    if seat.number == self.button
+    {
+      position.push_str(" (button)");
+    }
+    for action in self.actions.iter().filter(|action| action.player == player)
+    {
+      match action.act
+      {
+        Act::SmallBlind(_) => position.push_str(" (small blind)"),
+        Act::BigBlind(_) => position.push_str(" (big blind)"),
+        _ => ()
+      }
+    }
+
+    let won = self.won(player);
+    let shown = self.holdings.iter().find(|holding| holding.player == player && holding.shown);
+    let fold = self.actions.iter().find(|action| action.player == player && action.act == Act::Fold);
+    let with = |holding: &Holding| match holding.hand.is_empty()
+    {
+      true => String::new(),
+      false => format!(" with {}", holding.hand)
+    };
+    let outcome = match (shown, fold)
+    {
+      (Some(holding), _) if won > 0.0 => format!("showed {} and won ({}){}", cards_text(&holding.cards), self.amount(won), with(holding)),
+      (Some(holding), _) => format!("showed {} and lost{}", cards_text(&holding.cards), with(holding)),
+      (None, Some(action)) if action.street == Street::Preflop => String::from("folded before Flop"),
+      (None, Some(action)) =>
+      {
+        let street = action.street.name();
+        format!("folded on the {}{}", street[0..1].to_uppercase(), &street[1..])
+      },
+      (None, None) if won > 0.0 => format!("collected ({})", self.amount(won)),
+      (None, None) if self.showdown => String::from("mucked"),
+      (None, None) => String::from("lost")
+    };
+    format!("Seat {}: {}{} {}", seat.number, player, position, outcome)
+  }
+
+  /* read a line of betting, or anything else that happens during a street */
+  fn parse_action(&mut self, line: &str, street: Street) -> Result<(), String>
+  {
+    if let Some(rest) = line.strip_prefix("Dealt to ")
+    {
+      /* fast-fold tables list everyone dealt in, but only show us our own cards */
+      if let Some(start) = rest.find(" [")
+      {
+        let player = rest[..start].to_string();
+        let cards = parse_cards(&rest[start + 1..]).ok_or(format!("hand #{} has unreadable hole cards: {}", self.id, line))?;
+        self.hero = Some(player.clone());
+        self.holdings.push(Holding { player, cards, shown: false, hand: String::new() });
+      }
+      return Ok(());
+    }
+
+    if let Some(rest) = line.strip_prefix("Uncalled bet (")
+    {
+      if let Some((amount, player)) = rest.split_once(") returned to ")
+      {
+        self.uncalled.push((player.to_string(), parse_amount(amount, &self.id)?));
+      }
+      return Ok(());
+    }
+
+    let player = match self.player_at_start(line)
+    {
+      Some(player) => player,
+      None => return Ok(()) /* someone joining, leaving or chatting */
+    };
+    let rest = &line[player.len()..];
+
+    if let Some(rest) = rest.strip_prefix(" collected ")
+    {
+      let amount = rest.split_whitespace().next().unwrap_or("");
+      self.collected.push((player, parse_amount(amount, &self.id)?));
+      return Ok(());
+    }
+
+    let rest = match rest.strip_prefix(": ")
+    {
+      Some(rest) => rest,
+      None => return Ok(())
+    };
+
+    if let Some(shown) = rest.strip_prefix("shows ")
+    {
+      let cards = parse_cards(shown).ok_or(format!("hand #{} has unreadable shown cards: {}", self.id, line))?;
+      let hand = shown.split_once("] (").and_then(|(_, hand)| hand.strip_suffix(')')).unwrap_or("");
+      self.show(&player, cards, hand);
+      return Ok(());
+    }
+
+    let (rest, all_in) = match rest.strip_suffix(" and is all-in")
+    {
+      Some(rest) => (rest, true),
+      None => (rest, false)
+    };
+
+    let words: Vec<&str> = rest.split_whitespace().collect();
+    let amount = |word: usize| parse_amount(words.get(word).unwrap_or(&""), &self.id);
+    let act = match words.as_slice()
+    {
+      ["folds", ..] => Act::Fold,
+      ["checks"] => Act::Check,
+      ["calls", _] => Act::Call(amount(1)?),
+      ["bets", _] => Act::Bet(amount(1)?),
+      ["raises", _, "to", _] => Act::Raise { by: amount(1)?, to: amount(3)? },
+      ["posts", "small", "blind", _] => Act::SmallBlind(amount(3)?),
+      ["posts", "big", "blind", _] => Act::BigBlind(amount(3)?),
+      ["posts", "the", "ante", _] => Act::Ante(amount(3)?),
+      ["posts", "small", "&", "big", "blinds", _] => Act::BothBlinds(amount(5)?),
+      _ => return Ok(()) /* mucking, sitting out, timing out and so on */
+    };
+
+    self.actions.push(Action { player, street, act, all_in });
+    Ok(())
+  }
+
+  /* read a line of the summary, which repeats most of the hand. only the pot, rake,
+     board and any cards shown or mucked face up are new */
+  fn parse_summary(&mut self, line: &str) -> Result<(), String>
+  {
+    if let Some(rest) = line.strip_prefix("Total pot ")
+    {
+      let pot = rest.split_whitespace().next().unwrap_or("");
+      self.total_pot = parse_amount(pot, &self.id)?;
+      if let Some((_, rake)) = rest.split_once("| Rake ")
+      {
+        self.rake = parse_amount(rake.split_whitespace().next().unwrap_or(""), &self.id)?;
+      }
+      return Ok(());
+    }
+
+    if line.starts_with("Seat ")
+    {
+      for word in ["showed [", "mucked ["].iter()
+      {
+        if let Some(start) = line.find(word)
+        {
+          let player = match self.seats.iter().find(|seat| line.starts_with(&format!("Seat {}: {} ", seat.number, seat.player)))
+          {
+            Some(seat) => seat.player.clone(),
+            None => continue
+          };
+          let shown = &line[start + word.len() - 1..];
+          let cards = parse_cards(shown).ok_or(format!("hand #{} has unreadable shown cards: {}", self.id, line))?;
+          let hand = shown.split_once(" with ").map(|(_, hand)| hand).unwrap_or("");
+          self.show(&player, cards, hand);
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /* note down the cards a player showed, unless we know them already, and what they made.
+     shown hands are kept in the order they were shown */
+  fn show(&mut self, player: &str, cards: Vec<Card>, hand: &str)
+  {
+    let mut holding = match self.holdings.iter().position(|holding| holding.player == player)
+    {
+      Some(known) if self.holdings[known].shown =>
+      {
+        if self.holdings[known].hand.is_empty()
+        {
+          self.holdings[known].hand = hand.to_string();
+        }
+        return;
+      },
+      Some(dealt) => self.holdings.remove(dealt),
+      None => Holding { player: player.to_string(), cards, shown: false, hand: String::new() }
+    };
+    holding.shown = true;
+    holding.hand = hand.to_string();
+    self.holdings.push(holding);
+  }
+
+  /* find the seated player whose name starts the line. names can hold spaces and
+     colons, so pick the longest that fits */
+  fn player_at_start(&self, line: &str) -> Option<String>
+  {
+    self.seats.iter()
+              .map(|seat| &seat.player)
+              .filter(|name| line.starts_with(name.as_str()) &&
+                             (line[name.len()..].starts_with(": ") || line[name.len()..].starts_with(" collected ")))
+              .max_by_key(|name| name.len())
+              .cloned()
+  }
+}
+
+/* read the first line of a hand, eg:
+   PokerStars Hand #123:  Hold'em No Limit ($0.05/$0.10 USD) - 2018/01/01 12:00:00 ET
+   PokerStars Hand #123: Tournament #456, $1+$0.10 USD Hold'em No Limit - Level I (10/20) - 2018/01/01 12:00:00 ET */
+fn parse_header(line: &str) -> Result<HandHistory, String>
+{
+  let line = line.trim_start_matches('\u{feff}');
+  let (site, rest) = line.split_once(" Hand #").ok_or(format!("not a PokerStars hand: {}", line))?;
+  if !site.starts_with("PokerStars")
+  {
+    return Err(format!("not a PokerStars hand: {}", line));
+  }
+  let (id, rest) = rest.split_once(':').ok_or(format!("unreadable hand number: {}", line))?;
+  let parts: Vec<&str> = rest.trim().split(" - ").collect();
+  let unreadable = || format!("hand #{} has an unreadable first line: {}", id, line);
+
+  let mut hand = HandHistory
+  {
+    site: site.to_string(),
+    id: id.to_string(),
+    tournament: None,
+    game: String::new(),
+    currency: String::new(),
+    small_blind: 0.0,
+    big_blind: 0.0,
+    date: String::new(),
+    table: String::new(),
+    max_seats: 0,
+    button: 0,
+    seats: Vec::new(),
+    hero: None,
+    holdings: Vec::new(),
+    actions: Vec::new(),
+    board: Vec::new(),
+    showdown: false,
+    uncalled: Vec::new(),
+    collected: Vec::new(),
+    total_pot: 0.0,
+    rake: 0.0
+  };
+
+  let stakes = match parts[0].strip_prefix("Tournament #")
+  {
+    Some(tournament) =>
+    {
+      /* the buy-in runs up to where the name of the game starts */
+      let (tournament_id, rest) = tournament.split_once(", ").ok_or_else(unreadable)?;
+      let start = GAMES.iter().filter_map(|game| rest.find(game)).min().ok_or_else(unreadable)?;
+      let level = parts.get(1).ok_or_else(unreadable)?;
+      hand.tournament = Some(Tournament
+      {
+        id: tournament_id.to_string(),
+        buy_in: rest[..start].trim().to_string(),
+        level: level.to_string()
+      });
+      hand.game = rest[start..].trim().to_string();
+      hand.date = parts[2..].join(" - ");
+      level.to_string()
+    },
+    None =>
+    {
+      let (game, stakes) = parts[0].split_once(" (").ok_or_else(unreadable)?;
+      hand.game = game.trim().to_string();
+      hand.date = parts[1..].join(" - ");
+      stakes.to_string()
+    }
+  };
+
+  /* stakes are in brackets, eg, ($0.05/$0.10 USD) or (10/20) */
+  let start = stakes.find('(').map(|start| start + 1).unwrap_or(0);
+  let end = stakes.rfind(')').unwrap_or(stakes.len());
+  let mut words = stakes[start..end].split_whitespace();
+  let (small, big) = words.next().and_then(|blinds| blinds.split_once('/')).ok_or_else(unreadable)?;
+  hand.small_blind = parse_amount(small, id)?;
+  hand.big_blind = parse_amount(big, id)?;
+  hand.currency = words.next().unwrap_or("").to_string();
+  if hand.currency.is_empty() && hand.tournament.is_none() && small.starts_with('$')
+  {
+    hand.currency = String::from("USD");
+  }
+  Ok(hand)
+}
+
+/* games as PokerStars names them at the start of their description */
+const GAMES: [&str; 4] = ["Hold'em", "Omaha", "5 Card Omaha", "6 Card Omaha"];
+
+/* read the second line of a hand, eg: Table 'Alpha' 6-max Seat #1 is the button */
+fn parse_table(line: &str, hand: &mut HandHistory) -> Result<(), String>
+{
+  let unreadable = || format!("hand #{} has an unreadable table line: {}", hand.id, line);
+  let start = line.find('\'').ok_or_else(unreadable)?;
+  let end = line.rfind('\'').ok_or_else(unreadable)?;
+  if end <= start
+  {
+    return Err(unreadable());
+  }
+  let table = line[start + 1..end].to_string();
+
+  let mut max_seats = 0;
+  let mut button = 0;
+  for word in line[end + 1..].split_whitespace()
+  {
+    if let Some(seats) = word.strip_suffix("-max")
+    {
+      max_seats = seats.parse::<usize>().map_err(|_| unreadable())?;
+    }
+    if let Some(seat) = word.strip_prefix('#')
+    {
+      button = seat.parse::<usize>().map_err(|_| unreadable())?;
+    }
+  }
+
+  hand.table = table;
+  hand.max_seats = max_seats;
+  hand.button = button;
+  Ok(())
+}
+
+/* read a seat at the start of a hand, eg:
+   Seat 1: Alice ($10 in chips)
+   Seat 2: Bob (1500 in chips, $0.50 bounty) is sitting out
+   Seat 3: Carol (1500 in chips) out of hand (moved from another table into small blind) */
+fn parse_seat(line: &str, id: &str) -> Result<Seat, String>
+{
+  let unreadable = || format!("hand #{} has an unreadable seat: {}", id, line);
+  let rest = &line["Seat ".len()..];
+  let (number, rest) = rest.split_once(": ").ok_or_else(unreadable)?;
+
+  /* names can hold brackets, and so can what comes after the stack, so go by where the chips are */
+  let chips = rest.find(" in chips").ok_or_else(unreadable)?;
+  let start = rest[..chips].rfind(" (").ok_or_else(unreadable)?;
+  let after = &rest[chips..];
+
+  Ok(Seat
+  {
+    number: number.parse::<usize>().map_err(|_| unreadable())?,
+    player: rest[..start].to_string(),
+    stack: parse_amount(&rest[start + 2..chips], id)?,
+    sitting_out: after.ends_with(" is sitting out") || after.contains(") out of hand")
+  })
+}
+
+/* read an amount of money or chips, eg, $0.25, €10 or 1500 */
+fn parse_amount(text: &str, id: &str) -> Result<f64, String>
+{
+  let digits = text.trim_start_matches(|c: char| !c.is_ascii_digit()).trim_end_matches(|c: char| !c.is_ascii_digit());
+  digits.replace(',', "").parse::<f64>().map_err(|_| format!("hand #{} has an unreadable amount: {}", id, text))
+}
+
+/* write out a list of cards in brackets, eg: [Ah Kh] */
+fn cards_text(cards: &[Card]) -> String
+{
+  format!("[{}]", cards.iter().map(|card| card.code()).collect::<Vec<String>>().join(" "))
+}
+
+/* read a list of cards in brackets, eg: [Ah Kh] */
+fn parse_cards(text: &str) -> Option<Vec<Card>>
+{
+  let start = text.find('[')?;
+  let end = text.find(']')?;
+  if end < start
+  {
+    return None;
+  }
+  text[start + 1..end].split_whitespace().map(|code| Card::new(&code.to_lowercase())).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  const SAMPLE: &str = "\
+PokerStars Hand #184759612345:  Hold'em No Limit ($0.05/$0.10 USD) - 2018/04/14 21:05:31 CET [2018/04/14 15:05:31 ET]
+Table 'Aaltje II' 6-max Seat #3 is the button
+Seat 1: fishy (mc) ($10.52 in chips)
+Seat 2: Bob ($9.90 in chips) is sitting out
+Seat 3: carol99 ($4.15 in chips)
+Seat 4: Dave ($10 in chips)
+Dave: posts small blind $0.05
+fishy (mc): posts big blind $0.10
+*** HOLE CARDS ***
+Dealt to carol99 [Ah Kd]
+carol99: raises $0.20 to $0.30
+Dave: folds
+fishy (mc): calls $0.20
+*** FLOP *** [Kc 7h 2d]
+fishy (mc): checks
+carol99: bets $0.40
+fishy (mc): calls $0.40
+*** TURN *** [Kc 7h 2d] [9s]
+fishy (mc): checks
+carol99: bets $3.45 and is all-in
+fishy (mc): calls $3.45
+*** RIVER *** [Kc 7h 2d 9s] [Qs]
+*** SHOW DOWN ***
+fishy (mc): shows [Kh 7c] (two pair, Kings and Sevens)
+carol99: shows [Ah Kd] (a pair of Kings)
+fishy (mc) collected $7.98 from pot
+*** SUMMARY ***
+Total pot $8.35 | Rake $0.37
+Board [Kc 7h 2d 9s Qs]
+Seat 1: fishy (mc) (big blind) showed [Kh 7c] and won ($7.98) with two pair, Kings and Sevens
+Seat 3: carol99 (button) showed [Ah Kd] and lost with a pair of Kings
+Seat 4: Dave (small blind) folded before Flop
+";
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  fn close(a: f64, b: f64) -> bool
+  {
+    (a - b).abs() < 1e-9
+  }
+
+  #[test]
+  fn cash_game_header()
+  {
+    let hand = parse_header("PokerStars Hand #184759612345:  Hold'em No Limit ($0.05/$0.10 USD) - \
+                             2018/04/14 21:05:31 CET [2018/04/14 15:05:31 ET]").unwrap();
+    assert_eq!((hand.site.as_str(), hand.id.as_str()), ("PokerStars", "184759612345"));
+    assert_eq!(hand.game, "Hold'em No Limit");
+    assert_eq!(hand.rules(), Some(Rules::holdem()));
+    assert_eq!((hand.small_blind, hand.big_blind, hand.currency.as_str()), (0.05, 0.10, "USD"));
+    assert_eq!(hand.date, "2018/04/14 21:05:31 CET [2018/04/14 15:05:31 ET]");
+    assert!(hand.tournament.is_none());
+
+    let hand = parse_header("PokerStars Hand #184759612346:  Omaha Pot Limit (€0.10/€0.25 EUR) - 2018/04/14 21:06:02 CET").unwrap();
+    assert_eq!((hand.game.as_str(), hand.currency.as_str()), ("Omaha Pot Limit", "EUR"));
+    assert_eq!(hand.rules(), Some(Rules::omaha(4)));
+  }
+
+  #[test]
+  fn tournament_header()
+  {
+    let hand = parse_header("PokerStars Hand #184759700001: Tournament #2298437211, $0.98+$0.12 USD Hold'em No Limit - \
+                             Level III (25/50) - 2018/04/14 21:20:11 CET [2018/04/14 15:20:11 ET]").unwrap();
+    let tournament = hand.tournament.as_ref().unwrap();
+    assert_eq!((tournament.id.as_str(), tournament.buy_in.as_str()), ("2298437211", "$0.98+$0.12 USD"));
+    assert_eq!(tournament.level, "Level III (25/50)");
+    assert_eq!(hand.game, "Hold'em No Limit");
+    assert_eq!((hand.small_blind, hand.big_blind, hand.currency.as_str()), (25.0, 50.0, ""));
+  }
+
+  #[test]
+  fn seats()
+  {
+    let seat = parse_seat("Seat 1: fishy (mc) ($10.52 in chips)", "1").unwrap();
+    assert_eq!((seat.number, seat.player.as_str(), seat.stack, seat.sitting_out), (1, "fishy (mc)", 10.52, false));
+
+    let seat = parse_seat("Seat 2: Bob (1,500 in chips, $0.50 bounty) is sitting out", "1").unwrap();
+    assert_eq!((seat.number, seat.player.as_str(), seat.stack, seat.sitting_out), (2, "Bob", 1500.0, true));
+
+    let seat = parse_seat("Seat 3: carol99 (1500 in chips) out of hand (moved from another table into small blind)", "1").unwrap();
+    assert_eq!((seat.number, seat.player.as_str(), seat.stack, seat.sitting_out), (3, "carol99", 1500.0, true));
+
+    assert!(parse_seat("Seat 4: Dave", "1").is_err());
+  }
+
+  #[test]
+  fn whole_hand()
+  {
+    let hand = HandHistory::parse(SAMPLE).unwrap();
+    assert_eq!((hand.table.as_str(), hand.max_seats, hand.button), ("Aaltje II", 6, 3));
+    let seated: Vec<(&str, bool)> = hand.seats.iter().map(|seat| (seat.player.as_str(), seat.sitting_out)).collect();
+    assert_eq!(seated, vec![("fishy (mc)", false), ("Bob", true), ("carol99", false), ("Dave", false)]);
+
+    assert_eq!(hand.hero.as_deref(), Some("carol99"));
+    assert_eq!(hand.cards_of("carol99"), Some(&cards("ah kd")));
+    assert_eq!(hand.cards_of("fishy (mc)"), Some(&cards("kh 7c")));
+    assert!(hand.holdings.iter().all(|holding| holding.shown));
+    assert_eq!(hand.board, cards("kc 7h 2d 9s qs"));
+    assert!(hand.showdown);
+
+    let acts: Vec<(&str, Street, Act, bool)> = hand.actions.iter()
+                                                           .map(|action| (action.player.as_str(), action.street, action.act, action.all_in))
+                                                           .collect();
+    assert_eq!(acts, vec![
+      ("Dave", Street::Preflop, Act::SmallBlind(0.05), false),
+      ("fishy (mc)", Street::Preflop, Act::BigBlind(0.10), false),
+      ("carol99", Street::Preflop, Act::Raise { by: 0.20, to: 0.30 }, false),
+      ("Dave", Street::Preflop, Act::Fold, false),
+      ("fishy (mc)", Street::Preflop, Act::Call(0.20), false),
+      ("fishy (mc)", Street::Flop, Act::Check, false),
+      ("carol99", Street::Flop, Act::Bet(0.40), false),
+      ("fishy (mc)", Street::Flop, Act::Call(0.40), false),
+      ("fishy (mc)", Street::Turn, Act::Check, false),
+      ("carol99", Street::Turn, Act::Bet(3.45), true),
+      ("fishy (mc)", Street::Turn, Act::Call(3.45), false)
+    ]);
+
+    assert!(close(hand.total_pot, 8.35) && close(hand.rake, 0.37));
+    assert_eq!(hand.collected, vec![(String::from("fishy (mc)"), 7.98)]);
+    assert!(close(hand.invested("carol99"), 4.15) && close(hand.net("carol99"), -4.15));
+    assert!(close(hand.net("fishy (mc)"), 7.98 - 4.15));
+    assert!(close(hand.net("Dave"), -0.05) && close(hand.net("Bob"), 0.0));
+  }
+
+  #[test]
+  fn not_enough_players_dealt_in()
+  {
+    let text = "PokerStars Hand #1:  Hold'em No Limit ($0.05/$0.10 USD) - 2018/04/14 21:05:31 ET\n\
+                Table 'Aaltje II' 6-max Seat #1 is the button\n\
+                Seat 1: Alice ($10 in chips)\n\
+                Seat 2: Bob ($10 in chips) is sitting out\n";
+    assert!(HandHistory::parse(text).is_err());
+  }
+
+  #[test]
+  fn text_to_json_and_back()
+  {
+    let hand = HandHistory::parse(SAMPLE).unwrap();
+    let json = Json::parse(&hand.to_json().to_text()).unwrap();
+    let copy = HandHistory::from_json(&json).unwrap();
+    assert_eq!(copy, hand);
+    assert_eq!(copy.to_text(), SAMPLE);
+  }
+}
@@ -0,0 +1,182 @@
+/* icm.rs
+ * The Independent Chip Model: turn tournament chip stacks into shares of the prize
+ * money. Each player's chance of finishing first is their share of the chips in play;
+ * given who finished first, each remaining player's chance of finishing second is
+ * their share of the chips left, and so on down the paid places.
+ *
+ * Chips aren't worth the same in a tournament: losing your stack costs more prize
+ * money than doubling it wins, so calling an all in needs more equity than the pot
+ * odds alone suggest. required_equity() works out how much more.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use canon::choose;
+
+use std::collections::BTreeMap;
+
+/* most ways of filling the paid places to work through before giving up */
+const MOST_FINISHES: u64 = 10_000_000;
+
+/* equities
+   Work out each player's expected share of the prize money
+   => stacks = each player's chips, with zero for anyone who's busted but still to be paid
+      payouts = prize for each place, first place first
+   <= each player's equity in prize money, in the order given, or None if there are no
+      players with chips, a stack or prize is negative, or there are too many players and
+      paid places to work through */
+pub fn equities(stacks: &[f64], payouts: &[f64]) -> Option<Vec<f64>>
+{
+  if stacks.len() > 64 || stacks.iter().chain(payouts.iter()).any(|amount| *amount < 0.0 || !amount.is_finite())
+  {
+    return None;
+  }
+  let live: Vec<usize> = (0..stacks.len()).filter(|player| stacks[*player] > 0.0).collect();
+  let paid = payouts.len().min(live.len());
+  let finishes = (0..paid).fold(0u64, |total, placed| total.saturating_add(choose(live.len() as u64, placed as u64)));
+  if live.is_empty() || finishes > MOST_FINISHES
+  {
+    return None;
+  }
+
+  /* work down the places, keeping the chance of each set of players filling the places so
+     far, in any order. each player still to place takes the next place in proportion to
+     their chips */
+  let total: f64 = live.iter().map(|player| stacks[*player]).sum();
+  let mut equity = vec![0.0; stacks.len()];
+  let mut placed = BTreeMap::<u64, f64>::new();
+  placed.insert(0, 1.0);
+  for prize in payouts.iter().take(paid)
+  {
+    let mut next = BTreeMap::<u64, f64>::new();
+    for (set, chance) in placed.iter()
+    {
+      let left = total - live.iter().enumerate()
+                                    .filter(|(bit, _)| set & 1 << bit != 0)
+                                    .map(|(_, player)| stacks[*player])
+                                    .sum::<f64>();
+      for (bit, player) in live.iter().enumerate().filter(|(bit, _)| set & 1 << bit == 0)
+      {
+        let finish = chance * stacks[*player] / left;
+        equity[*player] += finish * prize;
+        *next.entry(set | 1 << bit).or_insert(0.0) += finish;
+      }
+    }
+    placed = next;
+  }
+
+  /* players with no chips take the places below everyone else's, sharing their prizes */
+  let busted = stacks.len() - live.len();
+  if busted > 0
+  {
+    /* fold from zero rather than sum(), which gives -0.0 when no places are left to share */
+    let prizes = payouts.iter().skip(live.len()).take(busted).fold(0.0, |prizes, prize| prizes + prize);
+    for (player, stack) in stacks.iter().enumerate()
+    {
+      if *stack == 0.0
+      {
+        equity[player] = prizes / busted as f64;
+      }
+    }
+  }
+  Some(equity)
+}
+
+/* required_equity
+   Work out the share of the pot we need to win for calling an all in to be worth as much
+   prize money as folding. If the bet is more than we have, we can only call what we have
+   and the bettor gets the rest back
+   => stacks = each player's chips behind, not counting what's already in the pot
+      payouts = prize for each place, first place first
+      hero = index of our stack
+      bettor = index of the stack of the player who's all in
+      pot = chips in the pot, including the bet we face
+      call = chips it costs us to call, before capping at our stack
+   <= equity needed to call, from 0.0 to 1.0, or None if the players aren't different, the
+      call can't be more than the pot, or the prizes can't be worked out or don't depend on
+      the result */
+pub fn required_equity(stacks: &[f64], payouts: &[f64], hero: usize, bettor: usize, pot: f64, call: f64) -> Option<f64>
+{
+  if hero == bettor || hero >= stacks.len() || bettor >= stacks.len() || call < 0.0 || pot < call
+  {
+    return None;
+  }
+  let called = call.min(stacks[hero]);
+  let uncalled = call - called;
+
+  /* our prize equity after folding, and after calling and winning or losing */
+  let mut fold = stacks.to_vec();
+  fold[bettor] += pot;
+  let mut win = stacks.to_vec();
+  win[hero] = win[hero] + pot - uncalled;
+  win[bettor] += uncalled;
+  let mut lose = stacks.to_vec();
+  lose[hero] -= called;
+  lose[bettor] = lose[bettor] + pot + called;
+
+  let fold = equities(&fold, payouts)?[hero];
+  let win = equities(&win, payouts)?[hero];
+  let lose = equities(&lose, payouts)?[hero];
+  if win <= lose
+  {
+    return None;
+  }
+  Some((fold - lose) / (win - lose))
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn close(a: f64, b: f64) -> bool
+  {
+    (a - b).abs() < 1e-9
+  }
+
+  #[test]
+  fn equal_stacks_share_equally()
+  {
+    let shares = equities(&[2000.0, 2000.0, 2000.0, 2000.0], &[50.0, 30.0, 20.0]).unwrap();
+    for share in shares.iter()
+    {
+      assert!(close(*share, 25.0), "{:?}", shares);
+    }
+  }
+
+  #[test]
+  fn all_the_chips_take_first_prize()
+  {
+    let shares = equities(&[0.0, 6000.0, 0.0], &[50.0, 30.0, 20.0]).unwrap();
+    assert!(close(shares[1], 50.0));
+    assert!(close(shares[0], 25.0) && close(shares[2], 25.0));
+
+    /* with fewer prizes than busted players, the ones left over win nothing, not -0 */
+    let shares = equities(&[6000.0, 0.0], &[50.0]).unwrap();
+    assert_eq!(shares[1].to_bits(), 0.0f64.to_bits());
+  }
+
+  #[test]
+  fn shares_add_up_to_the_prize_pool()
+  {
+    let payouts = [50.0, 30.0, 20.0];
+    for stacks in [vec![5000.0, 3000.0, 2000.0], vec![100.0, 9000.0, 450.0, 450.0, 1.0], vec![1.0, 1.0]].iter()
+    {
+      let shares = equities(stacks, &payouts).unwrap();
+      let paid: f64 = payouts.iter().take(stacks.len()).sum();
+      assert!(close(shares.iter().sum::<f64>(), paid), "{:?}", stacks);
+    }
+    let shares = equities(&[5000.0, 3000.0, 2000.0], &payouts).unwrap();
+    assert!(shares[0] > shares[1] && shares[1] > shares[2]);
+  }
+
+  #[test]
+  fn calls_for_more_than_our_stack_are_capped()
+  {
+    /* we can only call 1000 of the 3000 bet; the other 2000 goes back to the bettor */
+    let needed = required_equity(&[1000.0, 3000.0, 2000.0], &[50.0, 30.0, 20.0], 0, 1, 3300.0, 3000.0).unwrap();
+    let capped = required_equity(&[1000.0, 5000.0, 2000.0], &[50.0, 30.0, 20.0], 0, 1, 1300.0, 1000.0).unwrap();
+    assert!(close(needed, capped));
+    assert!(needed > 1000.0 / 2300.0 && needed < 1.0);
+  }
+}
@@ -0,0 +1,355 @@
+/* json.rs
+ * Just enough JSON to export results, and read them back in, without pulling in a dependency
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json
+{
+  Null,
+  Bool(bool),
+  Number(f64),
+  String(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>) /* keys are kept in the order they were added */
+}
+
+impl Json
+{
+  /* create an object from a list of keys and values */
+  pub fn object(fields: Vec<(&str, Json)>) -> Json
+  {
+    Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+  }
+
+  /* create a string value */
+  pub fn string(text: &str) -> Json
+  {
+    Json::String(text.to_string())
+  }
+
+  /* parse
+     Read a value from JSON text
+     => text = JSON text holding one value, with any whitespace around it
+     <= Json object, or None if the text isn't valid JSON */
+  pub fn parse(text: &str) -> Option<Json>
+  {
+    let chars: Vec<char> = text.chars().collect();
+    let mut reader = Reader { chars: &chars, next: 0 };
+    let value = reader.value()?;
+    reader.skip_whitespace();
+    if reader.next != chars.len()
+    {
+      return None;
+    }
+    Some(value)
+  }
+
+  /* return the value of an object's field, or None if there's no such field or this isn't an object */
+  pub fn get(&self, key: &str) -> Option<&Json>
+  {
+    match *self
+    {
+      Json::Object(ref fields) => fields.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+      _ => None
+    }
+  }
+
+  /* return the value as a number, or None if it isn't one */
+  pub fn as_f64(&self) -> Option<f64>
+  {
+    match *self
+    {
+      Json::Number(n) => Some(n),
+      _ => None
+    }
+  }
+
+  /* return the value as a string, or None if it isn't one */
+  pub fn as_str(&self) -> Option<&str>
+  {
+    match *self
+    {
+      Json::String(ref s) => Some(s),
+      _ => None
+    }
+  }
+
+  /* return the value as a boolean, or None if it isn't one */
+  pub fn as_bool(&self) -> Option<bool>
+  {
+    match *self
+    {
+      Json::Bool(b) => Some(b),
+      _ => None
+    }
+  }
+
+  /* return the items of an array, or None if it isn't one */
+  pub fn as_array(&self) -> Option<&Vec<Json>>
+  {
+    match *self
+    {
+      Json::Array(ref items) => Some(items),
+      _ => None
+    }
+  }
+
+  /* render the value as compact JSON text */
+  pub fn to_text(&self) -> String
+  {
+    let mut text = String::new();
+    self.write(&mut text);
+    text
+  }
+
+  fn write(&self, text: &mut String)
+  {
+    match *self
+    {
+      Json::Null => text.push_str("null"),
+      Json::Bool(b) => text.push_str(if b { "true" } else { "false" }),
+      Json::Number(n) =>
+      {
+        /* JSON has no room for infinities or NaNs */
+        if n.is_finite()
+        {
+          text.push_str(&format!("{}", n));
+        }
+        else
+        {
+          text.push_str("null");
+        }
+      },
+      Json::String(ref s) => write_string(s, text),
+      Json::Array(ref items) =>
+      {
+        text.push('[');
+        for (i, item) in items.iter().enumerate()
+        {
+          if i > 0
+          {
+            text.push(',');
+          }
+          item.write(text);
+        }
+        text.push(']');
+      },
+      Json::Object(ref fields) =>
+      {
+        text.push('{');
+        for (i, (key, value)) in fields.iter().enumerate()
+        {
+          if i > 0
+          {
+            text.push(',');
+          }
+          write_string(key, text);
+          text.push(':');
+          value.write(text);
+        }
+        text.push('}');
+      }
+    }
+  }
+}
+
+/* write out a quoted string, escaping as needed */
+fn write_string(s: &str, text: &mut String)
+{
+  text.push('"');
+  for c in s.chars()
+  {
+    match c
+    {
+      '"'  => text.push_str("\\\""),
+      '\\' => text.push_str("\\\\"),
+      '\n' => text.push_str("\\n"),
+      '\r' => text.push_str("\\r"),
+      '\t' => text.push_str("\\t"),
+      c if (c as u32) < 0x20 => text.push_str(&format!("\\u{:04x}", c as u32)),
+      c => text.push(c)
+    }
+  }
+  text.push('"');
+}
+
+/* reads JSON text a character at a time */
+struct Reader<'a>
+{
+  chars: &'a [char], /* the text being read */
+  next: usize        /* position of the next character to read */
+}
+
+impl<'a> Reader<'a>
+{
+  /* read any value, and the whitespace before it */
+  fn value(&mut self) -> Option<Json>
+  {
+    self.skip_whitespace();
+    match *self.chars.get(self.next)?
+    {
+      'n' => self.word("null", Json::Null),
+      't' => self.word("true", Json::Bool(true)),
+      'f' => self.word("false", Json::Bool(false)),
+      '"' => self.string().map(Json::String),
+      '[' =>
+      {
+        self.next += 1;
+        let mut items = Vec::<Json>::new();
+        if self.take(']')
+        {
+          return Some(Json::Array(items));
+        }
+        loop
+        {
+          items.push(self.value()?);
+          if self.take(']')
+          {
+            return Some(Json::Array(items));
+          }
+          self.expect(',')?;
+        }
+      },
+      '{' =>
+      {
+        self.next += 1;
+        let mut fields = Vec::<(String, Json)>::new();
+        if self.take('}')
+        {
+          return Some(Json::Object(fields));
+        }
+        loop
+        {
+          self.skip_whitespace();
+          let key = self.string()?;
+          self.expect(':')?;
+          fields.push((key, self.value()?));
+          if self.take('}')
+          {
+            return Some(Json::Object(fields));
+          }
+          self.expect(',')?;
+        }
+      },
+      _ => self.number()
+    }
+  }
+
+  /* read a keyword, eg, null, and return the value it stands for */
+  fn word(&mut self, word: &str, value: Json) -> Option<Json>
+  {
+    for c in word.chars()
+    {
+      if self.chars.get(self.next) != Some(&c)
+      {
+        return None;
+      }
+      self.next += 1;
+    }
+    Some(value)
+  }
+
+  /* read a number */
+  fn number(&mut self) -> Option<Json>
+  {
+    let start = self.next;
+    while self.chars.get(self.next).is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+    {
+      self.next += 1;
+    }
+    let text: String = self.chars[start..self.next].iter().collect();
+    text.parse::<f64>().ok().map(Json::Number)
+  }
+
+  /* read a quoted string, undoing any escapes */
+  fn string(&mut self) -> Option<String>
+  {
+    if self.chars.get(self.next) != Some(&'"')
+    {
+      return None;
+    }
+    self.next += 1;
+
+    let mut s = String::new();
+    loop
+    {
+      let c = *self.chars.get(self.next)?;
+      self.next += 1;
+      match c
+      {
+        '"' => return Some(s),
+        '\\' =>
+        {
+          let escaped = *self.chars.get(self.next)?;
+          self.next += 1;
+          match escaped
+          {
+            'n' => s.push('\n'),
+            'r' => s.push('\r'),
+            't' => s.push('\t'),
+            'b' => s.push('\u{8}'),
+            'f' => s.push('\u{c}'),
+            'u' =>
+            {
+              let code = self.hex()?;
+              /* characters beyond the basic plane come as a pair of surrogates */
+              let code = match code
+              {
+                0xd800..=0xdbff =>
+                {
+                  self.word("\\u", Json::Null)?;
+                  let low = self.hex()?;
+                  0x10000 + ((code - 0xd800) << 10) + (low.checked_sub(0xdc00)? & 0x3ff)
+                },
+                _ => code
+              };
+              s.push(char::from_u32(code)?);
+            },
+            other => s.push(other)
+          }
+        },
+        c => s.push(c)
+      }
+    }
+  }
+
+  /* read the four hex digits of a \u escape */
+  fn hex(&mut self) -> Option<u32>
+  {
+    let digits: String = self.chars.get(self.next..self.next + 4)?.iter().collect();
+    self.next += 4;
+    u32::from_str_radix(&digits, 16).ok()
+  }
+
+  /* skip whitespace, then step over the given character, or fail if it isn't next */
+  fn expect(&mut self, c: char) -> Option<()>
+  {
+    match self.take(c)
+    {
+      true => Some(()),
+      false => None
+    }
+  }
+
+  /* skip whitespace, then step over the given character if it's next */
+  fn take(&mut self, c: char) -> bool
+  {
+    self.skip_whitespace();
+    if self.chars.get(self.next) == Some(&c)
+    {
+      self.next += 1;
+      return true;
+    }
+    false
+  }
+
+  fn skip_whitespace(&mut self)
+  {
+    while self.chars.get(self.next).is_some_and(|c| c.is_whitespace())
+    {
+      self.next += 1;
+    }
+  }
+}
@@ -0,0 +1,36 @@
+/* pokercalc library
+ * The cards, decks, hands and analysis behind the pokercalc tool, so other
+ * programs can build on them
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+pub mod card;
+pub mod deck;
+pub mod hand;
+pub mod canon;
+pub mod texture;
+pub mod range;
+pub mod metrics;
+pub mod histogram;
+pub mod json;
+pub mod equity;
+pub mod parallel;
+pub mod rules;
+pub mod low;
+pub mod rng;
+pub mod stud;
+pub mod draw;
+pub mod pineapple;
+pub mod replay;
+pub mod history;
+pub mod luck;
+pub mod stats;
+pub mod table;
+pub mod showdown;
+pub mod bot;
+pub mod simulate;
+pub mod preflop;
+pub mod pushfold;
+pub mod river;
+pub mod icm;
@@ -0,0 +1,312 @@
+/* low.rs
+ * Score low hands for split-pot games. An ace-to-five low is the five lowest different
+ * values in the hand, with aces counting as one and straights and flushes not counting
+ * against it, so the best possible low is 5-4-3-2-A, the wheel. In eight or better
+ * games, a low only qualifies if all five of its cards are eight or lower.
+ *
+ * Low scores are turned around so that, like Hand::score(), a higher score is a
+ * better hand. A hand that can't make a qualifying low has no score at all.
+ *
+ * In lowball games, such as razz, the lowest hand takes the whole pot and every hand
+ * has a low, however bad: pairs and worse count against it rather than disqualifying it.
+ * Ace-to-five lowball ignores straights and flushes and plays aces low, so the best hand
+ * is 5-4-3-2-A. Deuce-to-seven lowball is the opposite of a normal high hand, with
+ * straights and flushes counting against you and aces always high, so the best hand
+ * is 7-5-4-3-2 in mixed suits.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use card::Value;
+use deck::combinations;
+use hand::Hand;
+
+/* highest value a card may have in an eight or better low */
+pub const EIGHT_OR_BETTER: u32 = 8;
+
+/* largest packed set of five low values, used to turn scores around */
+const WORST: u32 = 0xfffff;
+
+/* largest packed lowball hand: a type of hand above five low values */
+const WORST_LOWBALL: u32 = 0xffffff;
+
+/* ways of playing for low when the lowest hand wins the whole pot */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lowball
+{
+  AceToFive,  /* as in razz: aces are low, and straights and flushes don't count */
+  DeuceToSeven /* the worst high hand wins: aces are high, and straights and flushes count */
+}
+
+impl Lowball
+{
+  /* score
+     Score the best low a player can make from their cards, pairs and all
+     => cards = two to seven cards
+     <= score, higher is better. hands of different sizes aren't comparable */
+  pub fn score(&self, cards: &[Card]) -> u32
+  {
+    let size = if cards.len() < 5 { cards.len() } else { 5 };
+    let mut best = WORST_LOWBALL;
+    combinations(cards, size, |picked|
+    {
+      let packed = match *self
+      {
+        Lowball::AceToFive => pack_ace_to_five(picked),
+        Lowball::DeuceToSeven => pack_deuce_to_seven(picked)
+      };
+      if packed < best
+      {
+        best = packed;
+      }
+    });
+    WORST_LOWBALL - best
+  }
+
+  /* is a lowball hand made: no pair, and in deuce-to-seven no straight or flush either? */
+  pub fn made(&self, score: u32) -> bool
+  {
+    (WORST_LOWBALL - score) >> 20 == 0
+  }
+
+  /* top
+     Find the top card of a made lowball hand, which is how lows are roughly graded,
+     eg, 7-5-4-3-2 is a seven low
+     => score = score of a hand under this lowball game
+     <= value of the top card, with one being an ace in ace-to-five, or None if the
+        hand isn't made */
+  pub fn top(&self, score: u32) -> Option<u32>
+  {
+    let packed = WORST_LOWBALL - score;
+    match packed >> 20
+    {
+      0 => Some((packed >> 16) & 0xf),
+      _ => None
+    }
+  }
+
+  /* describe
+     Describe a lowball hand, eg "seven-five low" or "pair of fours"
+     => score = score of a hand under this lowball game
+     <= string describing the hand */
+  pub fn describe(&self, score: u32) -> String
+  {
+    let packed = WORST_LOWBALL - score;
+    let first = || value_name((packed >> 16) & 0xf);
+    let second = || value_name((packed >> 12) & 0xf);
+    match (*self, packed >> 20)
+    {
+      (_, 0) => format!("{}-{} low", first(), second()),
+      (_, 1) => format!("pair of {}s", first()),
+      (_, 2) => format!("two pair, {}s and {}s", first(), second()),
+      (_, 3) => format!("three of a kind, {}s", first()),
+      (Lowball::AceToFive, 4) => format!("full house, {}s over {}s", first(), second()),
+      (Lowball::AceToFive, _) => format!("four of a kind, {}s", first()),
+      (Lowball::DeuceToSeven, 4) => format!("straight, {} high", first()),
+      (Lowball::DeuceToSeven, 5) => format!("flush, {} high", first()),
+      (Lowball::DeuceToSeven, 6) => format!("full house, {}s over {}s", first(), second()),
+      (Lowball::DeuceToSeven, 7) => format!("four of a kind, {}s", first()),
+      (Lowball::DeuceToSeven, 8) => format!("straight flush, {} high", first()),
+      (Lowball::DeuceToSeven, _) => "royal flush".to_string()
+    }
+  }
+}
+
+/* ace_to_five
+   Score the best ace-to-five low from a set of cards
+   => cards = cards to pick the low from
+      qualifier = highest value allowed in the low, eg EIGHT_OR_BETTER, or None for any
+   <= score, higher is better, or None if there aren't five different values that qualify */
+pub fn ace_to_five(cards: &[Card], qualifier: Option<u32>) -> Option<u32>
+{
+  /* values present, with aces as one */
+  let mut mask = 0u32;
+  for card in cards.iter()
+  {
+    mask |= 1 << low_value(card.value());
+  }
+
+  /* take the five lowest, packing them highest first so a smaller number is a better low */
+  let mut picked = Vec::<u32>::with_capacity(5);
+  for value in 1..14
+  {
+    if picked.len() < 5 && mask & (1 << value) != 0
+    {
+      picked.push(value);
+    }
+  }
+
+  if picked.len() < 5
+  {
+    return None;
+  }
+
+  if let Some(highest) = qualifier
+  {
+    if picked[4] > highest
+    {
+      return None;
+    }
+  }
+
+  let mut packed = 0;
+  for value in picked.iter().rev()
+  {
+    packed = (packed << 4) | value;
+  }
+  Some(WORST - packed)
+}
+
+/* describe
+   Describe a low hand by its top two cards, eg "eight-six low"
+   => score = score of an ace-to-five low
+   <= string describing the low */
+pub fn describe(score: u32) -> String
+{
+  let packed = WORST - score;
+  let top = (packed >> 16) & 0xf;
+  let next = (packed >> 12) & 0xf;
+  format!("{}-{} low", value_name(top), value_name(next))
+}
+
+/* pack_ace_to_five
+   Pack up to five cards as an ace-to-five lowball hand, so that a smaller number
+   is a better low
+   => cards = up to five cards
+   <= type of hand (0 for no pair up to 5 for four of a kind) in bits 20-23, then
+      the values that decide between hands of that type, most important first,
+      with the biggest group first and higher values first within a group */
+fn pack_ace_to_five(cards: &[Card]) -> u32
+{
+  let mut totals = [0u32; 14];
+  for card in cards.iter()
+  {
+    let value = low_value(card.value()) as usize;
+    totals[value] += 1;
+  }
+
+  let mut packed = 0;
+  let mut position = 5;
+  for size in (1..5).rev()
+  {
+    for value in (1..14).rev()
+    {
+      if totals[value] == size
+      {
+        position -= 1;
+        packed |= (value as u32) << (position * 4);
+      }
+    }
+  }
+
+  /* the type of hand follows from the biggest group and how many groups there are */
+  let groups = totals.iter().filter(|t| **t > 0).count();
+  let largest = *totals.iter().max().unwrap();
+  let kind = match (largest, cards.len() - groups)
+  {
+    (4, _) => 5,
+    (3, 3) => 4,
+    (3, _) => 3,
+    (2, 2) => 2,
+    (2, _) => 1,
+    _ => 0
+  };
+  (kind << 20) | packed
+}
+
+/* pack_deuce_to_seven
+   Pack up to five cards as a deuce-to-seven lowball hand, so that a smaller number
+   is a better low. This is the hand's usual high score, except that aces are only
+   ever high, so A-2-3-4-5 is ace high, or an ace-high flush, rather than a straight
+   => cards = up to five cards
+   <= packed hand, laid out as Hand::score() */
+fn pack_deuce_to_seven(cards: &[Card]) -> u32
+{
+  let packed = Hand::score_cards(cards);
+  let wheel = (packed >> 16) & 0xf == 5;
+  match packed >> 20
+  {
+    4 if wheel => 0xe5432,
+    8 if wheel => (5 << 20) | 0xe5432,
+    _ => packed
+  }
+}
+
+/* value of a card in a low hand, with aces as one */
+fn low_value(value: Value) -> u32
+{
+  match value
+  {
+    Value::Ace => 1,
+    _ => value.to_u32()
+  }
+}
+
+/* name of a low card value, with one being an ace */
+pub fn value_name(value: u32) -> &'static str
+{
+  match value
+  {
+    1 => Value::Ace.to_str(),
+    _ => Value::from_u32(value).unwrap().to_str()
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  fn deuce_to_seven(text: &str) -> u32
+  {
+    Lowball::DeuceToSeven.score(&cards(text))
+  }
+
+  #[test]
+  fn deuce_to_seven_ordering()
+  {
+    /* best first: the number one, then rougher lows, then ace high, which isn't a
+       straight here, then a straight, a flush and a pair, which all count against you */
+    let hands = ["7h 5d 4c 3s 2h",
+                 "7h 6d 4c 3s 2h",
+                 "8h 5d 4c 3s 2h",
+                 "kh qd jc 9s 8h",
+                 "ah 5d 4c 3s 2h",
+                 "ah kd qc js 9h",
+                 "2h 2d 3c 4s 5h",
+                 "6h 5d 4c 3s 2h",
+                 "7h 5h 4h 3h 2h"];
+    for pair in hands.windows(2)
+    {
+      assert!(deuce_to_seven(pair[0]) > deuce_to_seven(pair[1]), "{} should beat {}", pair[0], pair[1]);
+    }
+
+    let lowball = Lowball::DeuceToSeven;
+    assert!(lowball.made(deuce_to_seven("ah 5d 4c 3s 2h")));
+    assert!(!lowball.made(deuce_to_seven("6h 5d 4c 3s 2h")));
+    assert!(!lowball.made(deuce_to_seven("7h 5h 4h 3h 2h")));
+    assert_eq!(lowball.describe(deuce_to_seven("6h 5d 4c 3s 2h")), "straight, six high");
+    assert_eq!(lowball.describe(deuce_to_seven("7h 5h 4h 3h 2h")), "flush, seven high");
+  }
+
+  #[test]
+  fn seven_five_low()
+  {
+    let hand = cards("7h 5d 4c 3s 2h");
+    assert_eq!(Lowball::DeuceToSeven.describe(Lowball::DeuceToSeven.score(&hand)), "seven-five low");
+    assert_eq!(Lowball::AceToFive.describe(Lowball::AceToFive.score(&hand)), "seven-five low");
+    assert_eq!(describe(ace_to_five(&hand, Some(EIGHT_OR_BETTER)).unwrap()), "seven-five low");
+    assert_eq!(Lowball::DeuceToSeven.top(Lowball::DeuceToSeven.score(&hand)), Some(7));
+
+    /* aces play low in ace-to-five, so A-2-3-4-7 is a seven-four */
+    let hand = cards("7h ad 4c 3s 2h");
+    assert_eq!(Lowball::AceToFive.describe(Lowball::AceToFive.score(&hand)), "seven-four low");
+    assert_eq!(Lowball::DeuceToSeven.describe(Lowball::DeuceToSeven.score(&hand)), "ace-seven low");
+  }
+}
@@ -0,0 +1,191 @@
+/* luck.rs
+ * All-in adjusted expected value: when players get all their chips in before the river,
+ * what they win depends on the cards to come, not how they played. Here we compare what
+ * each player won in those hands with what their equity at the moment they got it in
+ * entitled them to, and the difference is their luck.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use equity;
+use history::HandHistory;
+use history::Street;
+
+/* how one player fared in an all-in */
+#[derive(Debug, Clone)]
+pub struct Player
+{
+  pub name: String,     /* player's screen name */
+  pub cards: Vec<Card>, /* their hole cards */
+  pub equity: f64,      /* their share of the main pot as the money went in, from 0.0 to 1.0 */
+  pub expected: f64,    /* what their equity in each pot was worth, after rake */
+  pub won: f64          /* what they actually collected */
+}
+
+impl Player
+{
+  /* return how much more than expected the player won, in big blinds, or less if negative */
+  pub fn luck(&self, big_blind: f64) -> f64
+  {
+    (self.won - self.expected) / big_blind
+  }
+}
+
+/* a hand in which the money went in before the river */
+#[derive(Debug, Clone)]
+pub struct AllIn
+{
+  pub id: String,          /* hand number */
+  pub street: Street,      /* street the last of the money went in on */
+  pub big_blind: f64,      /* size of the big blind, to measure luck against */
+  pub players: Vec<Player> /* everyone who saw it through to showdown */
+}
+
+/* all_in
+   Work out each player's expected and actual winnings in a hand where they got all
+   their chips in before the river
+   => hand = hand history to look at
+   <= AllIn object, or None if the hand wasn't an all-in before the river, went to
+      showdown without every player's cards being seen, or isn't a game we can analyse */
+pub fn all_in(hand: &HandHistory) -> Option<AllIn>
+{
+  let rules = hand.rules()?;
+
+  /* the money's all in once the betting stops, so there's no betting after this street */
+  let street = hand.actions.last()?.street;
+  if !hand.showdown || street == Street::River || hand.board.len() != 5 ||
+     !hand.actions.iter().any(|action| action.all_in)
+  {
+    return None;
+  }
+
+  /* everyone still in at the end needs to have shown their cards */
+  let mut contenders = Vec::<(String, Vec<Card>)>::new();
+  for seat in hand.seats.iter().filter(|seat| !hand.folded(&seat.player) && hand.invested(&seat.player) > 0.0)
+  {
+    contenders.push((seat.player.clone(), hand.cards_of(&seat.player)?.clone()));
+  }
+  if contenders.len() < 2
+  {
+    return None;
+  }
+
+  /* share the pot out in proportion to equity, taking rake out as it was taken from the pot */
+  let invested: f64 = hand.seats.iter().map(|seat| hand.invested(&seat.player)).sum();
+  let collected: f64 = hand.collected.iter().map(|(_, amount)| amount).sum();
+  if invested <= 0.0
+  {
+    return None;
+  }
+  let kept = collected / invested;
+
+  let board = &hand.board[0..street.board_cards()];
+  let mut players: Vec<Player> = contenders.iter().map(|(name, cards)| Player
+  {
+    name: name.clone(),
+    cards: cards.clone(),
+    equity: 0.0,
+    expected: 0.0,
+    won: hand.won(name)
+  }).collect();
+
+  for (index, (size, eligible)) in pots(hand, &contenders).iter().enumerate()
+  {
+    let cards: Vec<Vec<Card>> = eligible.iter().map(|player| contenders[*player].1.clone()).collect();
+    let equities = equity::enumerate(&rules, &cards, board, &[])?;
+    for (player, equity) in eligible.iter().zip(equities.iter())
+    {
+      players[*player].expected += size * kept * equity.equity;
+      if index == 0
+      {
+        players[*player].equity = equity.equity;
+      }
+    }
+  }
+
+  Some(AllIn { id: hand.id.clone(), street, big_blind: hand.big_blind, players })
+}
+
+/* split the chips put in into the main pot and any side pots, each with the size of the pot
+   and which contenders can win it. folded players' chips go to the pots they contributed to */
+fn pots(hand: &HandHistory, contenders: &[(String, Vec<Card>)]) -> Vec<(f64, Vec<usize>)>
+{
+  let everyone: Vec<f64> = hand.seats.iter().map(|seat| hand.invested(&seat.player)).collect();
+  let covered: Vec<f64> = contenders.iter().map(|(name, _)| hand.invested(name)).collect();
+
+  let mut levels = covered.clone();
+  levels.sort_by(|a, b| a.partial_cmp(b).unwrap());
+  levels.dedup();
+
+  let mut pots = Vec::<(f64, Vec<usize>)>::new();
+  let mut below = 0.0;
+  for level in levels.iter()
+  {
+    let size: f64 = everyone.iter().map(|chips| chips.min(*level) - chips.min(below)).sum();
+    let eligible = (0..contenders.len()).filter(|player| covered[*player] >= *level).collect();
+    pots.push((size, eligible));
+    below = *level;
+  }
+  pots
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  const SAMPLE: &str = "\
+PokerStars Hand #184760011223:  Hold'em No Limit ($0.05/$0.10 USD) - 2018/04/14 22:12:40 CET [2018/04/14 16:12:40 ET]
+Table 'Aaltje II' 6-max Seat #1 is the button
+Seat 1: Alice ($10 in chips)
+Seat 2: Bob ($10 in chips)
+Alice: posts small blind $0.05
+Bob: posts big blind $0.10
+*** HOLE CARDS ***
+Dealt to Alice [Ah Kh]
+Alice: raises $0.20 to $0.30
+Bob: calls $0.20
+*** FLOP *** [Qh 7h 2c]
+Bob: checks
+Alice: bets $0.40
+Bob: raises $0.80 to $1.20
+Alice: calls $0.80
+*** TURN *** [Qh 7h 2c] [9d]
+Bob: bets $8.50 and is all-in
+Alice: calls $8.50 and is all-in
+*** RIVER *** [Qh 7h 2c 9d] [3s]
+*** SHOW DOWN ***
+Bob: shows [Qs Qd] (three of a kind, Queens)
+Alice: shows [Ah Kh] (high card Ace)
+Bob collected $19 from pot
+*** SUMMARY ***
+Total pot $20 | Rake $1
+Board [Qh 7h 2c 9d 3s]
+Seat 1: Alice (button) (small blind) showed [Ah Kh] and lost with high card Ace
+Seat 2: Bob (big blind) showed [Qs Qd] and won ($19) with three of a kind, Queens
+";
+
+  fn close(a: f64, b: f64) -> bool
+  {
+    (a - b).abs() < 1e-9
+  }
+
+  #[test]
+  fn turn_all_in()
+  {
+    let hand = HandHistory::parse(SAMPLE).unwrap();
+    let all_in = all_in(&hand).unwrap();
+    assert_eq!(all_in.street, Street::Turn);
+
+    /* Alice has 44 rivers to hit and 7 of them win: the nine hearts left, less the 2h and 9h,
+       which fill Bob up. $20 went in and $19 came out after rake, so she was due 19 * 7/44 */
+    let alice = &all_in.players[0];
+    let bob = &all_in.players[1];
+    assert_eq!((alice.name.as_str(), bob.name.as_str()), ("Alice", "Bob"));
+    assert!(close(alice.equity, 7.0 / 44.0) && close(bob.equity, 37.0 / 44.0));
+    assert!(close(alice.expected, 19.0 * 7.0 / 44.0) && close(bob.expected, 19.0 * 37.0 / 44.0));
+    assert!(close(alice.won, 0.0) && close(bob.won, 19.0));
+    assert!(close(alice.luck(0.10), -1330.0 / 44.0) && close(bob.luck(0.10), 1330.0 / 44.0));
+  }
+}
@@ -10,129 +10,1355 @@
  *
  * Ad 9h = your hole cards, Js Jc 3h = the flop, Ks = the turn, 7c = the river
  *
+ * The hole cards and board can also be separated by a |, and cards seen flashed or
+ * mucked by other players can be added after | dead: so that they're left out of
+ * the deck and your opponent's possible hands, for example:
+ * Ad 9h | Js Jc 3h | dead: 2c 3s
+ *
  * With the given cards, pokercalc works out all the two hole cards your opponent
  * needs to beat you, if possible, and lists those options, and percentage chance
  * of having those cards. Repeat until you hit Control-C or Control-D (or send an EOF)
  *
+ * Options:
+ * --histogram   also draw how your equity is spread over the turn and river to come
+ * --bins N      split equity histograms into N bins (default 10)
+ * --buckets K   instead, read in boards and cluster every possible hand on each
+ *               board into K buckets by equity histogram
+ * --json        print histograms and buckets as JSON rather than text
+ * --threads N   split the work across N threads (default one per core)
+ * --game NAME   play holdem (the default), or omaha, omaha5 or omaha6 for pot-limit
+ *               Omaha with four, five or six hole cards, of which you must use
+ *               exactly two with exactly three community cards, or omaha8 for Omaha
+ *               hi-lo, eight or better, or shortdeck for short deck (6+) hold 'em,
+ *               where A-6-7-8-9 is a straight and flushes beat full houses, or
+ *               shortdeck-trips for short deck with trips ranked above straights,
+ *               or stud or razz for seven-card stud and razz: enter your cards in
+ *               the order dealt, then each opponent's upcards, then any dead cards,
+ *               separated by |, eg: ah 2d 5c 7s | kd 9h | 3c qs | dead: 4h
+ *               or draw, 27draw, 27triple or a5triple for five-card draw, and single
+ *               and triple draw lowball: enter your five cards, then optionally your
+ *               opponent's five and any dead cards, eg: 7h 5d 4c 3s kh | dead: 2c
+ *               or pineapple or crazy-pineapple: enter three hole cards, then the
+ *               flop in crazy pineapple, to find the best discard before carrying
+ *               on as hold 'em with the discard out of play
+ * --samples N   estimate equity against a random hand from N deals (default 20000)
+ * --seed N      start the random deals from seed N (default 1)
+ * --jokers N    add one or two wild jokers to the deck, entered as xh and xs
+ * --wild VALUE  make every card of that value wild too, eg --wild 2 for deuces wild
+ * --five-of-a-kind  let wild cards make five of a kind, which beats a royal flush
+ * --replay      instead, read in a whole hold 'em hand and show how it went street by
+ *               street: enter your hole cards, your opponent's cards or range in the
+ *               usual shorthand, and the board, separated by |, and optionally any
+ *               dead cards, eg: ah kh | qq+, ak, ahqh | 2c 7d 9s ts qh | dead: 4c
+ * --history FILE  instead, read in a PokerStars hand history file and, for every hand
+ *               that went to showdown, show each player's hand and their equity as
+ *               each street was dealt
+ * --luck        with --history, instead list every hand that was all in before the
+ *               river, comparing what each player won with what their equity was
+ *               worth, and keep a running total of their luck in big blinds
+ * --stats       with --history, instead list each player's VPIP, PFR, 3-bet, fold to
+ *               3-bet, c-bet, WTSD and W$SD percentages, as a table or with --json
+ * --export FORMAT  with --history, instead write every hand back out, as PokerStars
+ *               text or as json. --history reads either back in
+ * --simulate N  instead, have bots play N hands of no-limit hold 'em or Omaha against
+ *               each other, shuffled from --seed, and show each bot's win rate in big
+ *               blinds per 100 hands, give or take a 95% confidence interval
+ * --bots LIST   with --simulate, the bots to seat, comma separated, from random,
+ *               station (a calling station) and equity (bets by its equity against
+ *               random hands), eg: equity,station,random (default equity,station)
+ * --stack N     with --simulate, start each hand with N big blinds (default 100)
+ * --pushfold N  instead, solve preflop push or fold play for stacks of N big blinds,
+ *               and show the range each player pushes or calls all ins with
+ * --players N   with --pushfold, solve heads up (2, the default) or three handed (3)
+ * --ante N      with --pushfold, each player antes N big blinds, eg 0.125
+ * --river       instead, solve river spots with CFR+: enter the ranges of the player
+ *               out of position and the player in position, the board, and the pot
+ *               and stacks behind in chips, separated by |, eg:
+ *               qq+, ak, 76s | 99+, aq, t9s | ah kd 7c 4s 2h | 100 400
+ *               and see how often each combo takes each action, and what it's worth
+ * --bets LIST   with --river, bet sizes as fractions of the pot (default 0.33,0.75)
+ * --raises LIST with --river, raise sizes as fractions of the pot once the bet's called
+ *               (default 1)
+ * --max-raises N  with --river, allow at most N raises after the first bet (default 2)
+ * --all-in      with --river, also allow betting and raising all in every time
+ * --iterations N  with --river, run N iterations (default 1000)
+ * --cfr         with --river, use plain CFR rather than CFR+
+ *
+ * --pot N       with --call, chips in the pot, including the bet you face
+ * --call N      chips it costs you to call: recommends calling or folding by whether
+ *               your equity against a random hand beats the pot odds
+ * --stacks LIST with --call and --payouts, the chips behind each player still in the
+ *               tournament, yours first and the bettor's second, comma separated, to
+ *               recommend by the equity needed under the Independent Chip Model instead.
+ *               Without --call, show each player's share of the prize money, eg:
+ *               --stacks 5000,3000,2000 --payouts 50,30,20
+ * --payouts LIST  with --stacks, the prize for each place, first place first
+ *
  * Hint: Use these percentages to decide whether it is worth calling a bet or raise,
  * based on the size of the amount to pay versus what's in the pot, and your opponent's range
  *
  * (c) Chris Williams, 2018. Open-source software: see LICENSE
  */
 
-mod card;
-mod hand;
-mod deck;
+extern crate pokercalc;
 
-use card::Card;
-use hand::Hand;
-use deck::Deck;
+use pokercalc::card::Card;
+use pokercalc::card::Value;
+use pokercalc::hand::Hand;
+use pokercalc::hand::Wilds;
+use pokercalc::hand::Ranking;
+use pokercalc::deck::Deck;
+use pokercalc::texture::Texture;
+use pokercalc::range::Range;
+use pokercalc::metrics::Metrics;
+use pokercalc::histogram::Histogram;
+use pokercalc::histogram::Buckets;
+use pokercalc::canon::Indexer;
+use pokercalc::deck::combinations;
+use pokercalc::json::Json;
+use pokercalc::parallel;
+use pokercalc::rules::Rules;
+use pokercalc::rules::Game;
+use pokercalc::stud::Stud;
+use pokercalc::draw::Draw;
+use pokercalc::pineapple;
+use pokercalc::canon::choose;
+use pokercalc::equity;
+use pokercalc::replay;
+use pokercalc::history;
+use pokercalc::history::HandHistory;
+use pokercalc::luck;
+use pokercalc::stats;
+use pokercalc::table::Blinds;
+use pokercalc::bot::RandomBot;
+use pokercalc::bot::CallingStation;
+use pokercalc::bot::EquityBot;
+use pokercalc::simulate::Simulator;
+use pokercalc::preflop::Equities;
+use pokercalc::pushfold;
+use pokercalc::river::River;
+use pokercalc::river::BetSizes;
+use pokercalc::table::Action;
+use pokercalc::icm;
 
 use std::io;
+use std::env;
+use std::fs;
+use std::process::exit;
+use std::collections::HashMap;
+
+/* number of the best discards to list in draw games */
+const DRAW_CHOICES_SHOWN: usize = 5;
+
+/* most rounds of k-means to run when bucketing */
+const BUCKET_ITERATIONS: usize = 100;
+
+/* chips in a big blind when simulating, with a small blind of half that */
+const SIMULATED_BIG_BLIND: u64 = 100;
+
+/* deals the equity bot samples each time it decides, and the equity it needs to call or raise */
+const EQUITY_BOT_SAMPLES: usize = 100;
+const EQUITY_BOT_CALL: f64 = 0.45;
+const EQUITY_BOT_RAISE: f64 = 0.65;
+
+/* rounds of play when solving push or fold */
+const PUSH_FOLD_ITERATIONS: usize = 1000;
+
+/* command line settings */
+struct Options
+{
+  histogram: bool,       /* draw an equity histogram for each hand */
+  bins: usize,           /* number of bins in equity histograms */
+  buckets: Option<usize>, /* cluster every hand on each board into this many buckets */
+  json: bool,            /* print histograms and buckets as JSON */
+  replay: bool,          /* replay whole hands street by street */
+  history: Option<String>, /* hand history file to read instead of stdin */
+  luck: bool,            /* report all-in luck from the hand history */
+  stats: bool,           /* report player statistics from the hand history */
+  export: Option<bool>,  /* write the hand history back out, as JSON if true, or text */
+  simulate: Option<usize>, /* hands for bots to play against each other */
+  bots: Vec<String>,     /* bots to seat in the simulation */
+  stack: usize,          /* big blinds each bot starts each simulated hand with */
+  pushfold: Option<f64>, /* stack in big blinds to solve push or fold play for */
+  players: usize,        /* players at the table when solving push or fold */
+  ante: f64,             /* each player's ante in big blinds when solving push or fold */
+  river: bool,           /* solve river spots */
+  sizes: BetSizes,       /* bets and raises allowed when solving the river */
+  iterations: usize,     /* iterations to run when solving the river */
+  plus: bool,            /* solve the river with CFR+ rather than plain CFR */
+  pot: Option<u64>,      /* chips in the pot, including the bet faced */
+  call: Option<u64>,     /* chips it costs to call, to recommend calling or folding */
+  stacks: Option<Vec<f64>>, /* chips behind each player in the tournament, ours then the bettor's */
+  payouts: Option<Vec<f64>>, /* prize money for each place */
+  rules: Rules,          /* game being played */
+  samples: usize,        /* number of deals to sample when estimating equity */
+  seed: u64              /* starting point for sampling random deals */
+}
+
+/* parse_options
+   Read the settings from the command line, bailing out with usage info if they
+   don't make sense
+   <= Options object */
+fn parse_options() -> Options
+{
+  let mut options = Options
+  {
+    histogram: false,
+    bins: 10,
+    buckets: None,
+    json: false,
+    replay: false,
+    history: None,
+    luck: false,
+    stats: false,
+    export: None,
+    simulate: None,
+    bots: vec!["equity".to_string(), "station".to_string()],
+    stack: 100,
+    pushfold: None,
+    players: 2,
+    ante: 0.0,
+    river: false,
+    sizes: BetSizes { bets: vec![0.33, 0.75], raises: vec![1.0], max_raises: 2, all_in: false },
+    iterations: 1000,
+    plus: true,
+    pot: None,
+    call: None,
+    stacks: None,
+    payouts: None,
+    rules: Rules::holdem(),
+    samples: 20000,
+    seed: 1
+  };
+
+  let mut wilds = Wilds::none();
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next()
+  {
+    match arg.as_str()
+    {
+      "--histogram" => options.histogram = true,
+      "--json" => options.json = true,
+      "--bins" => options.bins = number_arg(&arg, args.next()),
+      "--buckets" => options.buckets = Some(number_arg(&arg, args.next())),
+      "--threads" => parallel::set_threads(number_arg(&arg, args.next())),
+      "--game" =>
+      {
+        options.rules = match args.next().and_then(|name| Rules::from_name(&name))
+        {
+          Some(rules) => rules,
+          None => usage("--game needs one of holdem, omaha, omaha5, omaha6, omaha8, \
+                         shortdeck, shortdeck-trips, stud, razz, \
+                         draw, 27draw, 27triple, a5triple, pineapple or crazy-pineapple")
+        }
+      },
+      "--samples" => options.samples = number_arg(&arg, args.next()),
+      "--seed" => options.seed = number_arg(&arg, args.next()) as u64,
+      "--jokers" => wilds.jokers = number_arg(&arg, args.next()),
+      "--wild" =>
+      {
+        wilds.rank = match args.next().and_then(|rank| Card::new(&format!("{}h", rank.to_lowercase())))
+        {
+          Some(card) if card.value() != Value::Joker => Some(card.value()),
+          _ => usage("--wild needs a card value, one of 2-9, t, j, q, k or a")
+        }
+      },
+      "--five-of-a-kind" => wilds.five_of_a_kind = true,
+      "--replay" => options.replay = true,
+      "--luck" => options.luck = true,
+      "--stats" => options.stats = true,
+      "--export" =>
+      {
+        options.export = match args.next().as_deref()
+        {
+          Some("text") => Some(false),
+          Some("json") => Some(true),
+          _ => usage("--export needs a format, text or json")
+        }
+      },
+      "--simulate" => options.simulate = Some(number_arg(&arg, args.next())),
+      "--stack" => options.stack = number_arg(&arg, args.next()),
+      "--pushfold" => options.pushfold = Some(float_arg(&arg, args.next())),
+      "--ante" => options.ante = float_arg(&arg, args.next()),
+      "--players" =>
+      {
+        options.players = number_arg(&arg, args.next());
+        if options.players != 2 && options.players != 3
+        {
+          usage("--players needs 2 for heads up or 3 for three handed");
+        }
+      },
+      "--bots" =>
+      {
+        options.bots = match args.next()
+        {
+          Some(list) => list.split(',').map(|bot| bot.trim().to_lowercase()).collect(),
+          None => usage("--bots needs a comma separated list of random, station or equity")
+        };
+        if options.bots.len() < 2 || options.bots.iter().any(|bot| !["random", "station", "equity"].contains(&bot.as_str()))
+        {
+          usage("--bots needs two or more of random, station or equity, comma separated");
+        }
+      },
+      "--river" => options.river = true,
+      "--bets" => options.sizes.bets = fractions_arg(&arg, args.next()),
+      "--raises" => options.sizes.raises = fractions_arg(&arg, args.next()),
+      "--max-raises" =>
+      {
+        options.sizes.max_raises = match args.next().and_then(|a| a.parse::<usize>().ok())
+        {
+          Some(n) => n,
+          None => usage("--max-raises needs a number, zero or more")
+        }
+      },
+      "--all-in" => options.sizes.all_in = true,
+      "--iterations" => options.iterations = number_arg(&arg, args.next()),
+      "--cfr" => options.plus = false,
+      "--pot" => options.pot = Some(number_arg(&arg, args.next()) as u64),
+      "--call" => options.call = Some(number_arg(&arg, args.next()) as u64),
+      "--stacks" => options.stacks = Some(amounts_arg(&arg, args.next())),
+      "--payouts" => options.payouts = Some(amounts_arg(&arg, args.next())),
+      "--history" =>
+      {
+        options.history = match args.next()
+        {
+          Some(file) => Some(file),
+          None => usage("--history needs a hand history file to read")
+        }
+      },
+      _ => usage(&format!("Unknown option '{}'", arg))
+    }
+  }
+
+  if wilds.jokers > 2
+  {
+    usage("--jokers needs a number from 1 to 2");
+  }
+  if wilds.any() && (options.rules.hilo || options.rules.lowball.is_some())
+  {
+    usage("Wild cards only work in games played for high");
+  }
+  options.rules.wilds = wilds;
+
+  if options.buckets.is_some() && options.rules != Rules::holdem()
+  {
+    usage("--buckets only works with standard hold 'em");
+  }
+  if (options.luck || options.stats || options.export.is_some()) && options.history.is_none()
+  {
+    usage("--luck, --stats and --export need a hand history file to read with --history");
+  }
+  if [options.luck, options.stats, options.export.is_some()].iter().filter(|mode| **mode).count() > 1
+  {
+    usage("Pick one of --luck, --stats or --export");
+  }
+  if options.replay && (options.rules != Rules::holdem() || options.buckets.is_some())
+  {
+    usage("--replay only works with standard hold 'em, and not with --buckets");
+  }
+  let betting_game = match options.rules.game
+  {
+    Game::Holdem | Game::Omaha(_) => options.rules.lowball.is_none(),
+    _ => false
+  };
+  if options.simulate.is_some() && !betting_game
+  {
+    usage("--simulate only works with hold 'em and Omaha");
+  }
+  if options.pot.is_some() != options.call.is_some()
+  {
+    usage("--pot and --call go together");
+  }
+  if options.stacks.is_some() != options.payouts.is_some()
+  {
+    usage("--stacks and --payouts go together");
+  }
+  if options.call.is_some() && options.stacks.as_ref().is_some_and(|stacks| stacks.len() < 2)
+  {
+    usage("--stacks needs your stack and the bettor's, then anyone else's");
+  }
+  if (options.pushfold.is_some() || options.river) && options.rules != Rules::holdem()
+  {
+    usage("--pushfold and --river only work with standard hold 'em");
+  }
+
+  options
+}
+
+/* turn an option's argument into a number above zero, or bail out */
+fn number_arg(option: &str, arg: Option<String>) -> usize
+{
+  match arg.and_then(|a| a.parse::<usize>().ok())
+  {
+    Some(n) if n > 0 => n,
+    _ => usage(&format!("{} needs a number above zero", option))
+  }
+}
+
+/* turn an option's argument into a number of big blinds, zero or more, or bail out */
+fn float_arg(option: &str, arg: Option<String>) -> f64
+{
+  match arg.and_then(|a| a.parse::<f64>().ok())
+  {
+    Some(n) if n >= 0.0 && n.is_finite() => n,
+    _ => usage(&format!("{} needs a number of big blinds", option))
+  }
+}
+
+/* turn an option's argument into a list of fractions of the pot, eg 0.5,1, or bail out */
+fn fractions_arg(option: &str, arg: Option<String>) -> Vec<f64>
+{
+  let fractions = arg.and_then(|a| a.split(',').map(|f| f.trim().parse::<f64>().ok()).collect::<Option<Vec<f64>>>());
+  match fractions
+  {
+    Some(list) if list.iter().all(|f| *f > 0.0 && f.is_finite()) => list,
+    _ => usage(&format!("{} needs a comma separated list of fractions of the pot above zero, eg 0.5,1", option))
+  }
+}
+
+/* turn an option's argument into a list of chips or prizes, zero or more, or bail out */
+fn amounts_arg(option: &str, arg: Option<String>) -> Vec<f64>
+{
+  let amounts = arg.and_then(|a| a.split(',').map(|n| n.trim().parse::<f64>().ok()).collect::<Option<Vec<f64>>>());
+  match amounts
+  {
+    Some(list) if list.iter().all(|n| *n >= 0.0 && n.is_finite()) => list,
+    _ => usage(&format!("{} needs a comma separated list of amounts, eg 5000,3000,2000", option))
+  }
+}
+
+/* explain how to run the program, and exit */
+fn usage(problem: &str) -> !
+{
+  println!("{}", problem);
+  println!("Usage: pokercalc [--game NAME] [--histogram] [--bins N] [--buckets K] [--json] \
+            [--threads N] [--samples N] [--seed N] [--jokers N] [--wild VALUE] [--five-of-a-kind] [--replay] [--history FILE] [--luck] [--stats] [--export FORMAT] \
+            [--simulate N] [--bots LIST] [--stack N] [--pushfold N] [--players N] [--ante N] \
+            [--river] [--bets LIST] [--raises LIST] [--max-raises N] [--all-in] [--iterations N] [--cfr] \
+            [--pot N] [--call N] [--stacks LIST] [--payouts LIST]");
+  exit(1);
+}
 
 /* process
    Break up an input string of card descriptions into cards and then calculate required
-   cards to beat the player. Only accept 2, 5, 6 or 7 cards (two holes + flop,
-   two holes + flop + river, or two holes + flop + river + turn) */
-fn process(input: String)
+   cards to beat the player. Only accept the player's hole cards (two in hold 'em,
+   four or more in Omaha) followed by nothing, the flop, flop + turn, or flop + turn + river,
+   optionally separated by a |, and then optionally any dead cards after | dead: */
+fn process(input: String, options: &Options)
 {
-  let card_count = input.split_whitespace().count();
-  if card_count < 2 || card_count == 3 || card_count == 4 || card_count > 7
+  let rules = &options.rules;
+  let (mut hole, board, mut dead) = match parse_hand(rules, &input)
+  {
+    Some(parsed) => parsed,
+    None => return
+  };
+
+  let mut community = Hand::new();
+  for card in board.iter()
+  {
+    community.add(*card);
+  }
+
+  /* in pineapple games, make the best discard first, then carry on as hold 'em with
+     the thrown card dead */
+  let holdem = Rules::holdem();
+  let rules = match rules.game
+  {
+    Game::Pineapple(_) =>
+    {
+      let keep = match discard_pineapple(options, &hole, community.cards(), &dead)
+      {
+        Some(keep) => keep,
+        None => return
+      };
+      dead.extend(hole.iter().filter(|card| !keep.contains(card)));
+      hole = keep;
+      &holdem
+    },
+    _ => rules
+  };
+  let hole_count = rules.hole_cards();
+
+  /* cards that can't be in the deck or the opponent's hand */
+  let mut out = hole.clone();
+  out.extend_from_slice(&board);
+  out.extend_from_slice(&dead);
+
+  /* calculate the strength of the hand, and tell the player */
+  println!("Your hand: {}", rules.describe(&hole, community.cards()));
+  /* the texture counts straights with the usual A-2-3-4-5 wheel, so it's only shown for
+     the standard ranking */
+  if let Some(texture) = Texture::new(community.cards()).filter(|_| !rules.wilds.any() &&
+                                                                     rules.ranking == Ranking::Standard)
+  {
+    println!("Board: {}", texture.describe());
+  }
+
+  /* now create a deck excluding the cards we can see and the dead cards */
+  let mut deck = Deck::with_rules(rules);
+  deck.remove_all(&out);
+  let unseen = deck.cards().clone();
+  let unknown_cards = unseen.len();
+
+  /* iterate over hand combinations: take every set of hole cards from the remaining
+     deck and run them against the community cards to see which hole cards beat
+     the player's, which in hi-lo games means scooping them. each thread takes a first
+     hole card and runs it with every set of the others before it in the deck */
+  let results = parallel::map(unknown_cards, |first|
+  {
+    let mut outs = String::new();
+    let mut count = 0;
+    let mut opponent = vec![unseen[first]];
+    combinations(&unseen[0..first], hole_count - 1, |rest|
+    {
+      opponent.truncate(1);
+      opponent.extend_from_slice(rest);
+      if equity::scoops(rules, &opponent, &hole, community.cards())
+      {
+        outs.push_str("( ");
+        for card in opponent.iter()
+        {
+          outs.push_str(&card.describe());
+          outs.push(' ');
+        }
+        outs.push_str(") ");
+        count += 1;
+      }
+    });
+    (outs, count)
+  });
+
+  let mut opponent_outs = String::new();
+  let mut opponent_outs_count = 0;
+  for (outs, count) in results.iter().rev()
+  {
+    opponent_outs.push_str(outs);
+    opponent_outs_count += count;
+  }
+
+  /* work out the odds of being dealt one of these sets of hole cards from the deck
+     of unseen cards, out of all the sets that could be dealt */
+  let running_odds = opponent_outs_count as f64 /
+                     choose(unknown_cards as u64, hole_count as u64) as f64;
+
+  if opponent_outs_count > 20
+  {
+    println!("Opponent has potentially {} hands that can beat you", opponent_outs_count);
+  }
+  else
+  {
+    println!("Opponent needs: {}", opponent_outs);
+  }
+
+  println!("{:.2}% chance opponent has better cards", running_odds * 100.0);
+
+  /* estimate our equity against a random hand from a sample of deals */
+  let any_hand = Range::uniform_for(rules, &out);
+  if let Some(equity) = equity::sample(rules, &hole, community.cards(), &dead, &any_hand,
+                                       options.samples, options.seed)
+  {
+    println!("Against a random hand: {}",
+             if rules.hilo { equity.describe_hilo() } else { equity.describe() });
+    if let (Some(pot), Some(call)) = (options.pot, options.call)
+    {
+      recommend_call(equity.equity, pot, call, options);
+    }
+  }
+
+  /* look ahead to the turn and river against any two cards. these only apply
+     to standard hold 'em */
+  if *rules != Rules::holdem()
   {
-    println!("Invalid number of cards ({})", card_count);
     return;
   }
 
-  /* create two hand objects: one with the player's hole and community cards,
-     and the other with just the community cards - which will be used by the opponent */
-  let mut card_count = 0;
-  let mut hand = Hand::new();
-  let mut community = Hand::new();
-  for card_desc in input.split_whitespace()
+  let opponent_range = any_hand;
+  if let Some(metrics) = Metrics::new(&hole, community.cards(), &dead, &opponent_range)
   {
-    match card_count
+    println!("Against any two cards: {}", metrics.describe());
+  }
+
+  if options.histogram
+  {
+    if let Some(histogram) = Histogram::new(&hole, community.cards(), &dead, &opponent_range,
+                                            options.bins)
     {
-      /* first two hards go to the player */
-      0 | 1 => hand.add(Card::new(&card_desc).unwrap()),
+      match options.json
+      {
+        true => println!("{}", histogram.to_json().to_text()),
+        false => print!("Equity by the river:\n{}", histogram.ascii())
+      }
+    }
+  }
+}
 
-      /* all remaining cards are the player's and community cards */
-      _ =>
+/* recommend_call
+   Say whether to call or fold, by whether our equity beats the equity the call needs: the
+   pot odds, or in a tournament, the equity needed for calling to be worth as much prize
+   money as folding under the Independent Chip Model
+   => equity = our share of the pot if we call
+      pot = chips in the pot, including the bet we face
+      call = chips it costs us to call
+      options = stacks and payouts, if it's a tournament */
+fn recommend_call(equity: f64, pot: u64, call: u64, options: &Options)
+{
+  let pot_odds = call as f64 / (pot + call) as f64;
+  let (needed, by) = match (&options.stacks, &options.payouts)
+  {
+    (Some(stacks), Some(payouts)) =>
+    {
+      /* if we can't cover the bet, we're only calling our stack for what it can match */
+      let called = (call as f64).min(stacks[0]);
+      let pot_odds = called / (pot as f64 - call as f64 + 2.0 * called);
+      match icm::required_equity(stacks, payouts, 0, 1, pot as f64, call as f64)
       {
-        hand.add(Card::new(&card_desc).unwrap());
-        community.add(Card::new(&card_desc).unwrap());
+        Some(needed) if called < call as f64 =>
+          (needed, format!("under ICM, against {:.2}% by pot odds, all in for {} of it", pot_odds * 100.0, called)),
+        Some(needed) => (needed, format!("under ICM, against {:.2}% by pot odds", pot_odds * 100.0)),
+        None =>
+        {
+          println!("Can't work out the ICM equity needed: check the pot covers the call, and the stacks and payouts");
+          return;
+        }
       }
+    },
+    _ => (pot_odds, String::from("by pot odds"))
+  };
+  println!("Calling {} to win {} needs {:.2}% equity {}: {}", call, pot, needed * 100.0, by,
+           if equity >= needed { "call" } else { "fold" });
+}
+
+/* list each player's stack and their share of the prize money under the Independent Chip Model */
+fn report_icm(stacks: &[f64], payouts: &[f64])
+{
+  let equities = match icm::equities(stacks, payouts)
+  {
+    Some(equities) => equities,
+    None => usage("Can't work out ICM equities for those stacks and payouts")
+  };
+
+  let chips: f64 = stacks.iter().sum();
+  let prizes: f64 = payouts.iter().sum();
+  println!("{:<8} {:>12} {:>8} {:>12} {:>8}", "Player", "Stack", "Chips", "Equity", "Prizes");
+  for (player, (stack, equity)) in stacks.iter().zip(equities.iter()).enumerate()
+  {
+    println!("{:<8} {:>12} {:>7.2}% {:>12.2} {:>7.2}%", player + 1, stack, stack * 100.0 / chips, equity,
+             if prizes > 0.0 { equity * 100.0 / prizes } else { 0.0 });
+  }
+}
+
+/* discard_pineapple
+   Work out our equity with each discard from three pineapple hole cards, and tell
+   the player which is best
+   => options = command line settings, including the game
+      hole = our three hole cards
+      board = community cards: none in pineapple, or the flop in crazy pineapple
+      dead = other cards known to be out of play
+   <= the two hole cards to keep, or None if the cards don't suit the game */
+fn discard_pineapple(options: &Options, hole: &[Card], board: &[Card], dead: &[Card])
+  -> Option<Vec<Card>>
+{
+  let choices = pineapple::choices(&options.rules, hole, board, dead, options.samples, options.seed);
+  let choices = match choices
+  {
+    Some(choices) => choices,
+    None =>
+    {
+      println!("Invalid cards: enter three hole cards, then the flop in crazy pineapple");
+      return None;
     }
+  };
 
-    card_count = card_count + 1;
+  for choice in choices.iter()
+  {
+    println!("Discard {}: {}", choice.discard.describe(), choice.equity.describe());
   }
+  println!("Best play: discard {}", choices[0].discard.describe());
+  Some(choices[0].keep.clone())
+}
 
-  /* calculate the strength of the hand, and tell the player */
-  hand.calc();
-  println!("Your hand: {}", hand.describe());
+/* parse_hand
+   Split the cards into the player's hole cards and the community cards, which
+   will also be used by the opponent, and any dead cards. the hole cards and board
+   can be separated by a |, or just listed one after the other
+   => rules = game being played
+      input = card descriptions, eg: jd 9d | 7s ac 9h | dead: 2c 3s
+   <= hole cards, board and dead cards, or None if they're invalid */
+fn parse_hand(rules: &Rules, input: &str) -> Option<(Vec<Card>, Vec<Card>, Vec<Card>)>
+{
+  let hole_count = rules.hole_cards();
+  let (groups, dead) = parse_groups(rules, input)?;
+  let (hole, board) = match groups.len()
+  {
+    1 =>
+    {
+      let split = if groups[0].len() < hole_count { groups[0].len() } else { hole_count };
+      (groups[0][0..split].to_vec(), groups[0][split..].to_vec())
+    },
+    2 => (groups[0].clone(), groups[1].clone()),
+    _ =>
+    {
+      println!("Invalid input: enter your hole cards, then the board, then any dead cards, \
+                eg: jd 9d | 7s ac 9h | dead: 2c 3s");
+      return None;
+    }
+  };
 
-  /* now create a deck excluding the cards we can see */
-  let mut deck = Deck::new();
-  for card in hand.cards().iter()
+  if hole.len() != hole_count || (!board.is_empty() && board.len() < 3) || board.len() > 5
   {
-    deck.remove(card);
+    println!("Invalid number of cards ({})", hole.len() + board.len());
+    return None;
   }
 
-  let unknown_cards = deck.cards().len();
-  let mut running_odds = 0.0;
-  let mut opponent_outs = String::new();
-  let mut opponent_outs_count = 0;
+  /* a card can only be in one place: in our hand, on the board, or dead */
+  let mut known = hole.clone();
+  known.extend_from_slice(&board);
+  known.extend_from_slice(&dead);
+  if equity::has_duplicates(&known)
+  {
+    println!("Invalid cards: a card appears more than once in your hand, the board and the dead cards");
+    return None;
+  }
+  Some((hole, board, dead))
+}
 
-  /* iterate over hand combinations */
-  loop
+/* parse_groups
+   Break up an input string into groups of cards separated by a |, with any group
+   starting dead: holding cards known to be out of play, eg: ah 2d 5c 7s | kd 9h | dead: 4h
+   <= the groups of cards in order, and the dead cards, or None if a card is invalid */
+fn parse_groups(rules: &Rules, input: &str) -> Option<(Vec<Vec<Card>>, Vec<Card>)>
+{
+  let mut groups = Vec::<Vec<Card>>::new();
+  let mut dead = Vec::<Card>::new();
+  for text in input.split('|')
+  {
+    if text.trim().is_empty()
+    {
+      return None;
+    }
+
+    let (text, is_dead) = match text.trim().strip_prefix("dead:")
+    {
+      Some(rest) => (rest, true),
+      None => (text, false)
+    };
+
+    let mut cards = Vec::<Card>::new();
+    for card_desc in text.split_whitespace()
+    {
+      cards.push(parse_card(rules, card_desc)?);
+    }
+
+    match is_dead
+    {
+      true => dead.extend(cards),
+      false => groups.push(cards)
+    }
+  }
+  Some((groups, dead))
+}
+
+/* parse_card
+   Turn a card description into a card, turning away cards that aren't in the game's
+   deck: jokers unless the game has them, and the twos to fives in short deck
+   => rules = game being played
+      desc = card description, eg: ah
+   <= Card object, or None if it's invalid */
+fn parse_card(rules: &Rules, desc: &str) -> Option<Card>
+{
+  let card = Card::new(desc)?;
+  if card.value == Value::Joker && rules.wilds.jokers == 0
   {
-    /* grab this card, run it through the others, and remove it.
-       we're taking every two hole cards from the remaining deck and
-       running them against the community cards to see which hole cards
-       beat the player's */
-    match deck.cards().pop()
+    println!("Invalid card '{}': jokers are only in play with --jokers", desc);
+    return None;
+  }
+  if !Deck::with_rules(rules).cards().contains(&card)
+  {
+    println!("Invalid card '{}': it isn't in the deck for this game", desc);
+    return None;
+  }
+  Some(card)
+}
+
+/* process_stud
+   Break up an input string into our stud cards, each opponent's upcards and any dead
+   cards, then work out how likely each opponent is to be ahead of us now and our
+   equity by seventh street. Groups of cards are separated by a |, with our cards in
+   the order dealt first, then each opponent's upcards, then optionally the dead cards
+   after dead:, for example: ah 2d 5c 7s | kd 9h | 3c qs | dead: 4h */
+fn process_stud(input: String, options: &Options)
+{
+  let (mut groups, dead) = match parse_groups(&options.rules, &input)
+  {
+    Some(parsed) => parsed,
+    None => return
+  };
+  if groups.is_empty()
+  {
+    println!("Invalid hand: enter your three to seven cards, then each opponent's upcards, \
+              separated by |");
+    return;
+  }
+  let hero = groups.remove(0);
+  let upcards = groups;
+
+  let rules = &options.rules;
+  let stud = match Stud::new(rules, &hero, &upcards, &dead, options.samples, options.seed)
+  {
+    Some(stud) => stud,
+    None =>
     {
-      Some(hole1) =>
+      println!("Invalid cards: enter your three to seven cards, then each opponent's upcards, \
+                separated by |");
+      return;
+    }
+  };
+
+  println!("Your hand: {}", rules.describe(&hero, &[]));
+  for (up, beaten) in upcards.iter().zip(stud.beaten.iter())
+  {
+    let showing = up.iter().map(|c| c.describe()).collect::<Vec<String>>().join(" ");
+    println!("{:.2}% chance opponent showing {} has better cards", beaten * 100.0, showing);
+  }
+  println!("By seventh street: {}", stud.equity.describe());
+}
+
+/* process_draw
+   Break up an input string into our five cards, optionally the opponent's five cards
+   after a |, and any dead cards after dead:, then play out every discard and
+   recommend the best, for example: 7h 5d 4c 3s kh | dead: 2c */
+fn process_draw(input: String, options: &Options)
+{
+  let (groups, dead) = match parse_groups(&options.rules, &input)
+  {
+    Some(parsed) => parsed,
+    None => return
+  };
+  if groups.is_empty()
+  {
+    println!("Invalid cards: enter your five cards, then optionally your opponent's five, \
+              separated by |");
+    return;
+  }
+  let hand = &groups[0];
+  let opponent = groups.get(1).map(|cards| cards.as_slice());
+
+  let rules = &options.rules;
+  let draw = match Draw::new(rules, hand, &dead, opponent, options.samples, options.seed)
+  {
+    Some(draw) => draw,
+    None =>
+    {
+      println!("Invalid cards: enter your five cards, then optionally your opponent's five, \
+                separated by |");
+      return;
+    }
+  };
+
+  println!("Your hand: {}", rules.describe(hand, &[]));
+  match opponent
+  {
+    Some(cards) => println!("Against {}:", rules.describe(cards, &[])),
+    None => println!("Against a random hand:")
+  }
+  for discard in draw.discards.iter().take(DRAW_CHOICES_SHOWN)
+  {
+    println!("{}", discard.describe());
+  }
+
+  let best = draw.best();
+  match best.discard.len()
+  {
+    0 => println!("Best play: stand pat"),
+    _ => println!("Best play: discard {}",
+                  best.discard.iter().map(|c| c.describe()).collect::<Vec<String>>().join(" "))
+  }
+}
+
+/* process_replay
+   Break up an input string into our hole cards, the opponent's cards or range, the
+   board as it fell and optionally any dead cards, all separated by a |, then show
+   where we stood on each street, for example: ah kh | qq+, ak | 2c 7d 9s ts qh */
+fn process_replay(input: String, options: &Options)
+{
+  let invalid = "Invalid hand: enter your two hole cards, your opponent's cards or range, \
+                 and the board, separated by |, eg: ah kh | qq+, ak | 2c 7d 9s";
+
+  let parts: Vec<&str> = input.split('|').collect();
+  let (dead_parts, parts): (Vec<&str>, Vec<&str>) = parts.iter()
+                                                         .partition(|part| part.trim().starts_with("dead:"));
+  if parts.len() < 2 || parts.len() > 3 || dead_parts.len() > 1
+  {
+    println!("{}", invalid);
+    return;
+  }
+
+  /* the range goes in the middle, so pull out the groups of cards around it */
+  let cards = |text: &str| text.split_whitespace().map(|desc| parse_card(&options.rules, desc)).collect::<Option<Vec<Card>>>();
+  let hole = cards(parts[0]);
+  let board = cards(parts.get(2).unwrap_or(&""));
+  let dead = cards(dead_parts.first().map_or("", |part| part.trim().trim_start_matches("dead:")));
+  let range = Range::parse(parts[1]);
+  let streets = match (hole, board, dead, range)
+  {
+    (Some(hole), Some(board), Some(dead), Some(range)) =>
+      replay::replay(&hole, &range, &board, &dead, options.samples, options.seed),
+    _ => None
+  };
+  let streets = match streets
+  {
+    Some(streets) => streets,
+    None =>
+    {
+      println!("{}", invalid);
+      return;
+    }
+  };
+
+  println!("{:<8} {:<15} {:<40} {:>8} {:>11}  Biggest change", "Street", "Board", "Hand", "Equity", "Ahead");
+  for street in streets.iter()
+  {
+    let board = street.board.iter().map(|c| c.describe()).collect::<Vec<String>>().join(" ");
+    let ahead = format!("{}/{}", street.ahead, street.live);
+    let swing = match street.swing
+    {
+      Some((card, change)) => format!("{} ({:+.2}%)", card.describe(), change * 100.0),
+      None => String::from("-")
+    };
+    println!("{:<8} {:<15} {:<40} {:>7.2}% {:>11}  {}", street.name, board, street.hand.describe(),
+             street.equity.equity * 100.0, ahead, swing);
+  }
+}
+
+/* process_history
+   Read every hand in a hand history file, in PokerStars text or JSON, and either analyse
+   each that went to showdown or, with --luck, report the luck in each all-in, or with
+   --stats, profile each player, or with --export, write the hands back out */
+fn process_history(file: &str, options: &Options)
+{
+  let text = match fs::read_to_string(file)
+  {
+    Ok(text) => text,
+    Err(e) =>
+    {
+      println!("Can't read hand history file {}: {}", file, e);
+      exit(1);
+    }
+  };
+
+  let mut hands = Vec::<HandHistory>::new();
+  for parsed in history::read(&text)
+  {
+    match parsed
+    {
+      Ok(hand) => hands.push(hand),
+      Err(problem) => println!("Skipping a hand: {}", problem)
+    }
+  }
+
+  match (options.luck, options.stats, options.export)
+  {
+    (true, _, _) => report_luck(&hands),
+    (_, true, _) => report_stats(&hands, options.json),
+    (_, _, Some(true)) => println!("{}", Json::Array(hands.iter().map(|hand| hand.to_json()).collect()).to_text()),
+    (_, _, Some(false)) => print!("{}", hands.iter().map(|hand| hand.to_text()).collect::<Vec<String>>().join("\n\n")),
+    _ => report_showdowns(&hands)
+  }
+}
+
+/* list each player's statistics over the hands, as a table or JSON */
+fn report_stats(hands: &[HandHistory], json: bool)
+{
+  let players = stats::collect(hands);
+  if json
+  {
+    println!("{}", Json::Array(players.iter().map(|player| player.to_json()).collect()).to_text());
+    return;
+  }
+
+  if players.is_empty()
+  {
+    println!("No players to profile");
+    return;
+  }
+
+  print!("{:<16} {:>6}", "Player", "Hands");
+  for (name, _) in players[0].ratios().iter()
+  {
+    print!(" {:>6}", name);
+  }
+  println!();
+  for player in players.iter()
+  {
+    print!("{:<16} {:>6}", player.player, player.hands);
+    for (_, ratio) in player.ratios().iter()
+    {
+      match ratio.percent()
+      {
+        Some(percent) => print!(" {:>6.1}", percent),
+        None => print!(" {:>6}", "-")
+      }
+    }
+    println!();
+  }
+}
+
+/* show every player's hand and their equity on each street in hands that went to showdown */
+fn report_showdowns(hands: &[HandHistory])
+{
+  let mut showdowns = 0;
+  for hand in hands.iter()
+  {
+    let showdown = match hand.analyse()
+    {
+      Some(showdown) => showdown,
+      None => continue
+    };
+    showdowns += 1;
+
+    let board = hand.board.iter().map(|c| c.describe()).collect::<Vec<String>>().join(" ");
+    println!("Hand #{}, {} {}/{}, board {}", hand.id, hand.game, hand.amount(hand.small_blind),
+             hand.amount(hand.big_blind), board);
+    for (player, name) in showdown.players.iter().enumerate()
+    {
+      let cards = showdown.cards[player].iter().map(|c| c.describe()).collect::<Vec<String>>().join(" ");
+      let streets = showdown.equity.iter()
+                                   .map(|(street, equity)| format!("{} {:.2}%", street.name(), equity[player].equity * 100.0))
+                                   .collect::<Vec<String>>()
+                                   .join(", ");
+      println!("  {} {}: {}, won {} ({})", name, cards, showdown.hands[player].describe(),
+               hand.amount(hand.won(name)), streets);
+    }
+  }
+  println!("Read {} hand{}, {} with a showdown to analyse", hands.len(), if hands.len() == 1 { "" } else { "s" },
+           showdowns);
+}
+
+/* list every all-in before the river with what each player was expected to win and
+   what they did, keeping a running total of each player's luck in big blinds */
+fn report_luck(hands: &[HandHistory])
+{
+  let mut totals = Vec::<(String, f64)>::new();
+  println!("{:<14} {:<8} {:<16} {:<8} {:>8} {:>10} {:>10} {:>10} {:>10}", "Hand", "Street", "Player", "Cards",
+           "Equity", "Expected", "Won", "Luck (bb)", "Total (bb)");
+  for hand in hands.iter()
+  {
+    let all_in = match luck::all_in(hand)
+    {
+      Some(all_in) => all_in,
+      None => continue
+    };
+
+    for player in all_in.players.iter()
+    {
+      let luck = player.luck(all_in.big_blind);
+      let total = match totals.iter_mut().find(|(name, _)| *name == player.name)
       {
-        for hole2 in deck.cards().iter()
+        Some((_, total)) =>
         {
-          let mut opponent = community.clone();
-          opponent.add(hole1);
-          opponent.add(*hole2);
-          opponent.calc();
-          if opponent.score() > hand.score()
-          {
-            opponent_outs.push_str(format!("( {} {} ) ",
-                                   hole1.describe(), hole2.describe()).as_str());
-            opponent_outs_count = opponent_outs_count + 1;
-
-            /* keep a running total of the odds for drawing these two hole cards given
-               from the deck of unseen cards. we multiply by two to take into account
-               the fact AK is the same as KA */
-            running_odds = running_odds +
-                        2.0 * ((1.0 / unknown_cards as f32) * (1.0 / (unknown_cards - 1) as f32));
-          }
+          *total += luck;
+          *total
+        },
+        None =>
+        {
+          totals.push((player.name.clone(), luck));
+          luck
         }
-      },
+      };
 
-      None => break
+      let cards = player.cards.iter().map(|c| c.describe()).collect::<Vec<String>>().join(" ");
+      println!("{:<14} {:<8} {:<16} {:<8} {:>7.2}% {:>10} {:>10} {:>+10.2} {:>+10.2}", all_in.id, all_in.street.name(),
+               player.name, cards, player.equity * 100.0, hand.amount(player.expected), hand.amount(player.won),
+               luck, total);
     }
   }
 
-  if opponent_outs_count > 20
+  println!("Luck over the session, in big blinds:");
+  totals.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+  for (name, total) in totals.iter()
   {
-    println!("Opponent has potentially {} hands that can beat you", opponent_outs_count);
+    println!("  {:<16} {:+.2}", name, total);
   }
-  else
+}
+
+/* simulate
+   Seat the chosen bots and have them play each other for a number of hands, then list
+   how each did
+   => hands = number of hands to play
+      options = game, bots, stack and seed to play with */
+fn simulate(hands: usize, options: &Options)
+{
+  let blinds = Blinds { small: SIMULATED_BIG_BLIND / 2, big: SIMULATED_BIG_BLIND, ante: 0 };
+  let mut simulator = Simulator::new(options.rules, blinds, options.stack as u64 * SIMULATED_BIG_BLIND);
+  for (seat, bot) in options.bots.iter().enumerate()
   {
-    println!("Opponent needs: {}", opponent_outs);
+    let name = format!("{} {}", seat + 1, bot);
+    match bot.as_str()
+    {
+      "random" => simulator.seat(&name, Box::new(|seed| Box::new(RandomBot::new(seed)))),
+      "station" => simulator.seat(&name, Box::new(|_| Box::new(CallingStation))),
+      _ => simulator.seat(&name, Box::new(|seed| Box::new(EquityBot::new(EQUITY_BOT_CALL, EQUITY_BOT_RAISE,
+                                                                          EQUITY_BOT_SAMPLES, seed))))
+    }
   }
 
-  println!("{:.2}% chance opponent has better cards", running_odds * 100.0);
+  let standings = match simulator.run(hands, options.seed)
+  {
+    Some(standings) => standings,
+    None => usage("Can't simulate that game")
+  };
+  println!("{:<12} {:>10} {:>12} {:>10} {:>10}", "Bot", "Hands", "Won (bb)", "bb/100", "+/- 95%");
+  for standing in standings.iter()
+  {
+    println!("{:<12} {:>10} {:>+12.1} {:>+10.2} {:>10.2}", standing.name, standing.hands, standing.won,
+             standing.bb_per_100, standing.interval);
+  }
+}
+
+/* solve_push_fold
+   Work out the push or fold equilibrium and list the range each player goes all in with
+   => stack = big blinds each player starts with
+      options = number of players and ante */
+fn solve_push_fold(stack: f64, options: &Options)
+{
+  let equities = Equities::table();
+  let spots = match pushfold::solve(&equities, options.players, stack, options.ante, PUSH_FOLD_ITERATIONS)
+  {
+    Some(spots) => spots,
+    None => usage("--pushfold needs a stack bigger than the big blind and ante")
+  };
+
+  println!("Push or fold with {}bb stacks, {}{}", stack, if options.players == 2 { "heads up" } else { "three handed" },
+           if options.ante > 0.0 { format!(", {}bb antes", options.ante) } else { String::new() });
+  for spot in spots.iter()
+  {
+    let range = spot.range();
+    println!("{:<22} {:>5.1}%  {}", spot.name, spot.percent(), if range.is_empty() { "nothing" } else { &range });
+  }
+}
+
+/* process_river
+   Break up an input string into both players' ranges, the board, and the pot and stacks,
+   then solve the river and list each combo's value and its strategy at every decision */
+fn process_river(input: String, options: &Options)
+{
+  let invalid = "Invalid river spot: enter the ranges out of position and in position, the board, \
+                 and the pot and stacks behind, separated by |, eg: qq+, ak | 99+, aq | ah kd 7c 4s 2h | 100 400";
+
+  let parts: Vec<&str> = input.split('|').collect();
+  if parts.len() != 4
+  {
+    println!("{}", invalid);
+    return;
+  }
+  let board = parts[2].split_whitespace().map(|desc| parse_card(&options.rules, desc)).collect::<Option<Vec<Card>>>();
+  let chips = parts[3].split_whitespace().map(|n| n.parse::<u64>().ok()).collect::<Option<Vec<u64>>>();
+  let river = match (Range::parse(parts[0]), Range::parse(parts[1]), board, chips)
+  {
+    (Some(oop), Some(ip), Some(board), Some(chips)) if chips.len() == 2 =>
+      River::new(&options.rules, [&oop, &ip], &board, chips[0], chips[1], &options.sizes),
+    _ => None
+  };
+  let mut river = match river
+  {
+    Some(river) => river,
+    None =>
+    {
+      println!("{}", invalid);
+      return;
+    }
+  };
+
+  river.solve(options.iterations, options.plus);
+  let pot = parts[3].split_whitespace().next().and_then(|n| n.parse::<f64>().ok()).unwrap_or(1.0);
+  println!("Exploitability after {} iterations of {}: {:.3}% of the pot", options.iterations,
+           if options.plus { "CFR+" } else { "CFR" }, river.exploitability() * 100.0 / pot);
+
+  let names = ["OOP", "IP"];
+  for (player, name) in names.iter().enumerate()
+  {
+    println!("{} values:", name);
+    for (cards, value) in river.combos(player).iter().zip(river.ev(player).iter())
+    {
+      match value
+      {
+        Some(value) => println!("  {}{} {:>10.2}", cards[0].describe(), cards[1].describe(), value),
+        None => println!("  {}{} {:>10}", cards[0].describe(), cards[1].describe(), "-")
+      }
+    }
+  }
+
+  for decision in river.decisions()
+  {
+    let line = decision.line.iter().enumerate()
+                                   .map(|(place, action)| format!("{} {}", names[place % 2], action_name(action)))
+                                   .collect::<Vec<String>>();
+    println!("{} to act{}{}:", names[decision.player], if line.is_empty() { "" } else { " after " }, line.join(", "));
+    print!("  {:<6}", "Combo");
+    for action in decision.actions.iter()
+    {
+      print!(" {:>13}", action_name(action));
+    }
+    println!();
+    for (cards, strategy) in river.combos(decision.player).iter().zip(decision.strategy.iter())
+    {
+      print!("  {}{}  ", cards[0].describe(), cards[1].describe());
+      for share in strategy.iter()
+      {
+        print!(" {:>12.1}%", share * 100.0);
+      }
+      println!();
+    }
+  }
+}
+
+/* describe an action in a river spot, with bets and raises to a total for the river */
+fn action_name(action: &Action) -> String
+{
+  match action
+  {
+    Action::Fold => String::from("fold"),
+    Action::Check => String::from("check"),
+    Action::Call => String::from("call"),
+    Action::Bet(amount) => format!("bet {}", amount),
+    Action::Raise(amount) => format!("raise to {}", amount),
+    Action::AllIn => String::from("all in")
+  }
+}
+
+/* bucket_board
+   Break up an input string into three to five board cards, then cluster every hand
+   a player could hold on that board into buckets of similar equity histograms against
+   any two cards. Hands that are the same but for their suits are only worked out once,
+   then weighted by how many of them there are */
+fn bucket_board(input: String, options: &Options, buckets: usize)
+{
+  let mut board = Vec::<Card>::new();
+  for card_desc in input.split_whitespace()
+  {
+    match parse_card(&options.rules, card_desc)
+    {
+      Some(card) => board.push(card),
+      None => return
+    }
+  }
+
+  if board.len() < 3 || board.len() > 5
+  {
+    println!("Invalid number of board cards ({})", board.len());
+    return;
+  }
+
+  /* group the possible hands by their suit-isomorphic index on this board */
+  let indexer = Indexer::new(&[board.len(), 2]);
+  let mut deck = Deck::new();
+  for card in board.iter()
+  {
+    deck.remove(card);
+  }
+
+  let mut classes = Vec::<Vec<Card>>::new();
+  let mut members = Vec::<Vec<Vec<Card>>>::new();
+  let mut seen = HashMap::<u64, usize>::new();
+  combinations(deck.cards(), 2, |hole|
+  {
+    let mut cards = board.clone();
+    cards.extend_from_slice(hole);
+    let index = indexer.index(&cards).unwrap();
+    let class = *seen.entry(index).or_insert_with(||
+    {
+      classes.push(hole.to_vec());
+      members.push(Vec::new());
+      classes.len() - 1
+    });
+    members[class].push(hole.to_vec());
+  });
+
+  /* each thread takes one hand at a time */
+  let histograms = parallel::map(classes.len(), |class|
+  {
+    let hole = &classes[class];
+    let mut known = board.clone();
+    known.extend_from_slice(hole);
+    let range = Range::uniform(2, &known);
+    Histogram::new(hole, &board, &[], &range, options.bins).unwrap()
+  });
+
+  let weights: Vec<f64> = members.iter().map(|hands| hands.len() as f64).collect();
+  let result = Buckets::new(&histograms, &weights, buckets, BUCKET_ITERATIONS);
+  let describe = |cards: &Vec<Card>| cards.iter().map(|c| c.code()).collect::<String>();
+
+  if options.json
+  {
+    let mut list = Vec::<Json>::new();
+    for (bucket, centroid) in result.centroids.iter().enumerate()
+    {
+      let mut hands = Vec::<Json>::new();
+      for (class, assignment) in result.assignments.iter().enumerate()
+      {
+        if *assignment == bucket
+        {
+          hands.extend(members[class].iter().map(|h| Json::String(describe(h))));
+        }
+      }
+
+      if let Json::Object(mut fields) = centroid.to_json()
+      {
+        fields.push(("hands".to_string(), Json::Array(hands)));
+        list.push(Json::Object(fields));
+      }
+    }
+
+    println!("{}", Json::object(vec![
+      ("board", Json::Array(board.iter().map(|c| Json::String(c.code())).collect())),
+      ("buckets", Json::Array(list))
+    ]).to_text());
+    return;
+  }
+
+  for (bucket, centroid) in result.centroids.iter().enumerate()
+  {
+    let mut hands = Vec::<String>::new();
+    for (class, assignment) in result.assignments.iter().enumerate()
+    {
+      if *assignment == bucket
+      {
+        hands.extend(members[class].iter().map(&describe));
+      }
+    }
+
+    println!("Bucket {}: {:.1}% mean equity, {} hand{}: {}", bucket + 1,
+             centroid.equity() * 100.0, hands.len(), if hands.len() == 1 { "" } else { "s" },
+             hands.join(" "));
+    print!("{}", centroid.ascii());
+  }
 }
 
 /* handle frontend IO */
 fn main()
 {
+  let options = parse_options();
+  if let (Some(ref stacks), Some(ref payouts), None) = (&options.stacks, &options.payouts, options.call)
+  {
+    report_icm(stacks, payouts);
+    return;
+  }
+  if let Some(stack) = options.pushfold
+  {
+    solve_push_fold(stack, &options);
+    return;
+  }
+  if let Some(hands) = options.simulate
+  {
+    simulate(hands, &options);
+    return;
+  }
+  if let Some(ref file) = options.history
+  {
+    process_history(file, &options);
+    return;
+  }
+
   /* read a line in from STDIN until EOF, at which point, give up */
   loop
   {
@@ -145,7 +1371,16 @@ fn main()
            stripping the newline and making it all lowercase */
         if bytes > 0
         {
-          process(buffer.replace('\n', "").to_lowercase());
+          let input = buffer.replace('\n', "").to_lowercase();
+          match (options.buckets, options.rules.game)
+          {
+            (Some(buckets), _) => bucket_board(input, &options, buckets),
+            (None, _) if options.replay => process_replay(input, &options),
+            (None, _) if options.river => process_river(input, &options),
+            (None, Game::Stud) => process_stud(input, &options),
+            (None, Game::Draw(_)) => process_draw(input, &options),
+            (None, _) => process(input, &options)
+          }
         }
         else
         {
@@ -156,3 +1391,34 @@ fn main()
     }
   }
 }
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  #[test]
+  fn hole_cards_board_and_dead_cards()
+  {
+    let rules = Rules::holdem();
+    assert_eq!(parse_hand(&rules, "jd 9d | 7s ac 9h | dead: 2c 3s"),
+               Some((cards("jd 9d"), cards("7s ac 9h"), cards("2c 3s"))));
+    assert_eq!(parse_hand(&rules, "jd 9d 7s ac 9h"), Some((cards("jd 9d"), cards("7s ac 9h"), vec![])));
+    assert_eq!(parse_hand(&rules, "jd 9d | dead: 2c"), Some((cards("jd 9d"), vec![], cards("2c"))));
+  }
+
+  #[test]
+  fn dead_cards_must_be_unseen()
+  {
+    let rules = Rules::holdem();
+    assert_eq!(parse_hand(&rules, "jd 9d | 7s ac 9h | dead: 9d"), None);
+    assert_eq!(parse_hand(&rules, "jd 9d | 7s ac 9h | dead: 2c ac"), None);
+    assert_eq!(parse_hand(&rules, "jd 9d | dead: 2c 2c"), None);
+    assert_eq!(parse_hand(&rules, "jd 9d | dead: 2c | dead: 2c"), None);
+  }
+}
@@ -0,0 +1,260 @@
+/* metrics.rs
+ * Calculate Billings-style hand metrics for a hold 'em hand on the flop, turn or river,
+ * against an opponent's range:
+ *
+ * hand strength (HS): the chance we're ahead right now
+ * positive potential (PPot): the chance we end up ahead by the river when currently behind
+ * negative potential (NPot): the chance we end up behind by the river when currently ahead
+ * effective hand strength (EHS): HS x (1 - NPot) + (1 - HS) x PPot
+ *
+ * Ties count as half a win throughout.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use deck::Deck;
+use deck::combinations;
+use hand::Hand;
+use parallel;
+use range::Range;
+
+/* opponent holdings handed to each thread at a time */
+const COMBOS_PER_PIECE: usize = 16;
+
+/* where we stand against a single opposing holding */
+const AHEAD: usize = 0;
+const TIED: usize = 1;
+const BEHIND: usize = 2;
+
+/* weighted tallies from a chunk of the opponent's holdings */
+struct Tally
+{
+  now: [f64; 3],            /* where we stand now */
+  potential: [[f64; 3]; 3], /* where we stand now versus at the river */
+  totals: [f64; 3]          /* runouts looked at for each standing now */
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics
+{
+  pub hs: f64,   /* hand strength on the current board */
+  pub ppot: f64, /* positive potential */
+  pub npot: f64, /* negative potential */
+  pub ehs: f64   /* effective hand strength */
+}
+
+impl Metrics
+{
+  /* new
+     Calculate the hand metrics by running through every holding in the opponent's range
+     and every turn and river still to come
+     => hole = our two hole cards
+        board = three to five community cards
+        dead = other cards known to be out of play, which neither the opponent
+               nor the rest of the board can hold
+        range = opponent's possible holdings
+     <= Metrics object, or None if the board isn't three to five cards or the
+        opponent's range has no holdings left in it */
+  pub fn new(hole: &[Card], board: &[Card], dead: &[Card], range: &Range) -> Option<Metrics>
+  {
+    if board.len() < 3 || board.len() > 5
+    {
+      return None;
+    }
+
+    let mut known = hole.to_vec();
+    known.extend_from_slice(board);
+    let mut out = known.clone();
+    out.extend_from_slice(dead);
+
+    /* list the unseen cards and every way the rest of the board could fall */
+    let mut deck = Deck::new();
+    deck.remove_all(&out);
+    let mut runouts = Vec::<Vec<Card>>::new();
+    combinations(deck.cards(), 5 - board.len(), |runout| runouts.push(runout.to_vec()));
+
+    /* our score doesn't depend on the opponent, so work it out once per runout */
+    let our_score = Hand::score_cards(&known);
+    let mut cards = known.clone();
+    let our_finals: Vec<u32> = runouts.iter().map(|runout|
+    {
+      cards.truncate(known.len());
+      cards.extend_from_slice(runout);
+      Hand::score_cards(&cards)
+    }).collect();
+
+    let opponents = range.live(&out);
+    if opponents.is_empty()
+    {
+      return None;
+    }
+
+    /* weighted tallies of where we stand now, and where we stand now versus at the river,
+       worked out a chunk of the opponent's holdings at a time across the threads */
+    let pieces = parallel::chunks(opponents.len(), COMBOS_PER_PIECE);
+    let results = parallel::map(pieces, |piece|
+    {
+      let mut tally = Tally { now: [0.0; 3], potential: [[0.0; 3]; 3], totals: [0.0; 3] };
+      let first = piece * COMBOS_PER_PIECE;
+      let last = if first + COMBOS_PER_PIECE < opponents.len()
+                 { first + COMBOS_PER_PIECE } else { opponents.len() };
+      for combo in opponents[first..last].iter()
+      {
+        let mut theirs = combo.cards.clone();
+        theirs.extend_from_slice(board);
+        let standing = compare(our_score, Hand::score_cards(&theirs));
+        tally.now[standing] += combo.weight;
+
+        /* on the river the only runout is an empty one, so there's no potential either way */
+        for (runout, our_final) in runouts.iter().zip(our_finals.iter())
+        {
+          if runout.iter().any(|card| combo.cards.contains(card))
+          {
+            continue; /* opponent holds one of the cards this runout needs */
+          }
+
+          theirs.truncate(combo.cards.len() + board.len());
+          theirs.extend_from_slice(runout);
+          let finally = compare(*our_final, Hand::score_cards(&theirs));
+          tally.potential[standing][finally] += combo.weight;
+          tally.totals[standing] += combo.weight;
+        }
+      }
+      tally
+    });
+
+    /* combine the chunks in order, so the result doesn't depend on the thread count */
+    let mut now = [0.0; 3];
+    let mut potential = [[0.0; 3]; 3];
+    let mut potential_totals = [0.0; 3];
+    for tally in results.iter()
+    {
+      for i in 0..3
+      {
+        now[i] += tally.now[i];
+        potential_totals[i] += tally.totals[i];
+        for (total, tallied) in potential[i].iter_mut().zip(tally.potential[i].iter())
+        {
+          *total += tallied;
+        }
+      }
+    }
+
+    let total = now[AHEAD] + now[TIED] + now[BEHIND];
+    let hs = (now[AHEAD] + now[TIED] / 2.0) / total;
+
+    let ppot_base = potential_totals[BEHIND] + potential_totals[TIED] / 2.0;
+    let ppot = match ppot_base > 0.0
+    {
+      true => (potential[BEHIND][AHEAD] + potential[BEHIND][TIED] / 2.0 +
+               potential[TIED][AHEAD] / 2.0) / ppot_base,
+      false => 0.0
+    };
+
+    let npot_base = potential_totals[AHEAD] + potential_totals[TIED] / 2.0;
+    let npot = match npot_base > 0.0
+    {
+      true => (potential[AHEAD][BEHIND] + potential[AHEAD][TIED] / 2.0 +
+               potential[TIED][BEHIND] / 2.0) / npot_base,
+      false => 0.0
+    };
+
+    Some(Metrics
+    {
+      hs,
+      ppot,
+      npot,
+      ehs: hs * (1.0 - npot) + (1.0 - hs) * ppot
+    })
+  }
+
+  /* bucket
+     Sort the hand into one of a number of equal-width buckets by effective hand strength
+     => buckets = number of buckets
+     <= bucket number, from 0 (weakest) to buckets - 1 (strongest) */
+  pub fn bucket(&self, buckets: usize) -> usize
+  {
+    let bucket = (self.ehs * buckets as f64) as usize;
+    if bucket >= buckets { buckets - 1 } else { bucket }
+  }
+
+  /* return a string describing the metrics */
+  pub fn describe(&self) -> String
+  {
+    format!("HS {:.1}%, PPot {:.1}%, NPot {:.1}%, EHS {:.1}%",
+            self.hs * 100.0, self.ppot * 100.0, self.npot * 100.0, self.ehs * 100.0)
+  }
+}
+
+/* where do we stand with our score versus theirs? */
+fn compare(ours: u32, theirs: u32) -> usize
+{
+  if ours > theirs
+  {
+    AHEAD
+  }
+  else if ours == theirs
+  {
+    TIED
+  }
+  else
+  {
+    BEHIND
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  #[test]
+  fn no_potential_on_the_river()
+  {
+    let (hole, board) = (cards("ac kd"), cards("ah 7c 2d 9s qs"));
+    let mut known = hole.clone();
+    known.extend_from_slice(&board);
+    let metrics = Metrics::new(&hole, &board, &[], &Range::uniform(2, &known)).unwrap();
+
+    /* count the holdings that beat us, and tie with us, one by one */
+    let mut deck = Deck::new();
+    deck.remove_all(&known);
+    let ours = Hand::score_cards(&known);
+    let (mut ahead, mut tied, mut total) = (0.0, 0.0, 0.0);
+    combinations(deck.cards(), 2, |theirs|
+    {
+      let mut cards = theirs.to_vec();
+      cards.extend_from_slice(&board);
+      match compare(ours, Hand::score_cards(&cards))
+      {
+        AHEAD => ahead += 1.0,
+        TIED => tied += 1.0,
+        _ => ()
+      }
+      total += 1.0;
+    });
+
+    assert!((metrics.hs - (ahead + tied / 2.0) / total).abs() < 1e-9);
+    assert_eq!((metrics.ppot, metrics.npot), (0.0, 0.0));
+    assert_eq!(metrics.ehs, metrics.hs);
+  }
+
+  #[test]
+  fn billings_flop_example()
+  {
+    /* the worked example from Billings et al, The challenge of poker */
+    let (hole, board) = (cards("ad qc"), cards("3h 4c jh"));
+    let mut known = hole.clone();
+    known.extend_from_slice(&board);
+    let metrics = Metrics::new(&hole, &board, &[], &Range::uniform(2, &known)).unwrap();
+    assert!((metrics.hs - 0.585).abs() < 0.0005);
+    assert!((metrics.ppot - 0.208).abs() < 0.0005);
+    assert!((metrics.npot - 0.274).abs() < 0.0005);
+  }
+}
@@ -0,0 +1,127 @@
+/* parallel.rs
+ * Spread heavy enumeration across the CPU's cores using scoped threads.
+ *
+ * Work is cut into a fixed list of pieces that doesn't depend on how many threads
+ * there are, and each piece's result is handed back in order. Callers then combine
+ * the results in that order, so floating-point totals come out bit-for-bit the same
+ * whether one thread or sixty-four did the work.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+/* number of threads to use, or zero for one per core */
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/* set in worker threads so that work split up within a piece runs in place,
+   rather than each worker starting yet more threads */
+thread_local!(static WORKER: Cell<bool> = const { Cell::new(false) });
+
+/* set_threads
+   Choose how many threads to split work across
+   => threads = number of threads, or zero for one per core */
+pub fn set_threads(threads: usize)
+{
+  THREADS.store(threads, Ordering::Relaxed);
+}
+
+/* return the number of threads work will be split across */
+pub fn threads() -> usize
+{
+  match THREADS.load(Ordering::Relaxed)
+  {
+    0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    n => n
+  }
+}
+
+/* map
+   Work through a number of pieces across the threads
+   => pieces = number of pieces of work
+      work = closure that does a piece of work given its number, from 0 to pieces - 1
+   <= results of each piece, in piece order */
+pub fn map<T, F>(pieces: usize, work: F) -> Vec<T> where T: Send, F: Fn(usize) -> T + Sync
+{
+  let threads = if threads() < pieces { threads() } else { pieces };
+  if threads <= 1 || WORKER.with(|w| w.get())
+  {
+    return (0..pieces).map(work).collect();
+  }
+
+  /* each thread grabs the next piece not yet taken until there are none left */
+  let next = AtomicUsize::new(0);
+  let results = Mutex::new((0..pieces).map(|_| None).collect::<Vec<Option<T>>>());
+  thread::scope(|scope|
+  {
+    for _ in 0..threads
+    {
+      scope.spawn(||
+      {
+        WORKER.with(|w| w.set(true));
+        loop
+        {
+          let piece = next.fetch_add(1, Ordering::Relaxed);
+          if piece >= pieces
+          {
+            break;
+          }
+
+          let result = work(piece);
+          results.lock().unwrap()[piece] = Some(result);
+        }
+      });
+    }
+  });
+
+  results.into_inner().unwrap().into_iter().map(|r| r.unwrap()).collect()
+}
+
+/* chunks
+   Work out how many fixed-size chunks a list of items splits into
+   => items = number of items
+      size = items per chunk
+   <= number of chunks, the last of which may be short */
+pub fn chunks(items: usize, size: usize) -> usize
+{
+  items.div_ceil(size)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn same_bits_on_any_number_of_threads()
+  {
+    /* sums of awkward fractions, which come out differently if added up in another order */
+    let sum = || -> f64
+    {
+      let pieces = map(chunks(10000, 64), |piece|
+      {
+        (piece * 64..(piece * 64 + 64).min(10000)).map(|n| 1.0 / (n as f64 + 0.7).sqrt()).sum::<f64>()
+      });
+      pieces.iter().sum()
+    };
+
+    set_threads(1);
+    let one = sum();
+    let mut many = Vec::<f64>::new();
+    for threads in [2, 3, 8].iter()
+    {
+      set_threads(*threads);
+      many.push(sum());
+    }
+    set_threads(0);
+
+    for total in many.iter()
+    {
+      assert_eq!(total.to_bits(), one.to_bits());
+    }
+  }
+}
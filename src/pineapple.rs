@@ -0,0 +1,119 @@
+/* pineapple.rs
+ * Pick which hole card to throw away in pineapple games, where each player is dealt
+ * three hole cards and plays hold 'em with two of them. In pineapple the discard is
+ * made before the flop, and in crazy pineapple just after it. Each discard is judged
+ * by our all-in equity with the two cards kept against a random hand, with the thrown
+ * card out of play.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use equity;
+use equity::Equity;
+use range::Range;
+use rules::Game;
+use rules::Rules;
+
+/* one way of playing the hand */
+#[derive(Debug, Clone)]
+pub struct Choice
+{
+  pub discard: Card,   /* the card thrown away */
+  pub keep: Vec<Card>, /* the two hole cards kept */
+  pub equity: Equity   /* our all-in equity with them against a random hand */
+}
+
+/* choices
+   Work out our equity with each discard
+   => rules = pineapple or crazy pineapple rules
+      hole = our three hole cards
+      board = community cards: none in pineapple, or the flop in crazy pineapple
+      dead = other cards known to be out of play
+      deals = number of random deals to sample before the flop, where there are too
+              many boards to run through
+      seed = starting point for the random numbers
+   <= each discard, best equity first, or None if the game isn't pineapple, the
+      number of cards is wrong for when the discard is made, or a card appears twice */
+pub fn choices(rules: &Rules, hole: &[Card], board: &[Card], dead: &[Card],
+               deals: usize, seed: u64) -> Option<Vec<Choice>>
+{
+  let discard_board = match rules.game
+  {
+    Game::Pineapple(false) => 0,
+    Game::Pineapple(true) => 3,
+    _ => return None
+  };
+
+  if hole.len() != 3 || board.len() != discard_board
+  {
+    return None;
+  }
+
+  /* once the discard is made, the rest of the hand is plain hold 'em */
+  let holdem = Rules::holdem();
+  let mut known = hole.to_vec();
+  known.extend_from_slice(board);
+  known.extend_from_slice(dead);
+  let range = Range::uniform(2, &known);
+
+  let mut choices = Vec::<Choice>::new();
+  for discard in hole.iter()
+  {
+    let keep: Vec<Card> = hole.iter().filter(|card| *card != discard).cloned().collect();
+    let mut out = dead.to_vec();
+    out.push(*discard);
+
+    let equity = match board.is_empty()
+    {
+      true => equity::sample(&holdem, &keep, board, &out, &range, deals, seed)?,
+      false => equity::versus_range(&holdem, &keep, board, &out, &range)?
+    };
+    choices.push(Choice { discard: *discard, keep, equity });
+  }
+
+  choices.sort_by(|a, b| b.equity.equity.partial_cmp(&a.equity.equity).unwrap());
+  Some(choices)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  #[test]
+  fn the_discard_is_dead()
+  {
+    /* throwing the ten of spades takes an out to the royal flush away from our
+       ace-king, so it can't come on the turn or river */
+    let (hole, board) = (cards("as ks ts"), cards("qs js 3d"));
+    let choices = choices(&Rules::pineapple(true), &hole, &board, &[], 0, 1).unwrap();
+    assert_eq!(choices.len(), 3);
+
+    let holdem = Rules::holdem();
+    let range = Range::uniform(2, &cards("as ks ts qs js 3d"));
+    let keep = cards("as ks");
+    let choice = choices.iter().find(|choice| choice.discard == hole[2]).unwrap();
+    assert_eq!(choice.keep, keep);
+
+    let dead = equity::versus_range(&holdem, &keep, &board, &cards("ts"), &range).unwrap();
+    let live = equity::versus_range(&holdem, &keep, &board, &[], &range).unwrap();
+    assert!((choice.equity.equity - dead.equity).abs() < 1e-9);
+    assert!(live.equity > dead.equity);
+  }
+
+  #[test]
+  fn cards_must_suit_the_game()
+  {
+    let hole = cards("as ks ts");
+    assert!(choices(&Rules::pineapple(false), &hole, &cards("qs js 3d"), &[], 100, 1).is_none());
+    assert!(choices(&Rules::pineapple(true), &hole, &[], &[], 100, 1).is_none());
+    assert!(choices(&Rules::holdem(), &hole, &[], &[], 100, 1).is_none());
+    assert!(choices(&Rules::pineapple(false), &cards("as ks"), &[], &[], 100, 1).is_none());
+  }
+}
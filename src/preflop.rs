@@ -0,0 +1,444 @@
+/* preflop.rs
+ * The 169 classes of hold 'em starting hands, pairs, suited and offsuit, and how
+ * each fares all in preflop against each of the others.
+ *
+ * Classes are numbered as they sit in the usual 13 by 13 grid, AA in the top left
+ * and 22 in the bottom right, with suited hands above the diagonal of pairs and
+ * offsuit hands below it.
+ *
+ * Equities between classes are estimated by dealing random boards and, on each,
+ * playing every holding against every other holding that doesn't share a card, so
+ * every class meets every other class on every board. That takes a while to do well,
+ * so a table estimated once from a large fixed sample ships with the code, in
+ * preflop_equities.txt, and is regenerated with:
+ *
+ *   cargo run --release --example preflop_table > src/preflop_equities.txt
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use card::Suit;
+use card::Value;
+use deck::Deck;
+use parallel;
+use rng::Rng;
+use rules::Rules;
+
+/* number of classes of starting hands */
+pub const CLASSES: usize = 169;
+
+/* boards dealt in each piece of work when estimating equities */
+const BOARDS_PER_PIECE: usize = 50;
+
+/* boards dealt, and the seed they're dealt from, for the shipped table */
+pub const TABLE_BOARDS: usize = 200_000;
+pub const TABLE_SEED: u64 = 2018;
+
+/* the shipped table: comment lines starting with #, then a line of equities for each
+   class against every class, in index order */
+const TABLE: &str = include_str!("preflop_equities.txt");
+
+/* a class of starting hands, such as AKs, T9o or 22 */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartingHand
+{
+  pub high: Value,  /* higher card, the same as low for a pair */
+  pub low: Value,   /* lower card */
+  pub suited: bool  /* true for suited hands, always false for pairs */
+}
+
+impl StartingHand
+{
+  /* return every class of starting hand, in index order */
+  pub fn all() -> Vec<StartingHand>
+  {
+    (0..CLASSES).filter_map(StartingHand::from_index).collect()
+  }
+
+  /* from_index
+     Find the class of starting hand at a place in the grid
+     => index = from 0 for AA to 168 for 22
+     <= StartingHand object, or None if out of range */
+  pub fn from_index(index: usize) -> Option<StartingHand>
+  {
+    if index >= CLASSES
+    {
+      return None;
+    }
+
+    let (row, column) = ((index / 13) as u32, (index % 13) as u32);
+    let high = Value::from_u32(14 - row.min(column))?;
+    let low = Value::from_u32(14 - row.max(column))?;
+    Some(StartingHand { high, low, suited: row < column })
+  }
+
+  /* of
+     Find the class of a pair of hole cards
+     => cards = two hole cards
+     <= StartingHand object, or None if there aren't two different cards, or there's a joker */
+  pub fn of(cards: &[Card]) -> Option<StartingHand>
+  {
+    if cards.len() != 2 || cards[0] == cards[1] || cards.iter().any(|card| card.value == Value::Joker)
+    {
+      return None;
+    }
+
+    let (high, low) = match cards[0].value.to_u32() >= cards[1].value.to_u32()
+    {
+      true => (cards[0].value, cards[1].value),
+      false => (cards[1].value, cards[0].value)
+    };
+    Some(StartingHand { high, low, suited: cards[0].suit == cards[1].suit })
+  }
+
+  /* return the class's place in the grid, from 0 for AA to 168 for 22 */
+  pub fn index(&self) -> usize
+  {
+    let (high, low) = ((14 - self.high.to_u32()) as usize, (14 - self.low.to_u32()) as usize);
+    match self.suited
+    {
+      true => high * 13 + low,
+      false => low * 13 + high
+    }
+  }
+
+  /* return true for a pocket pair */
+  pub fn pair(&self) -> bool
+  {
+    self.high == self.low
+  }
+
+  /* return the class in range shorthand, eg AKs, T9o or 22 */
+  pub fn name(&self) -> String
+  {
+    let mut name = String::new();
+    name.push(self.high.to_char());
+    name.push(self.low.to_char());
+    if !self.pair()
+    {
+      name.push(if self.suited { 's' } else { 'o' });
+    }
+    name
+  }
+
+  /* return every pair of hole cards in the class: 6 for a pair, 4 suited or 12 offsuit */
+  pub fn combos(&self) -> Vec<[Card; 2]>
+  {
+    let mut combos = Vec::<[Card; 2]>::new();
+    for first in 0..4
+    {
+      for second in 0..4
+      {
+        if (self.pair() && second <= first) || (!self.pair() && (first == second) != self.suited)
+        {
+          continue;
+        }
+        if let (Some(high), Some(low)) = (Suit::from_u32(first), Suit::from_u32(second))
+        {
+          combos.push([Card { value: self.high, suit: high }, Card { value: self.low, suit: low }]);
+        }
+      }
+    }
+    combos
+  }
+}
+
+/* notation
+   Write a set of starting hands in range shorthand, eg: 77+, A2s+, KTs+, QJs, A9o-A7o
+   => hands = classes of starting hands in the range, in any order
+   <= the range, with pairs first and then hands by their top card, suited before offsuit */
+pub fn notation(hands: &[StartingHand]) -> String
+{
+  let mut parts = Vec::<String>::new();
+  let held = |high: u32, low: u32, suited: bool| hands.iter().any(|hand| hand.high.to_u32() == high &&
+                                                                         hand.low.to_u32() == low &&
+                                                                         hand.suited == suited);
+  let name = |high: u32, low: u32, suited: bool|
+  {
+    match (Value::from_u32(high), Value::from_u32(low))
+    {
+      (Some(high), Some(low)) => StartingHand { high, low, suited }.name(),
+      _ => String::new()
+    }
+  };
+
+  /* runs of pairs, from the top down */
+  let mut value = 14;
+  while value >= 2
+  {
+    if !held(value, value, false)
+    {
+      value -= 1;
+      continue;
+    }
+    let top = value;
+    while value >= 2 && held(value, value, false)
+    {
+      value -= 1;
+    }
+    let bottom = value + 1;
+    parts.push(match (top, bottom)
+    {
+      (_, _) if top == bottom => name(top, top, false),
+      (14, _) => format!("{}+", name(bottom, bottom, false)),
+      _ => format!("{}-{}", name(top, top, false), name(bottom, bottom, false))
+    });
+  }
+
+  /* runs of kickers under each top card, suited then offsuit */
+  for high in (3..15).rev()
+  {
+    for suited in [true, false].iter()
+    {
+      let mut low = high - 1;
+      while low >= 2
+      {
+        if !held(high, low, *suited)
+        {
+          low -= 1;
+          continue;
+        }
+        let top = low;
+        while low >= 2 && held(high, low, *suited)
+        {
+          low -= 1;
+        }
+        let bottom = low + 1;
+        parts.push(match (top, bottom)
+        {
+          (_, _) if top == bottom => name(high, top, *suited),
+          (_, _) if top == high - 1 => format!("{}+", name(high, bottom, *suited)),
+          _ => format!("{}-{}", name(high, top, *suited), name(high, bottom, *suited))
+        });
+      }
+    }
+  }
+  parts.join(", ")
+}
+
+/* how every class of starting hand fares all in preflop against every other */
+#[derive(Debug, Clone)]
+pub struct Equities
+{
+  equity: Vec<f64>, /* share of the pot won by one class against another, by their indexes */
+  weight: Vec<f64>  /* ways the two classes can be dealt together without sharing a card */
+}
+
+impl Equities
+{
+  /* load the table of equities that ships with the code */
+  pub fn table() -> Equities
+  {
+    Equities::from_text(TABLE).expect("the shipped preflop equity table is complete")
+  }
+
+  /* from_text
+     Read a table of equities written by to_text()
+     => text = comment lines starting with #, then a line for each class of its equity
+               against each class, separated by spaces
+     <= Equities object, or None if the table isn't 169 by 169 numbers */
+  pub fn from_text(text: &str) -> Option<Equities>
+  {
+    let mut equity = Vec::<f64>::with_capacity(CLASSES * CLASSES);
+    for line in text.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+    {
+      let row = line.split_whitespace().map(|number| number.parse::<f64>().ok()).collect::<Option<Vec<f64>>>()?;
+      if row.len() != CLASSES
+      {
+        return None;
+      }
+      equity.extend(row);
+    }
+    if equity.len() != CLASSES * CLASSES
+    {
+      return None;
+    }
+    Some(Equities { equity, weight: weights(&combos()) })
+  }
+
+  /* to_text
+     Write out the table of equities, to be read back in with from_text()
+     => comment = description of where the table came from, for the first line
+     <= the table, one line per class */
+  pub fn to_text(&self, comment: &str) -> String
+  {
+    let mut text = format!("# {}\n", comment);
+    for row in self.equity.chunks(CLASSES)
+    {
+      text.push_str(&row.iter().map(|equity| format!("{:.4}", equity)).collect::<Vec<String>>().join(" "));
+      text.push('\n');
+    }
+    text
+  }
+
+  /* sample
+     Estimate the equity of every class of hold 'em starting hand against every other,
+     as was done to make the shipped table
+     => boards = number of random boards to deal
+        seed = starting point for the random boards
+     <= Equities object */
+  pub fn sample(boards: usize, seed: u64) -> Equities
+  {
+    let rules = Rules::holdem();
+    let combos = combos();
+    let weight = weights(&combos);
+
+    /* each piece tallies half-pots won, two for a win and one for a tie, and showdowns,
+       for each class against each other */
+    let cards = Deck::new().cards().clone();
+    let pieces = parallel::chunks(boards, BOARDS_PER_PIECE);
+    let tallies = parallel::map(pieces, |piece|
+    {
+      let mut rng = Rng::new(seed ^ (piece as u64).wrapping_mul(0x2545f4914f6cdd1d));
+      let mut deck = cards.clone();
+      let mut won = vec![0u64; CLASSES * CLASSES];
+      let mut played = vec![0u64; CLASSES * CLASSES];
+      let count = if (piece + 1) * BOARDS_PER_PIECE <= boards { BOARDS_PER_PIECE } else { boards - piece * BOARDS_PER_PIECE };
+      for _ in 0..count
+      {
+        for place in 0..5
+        {
+          let other = place + rng.below(deck.len() - place);
+          deck.swap(place, other);
+        }
+        let board = &deck[0..5];
+        let board_mask = mask(board);
+        let live: Vec<(usize, u64, u32)> = combos.iter()
+                                                 .filter(|(_, _, cards)| cards & board_mask == 0)
+                                                 .map(|(hole, class, cards)| (*class, *cards, rules.score(hole, board)))
+                                                 .collect();
+
+        for (place, (first, first_mask, first_score)) in live.iter().enumerate()
+        {
+          for (second, second_mask, second_score) in live[(place + 1)..].iter()
+          {
+            if first_mask & second_mask != 0
+            {
+              continue;
+            }
+            let (ahead, behind) = (first * CLASSES + second, second * CLASSES + first);
+            match first_score.cmp(second_score)
+            {
+              std::cmp::Ordering::Greater => won[ahead] += 2,
+              std::cmp::Ordering::Less => won[behind] += 2,
+              std::cmp::Ordering::Equal =>
+              {
+                won[ahead] += 1;
+                won[behind] += 1;
+              }
+            }
+            played[ahead] += 1;
+            played[behind] += 1;
+          }
+        }
+      }
+      (won, played)
+    });
+
+    let mut won = vec![0u64; CLASSES * CLASSES];
+    let mut played = vec![0u64; CLASSES * CLASSES];
+    for (piece_won, piece_played) in tallies.iter()
+    {
+      for cell in 0..(CLASSES * CLASSES)
+      {
+        won[cell] += piece_won[cell];
+        played[cell] += piece_played[cell];
+      }
+    }
+
+    let equity = won.iter().zip(played.iter())
+                    .map(|(won, played)| if *played > 0 { *won as f64 / (2 * played) as f64 } else { 0.5 })
+                    .collect();
+    Equities { equity, weight }
+  }
+
+  /* return the share of the pot one class wins all in against another, from 0.0 to 1.0 */
+  pub fn equity(&self, hand: usize, against: usize) -> f64
+  {
+    self.equity[hand * CLASSES + against]
+  }
+
+  /* return the number of ways two classes can be dealt to two players, eg 12 for AKs against AA */
+  pub fn weight(&self, hand: usize, against: usize) -> f64
+  {
+    self.weight[hand * CLASSES + against]
+  }
+}
+
+/* return every pair of hole cards, with its class and a bit for each of its cards */
+fn combos() -> Vec<([Card; 2], usize, u64)>
+{
+  let mut combos = Vec::<([Card; 2], usize, u64)>::new();
+  for (index, hand) in StartingHand::all().iter().enumerate()
+  {
+    for cards in hand.combos()
+    {
+      combos.push((cards, index, mask(&cards)));
+    }
+  }
+  combos
+}
+
+/* return the exact number of ways to deal each pair of classes, by their indexes */
+fn weights(combos: &[([Card; 2], usize, u64)]) -> Vec<f64>
+{
+  let mut weight = vec![0.0; CLASSES * CLASSES];
+  for (_, first, first_mask) in combos.iter()
+  {
+    for (_, second, second_mask) in combos.iter()
+    {
+      if first_mask & second_mask == 0
+      {
+        weight[first * CLASSES + second] += 1.0;
+      }
+    }
+  }
+  weight
+}
+
+/* return a bit for each card, for quickly checking holdings against each other */
+fn mask(cards: &[Card]) -> u64
+{
+  cards.iter().fold(0, |mask, card| mask | 1 << (card.suit.to_u32() * 13 + card.value.to_u32() - 2))
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn class(name: &str) -> usize
+  {
+    StartingHand::all().iter().position(|hand| hand.name() == name).unwrap()
+  }
+
+  #[test]
+  fn shipped_table_is_consistent()
+  {
+    let equities = Equities::table();
+    for hand in 0..CLASSES
+    {
+      for against in 0..CLASSES
+      {
+        let total = equities.equity(hand, against) + equities.equity(against, hand);
+        assert!((total - 1.0).abs() < 0.0002, "{} and {}", hand, against);
+      }
+    }
+
+    /* well known all-in equities */
+    assert!((equities.equity(class("AA"), class("KK")) - 0.8195).abs() < 0.003);
+    assert!((equities.equity(class("AKs"), class("QQ")) - 0.4621).abs() < 0.003);
+    assert!((equities.equity(class("22"), class("AKo")) - 0.5264).abs() < 0.003);
+    assert_eq!(equities.weight(class("AKs"), class("AA")), 12.0);
+  }
+
+  #[test]
+  fn table_reads_back_what_it_writes()
+  {
+    let equities = Equities::table();
+    let text = equities.to_text("test");
+    assert_eq!(Equities::from_text(&text).unwrap().to_text("test"), text);
+    assert!(Equities::from_text("# nothing here").is_none());
+  }
+}
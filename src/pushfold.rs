@@ -0,0 +1,323 @@
+/* pushfold.rs
+ * Solve preflop push or fold play for short stacks: every player either moves all in
+ * or folds, and players facing an all in either call or fold. Heads up, the small
+ * blind on the button pushes or folds and the big blind calls or folds. Three handed,
+ * the button acts first, then the small blind, then the big blind, each pushing,
+ * calling or folding depending on what's happened before them.
+ *
+ * The equilibrium is found by fictitious play: each round, every player's best
+ * response to everyone else's strategy so far is worked out, class by class, and
+ * blended into their average strategy, which settles towards the Nash equilibrium.
+ * Everything's measured in big blinds won or lost, ignoring tournament payouts.
+ *
+ * All ins are valued with precomputed heads-up equities between the 169 classes of
+ * starting hands. A hand's opponents are dealt with the cards it holds taken out,
+ * but not those each other holds, and three-way all ins are estimated from the
+ * heads-up equities between each pair of players, so three-handed results are a
+ * close approximation rather than exact.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use parallel;
+use preflop;
+use preflop::Equities;
+use preflop::StartingHand;
+use preflop::CLASSES;
+
+/* decision points heads up, by their place in the list of spots */
+const HEADS_UP_PUSH: usize = 0;
+const HEADS_UP_CALL: usize = 1;
+
+/* decision points three handed */
+const BUTTON_PUSH: usize = 0;
+const SMALL_PUSH: usize = 1;
+const BIG_CALL_SMALL: usize = 2;
+const SMALL_CALL_BUTTON: usize = 3;
+const BIG_CALL_BUTTON: usize = 4;
+const BIG_CALL_BOTH: usize = 5;
+
+/* a decision point, and how often each class of starting hand goes all in from it */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spot
+{
+  pub name: &'static str,  /* who's deciding and what they're facing, eg: BB call vs SB */
+  pub frequency: Vec<f64>  /* how often each class pushes or calls, from 0.0 to 1.0, by class index */
+}
+
+impl Spot
+{
+  /* return the classes that push or call more often than not, in range shorthand */
+  pub fn range(&self) -> String
+  {
+    let hands: Vec<StartingHand> = StartingHand::all().into_iter()
+                                                      .filter(|hand| self.frequency[hand.index()] > 0.5)
+                                                      .collect();
+    preflop::notation(&hands)
+  }
+
+  /* return the percentage of all starting hands that push or call */
+  pub fn percent(&self) -> f64
+  {
+    let played: f64 = StartingHand::all().iter()
+                                         .map(|hand| hand.combos().len() as f64 * self.frequency[hand.index()])
+                                         .sum();
+    played * 100.0 / 1326.0
+  }
+}
+
+/* solve
+   Find the push or fold equilibrium for a table of equal stacks
+   => equities = preflop equities between classes of starting hands
+      players = 2 for heads up, or 3 for three handed
+      stack = big blinds each player starts the hand with, antes and blinds included
+      ante = big blinds each player antes, or 0.0 for none
+      iterations = rounds of fictitious play
+   <= each decision point in the order it's reached, or None if there aren't two or three
+      players or the stack doesn't cover the big blind and ante */
+pub fn solve(equities: &Equities, players: usize, stack: f64, ante: f64, iterations: usize) -> Option<Vec<Spot>>
+{
+  let names: &[&'static str] = match players
+  {
+    2 => &["SB push", "BB call"],
+    3 => &["BTN push", "SB push", "BB call vs SB", "SB call vs BTN", "BB call vs BTN", "BB call vs BTN and SB"],
+    _ => return None
+  };
+  if ante < 0.0 || stack <= 1.0 + ante
+  {
+    return None;
+  }
+
+  /* chance of an opponent holding each class, given our own */
+  let mut given = vec![0.0; CLASSES * CLASSES];
+  for hand in 0..CLASSES
+  {
+    let ways: f64 = (0..CLASSES).map(|other| equities.weight(hand, other)).sum();
+    for other in 0..CLASSES
+    {
+      given[hand * CLASSES + other] = equities.weight(hand, other) / ways;
+    }
+  }
+
+  let mut game = Game { equities, given, three_way: Vec::new(), stack, ante };
+  if players == 3
+  {
+    game.three_way = game.three_way_equities();
+  }
+  let mut strategy = vec![vec![0.0; CLASSES]; names.len()];
+  for round in 1..(iterations + 1)
+  {
+    let best = match players
+    {
+      2 => game.heads_up(&strategy),
+      _ => game.three_handed(&strategy)
+    };
+    for (spot, gains) in strategy.iter_mut().zip(best.iter())
+    {
+      for (frequency, gain) in spot.iter_mut().zip(gains.iter())
+      {
+        let response = if *gain > 0.0 { 1.0 } else { 0.0 };
+        *frequency = *frequency + (response - *frequency) / (round + 1) as f64;
+      }
+    }
+  }
+
+  Some(names.iter().zip(strategy).map(|(name, frequency)| Spot { name, frequency }).collect())
+}
+
+/* what's needed to value each decision */
+struct Game<'a>
+{
+  equities: &'a Equities,
+  given: Vec<f64>,     /* chance of an opponent holding a class given our own, by both indexes */
+  three_way: Vec<f64>, /* share of the pot a class wins against two others, by all three indexes */
+  stack: f64,
+  ante: f64
+}
+
+impl<'a> Game<'a>
+{
+  /* chance of an opponent holding a class given our own */
+  fn odds(&self, hand: usize, other: usize) -> f64
+  {
+    self.given[hand * CLASSES + other]
+  }
+
+  /* share of the pot a class wins against another */
+  fn equity(&self, hand: usize, against: usize) -> f64
+  {
+    self.equities.equity(hand, against)
+  }
+
+  /* work out the share of the pot each class wins against every two others, estimated from the
+     heads-up equities by the chance of it beating both of them, against the same for each of them */
+  fn three_way_equities(&self) -> Vec<f64>
+  {
+    let rows = parallel::map(CLASSES, |hand|
+    {
+      let mut row = Vec::with_capacity(CLASSES * CLASSES);
+      for first in 0..CLASSES
+      {
+        for second in 0..CLASSES
+        {
+          let ours = self.equity(hand, first) * self.equity(hand, second);
+          let theirs = self.equity(first, hand) * self.equity(first, second) +
+                       self.equity(second, hand) * self.equity(second, first);
+          row.push(ours / (ours + theirs));
+        }
+      }
+      row
+    });
+    rows.concat()
+  }
+
+  /* shares of the pot a class wins against a first opponent's class and each class of second opponent */
+  fn equity3(&self, hand: usize, first: usize) -> &[f64]
+  {
+    let start = (hand * CLASSES + first) * CLASSES;
+    &self.three_way[start..(start + CLASSES)]
+  }
+
+  /* heads_up
+     Work out what each class gains by going all in rather than folding, heads up
+     => strategy = how often each class pushes or calls at each spot so far
+     <= big blinds gained by pushing or calling over folding, at each spot for each class,
+        scaled by the chance of facing the decision */
+  fn heads_up(&self, strategy: &[Vec<f64>]) -> Vec<Vec<f64>>
+  {
+    let (stack, ante) = (self.stack, self.ante);
+    let (push, call) = (&strategy[HEADS_UP_PUSH], &strategy[HEADS_UP_CALL]);
+
+    let pushing = (0..CLASSES).map(|hand|
+    {
+      let raised: f64 = (0..CLASSES).map(|other|
+      {
+        let showdown = self.equity(hand, other) * 2.0 * stack - stack;
+        self.odds(hand, other) * ((1.0 - call[other]) * (1.0 + ante) + call[other] * showdown)
+      }).sum();
+      raised + 0.5 + ante
+    }).collect();
+
+    let calling = (0..CLASSES).map(|hand|
+    {
+      (0..CLASSES).map(|other| self.odds(hand, other) * push[other] *
+                               (self.equity(hand, other) * 2.0 * stack - stack + 1.0 + ante)).sum()
+    }).collect();
+
+    vec![pushing, calling]
+  }
+
+  /* three_handed
+     Work out what each class gains by going all in rather than folding, three handed
+     => strategy = how often each class pushes or calls at each spot so far
+     <= big blinds gained by pushing or calling over folding, at each spot for each class,
+        scaled by the chance of facing the decision */
+  fn three_handed(&self, strategy: &[Vec<f64>]) -> Vec<Vec<f64>>
+  {
+    let (stack, ante) = (self.stack, self.ante);
+    let button_push = &strategy[BUTTON_PUSH];
+    let small_push = &strategy[SMALL_PUSH];
+    let big_call_small = &strategy[BIG_CALL_SMALL];
+    let small_call = &strategy[SMALL_CALL_BUTTON];
+    let big_call_button = &strategy[BIG_CALL_BUTTON];
+    let big_call_both = &strategy[BIG_CALL_BOTH];
+
+    /* pots when two players are all in, with the blind and antes of whoever folded */
+    let against_small = 2.0 * stack + 1.0 + ante;
+    let against_big = 2.0 * stack + 0.5 + ante;
+    let blinds_only = 2.0 * stack + ante;
+
+    /* the button pushing: everyone folds, one of the blinds calls, or both do */
+    let button = parallel::map(CLASSES, |hand|
+    {
+      let (mut small_folds, mut small_calls, mut small_share) = (0.0, 0.0, 0.0);
+      let (mut big_folds, mut big_calls, mut big_share, mut big_folds_after) = (0.0, 0.0, 0.0, 0.0);
+      for other in 0..CLASSES
+      {
+        let odds = self.odds(hand, other);
+        small_folds += odds * (1.0 - small_call[other]);
+        small_calls += odds * small_call[other];
+        small_share += odds * small_call[other] * self.equity(hand, other);
+        big_folds += odds * (1.0 - big_call_button[other]);
+        big_calls += odds * big_call_button[other];
+        big_share += odds * big_call_button[other] * self.equity(hand, other);
+        big_folds_after += odds * (1.0 - big_call_both[other]);
+      }
+
+      let mut both = 0.0;
+      let odds = &self.given[(hand * CLASSES)..((hand + 1) * CLASSES)];
+      for small in (0..CLASSES).filter(|small| small_call[*small] > 0.0)
+      {
+        let shares = self.equity3(hand, small);
+        let called: f64 = (0..CLASSES).map(|big| odds[big] * big_call_both[big] * (shares[big] * 3.0 * stack - stack)).sum();
+        both += odds[small] * small_call[small] * called;
+      }
+
+      small_folds * big_folds * (1.5 + 2.0 * ante) +
+      (small_share * against_small - small_calls * stack) * big_folds_after +
+      small_folds * (big_share * against_big - big_calls * stack) +
+      both + ante
+    });
+
+    /* the small blind pushing once the button folds */
+    let small = (0..CLASSES).map(|hand|
+    {
+      let raised: f64 = (0..CLASSES).map(|other|
+      {
+        let showdown = self.equity(hand, other) * blinds_only - stack;
+        self.odds(hand, other) * ((1.0 - big_call_small[other]) * (1.0 + 2.0 * ante) + big_call_small[other] * showdown)
+      }).sum();
+      raised + 0.5 + ante
+    }).collect();
+
+    /* the big blind calling the small blind */
+    let big_small = (0..CLASSES).map(|hand|
+    {
+      (0..CLASSES).map(|other| self.odds(hand, other) * small_push[other] *
+                               (self.equity(hand, other) * blinds_only - stack + 1.0 + ante)).sum()
+    }).collect();
+
+    /* the small blind calling the button, with the big blind still to come */
+    let small_button = parallel::map(CLASSES, |hand|
+    {
+      let mut gain = 0.0;
+      let odds = &self.given[(hand * CLASSES)..((hand + 1) * CLASSES)];
+      for pusher in (0..CLASSES).filter(|pusher| button_push[*pusher] > 0.0)
+      {
+        let heads_up = self.equity(hand, pusher) * against_small - stack;
+        let shares = self.equity3(hand, pusher);
+        let showdown: f64 = (0..CLASSES).map(|big| odds[big] * ((1.0 - big_call_both[big]) * heads_up +
+                                                                 big_call_both[big] * (shares[big] * 3.0 * stack - stack)))
+                                        .sum();
+        gain += odds[pusher] * button_push[pusher] * (showdown + 0.5 + ante);
+      }
+      gain
+    });
+
+    /* the big blind calling the button once the small blind folds. the chance of the small
+       blind folding is the same whatever the button holds, so it doesn't change the decision */
+    let big_button = (0..CLASSES).map(|hand|
+    {
+      (0..CLASSES).map(|other| self.odds(hand, other) * button_push[other] *
+                               (self.equity(hand, other) * against_big - stack + 1.0 + ante)).sum()
+    }).collect();
+
+    /* the big blind calling both the button and the small blind */
+    let big_both = parallel::map(CLASSES, |hand|
+    {
+      let mut gain = 0.0;
+      let odds = &self.given[(hand * CLASSES)..((hand + 1) * CLASSES)];
+      for pusher in (0..CLASSES).filter(|pusher| button_push[*pusher] > 0.0)
+      {
+        let shares = self.equity3(hand, pusher);
+        let called: f64 = (0..CLASSES).map(|caller| odds[caller] * small_call[caller] *
+                                                    (shares[caller] * 3.0 * stack - stack + 1.0 + ante))
+                                      .sum();
+        gain += odds[pusher] * button_push[pusher] * called;
+      }
+      gain
+    });
+
+    vec![button, small, big_small, small_button, big_button, big_both]
+  }
+}
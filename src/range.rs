@@ -0,0 +1,341 @@
+/* range.rs
+ * Describe the hole cards an opponent could hold as a range: a list of holdings,
+ * each weighted by how likely the opponent is to play it
+ *
+ * Hold 'em ranges can be written in the usual shorthand, as a comma-separated list of:
+ * pairs (QQ), suited (AKs) or offsuit (AKo) hands, or both (AK); any of these followed
+ * by + for every better hand of the same kind (TT+, A2s+), or two of them joined by
+ * a - for everything in between (22-66, K9o-KJo); or exact hands such as AhKh
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use card::Suit;
+use card::Value;
+use deck::Deck;
+use deck::combinations;
+use rules::Rules;
+
+/* one holding in the range */
+#[derive(Debug, Clone)]
+pub struct Combo
+{
+  pub cards: Vec<Card>, /* the hole cards */
+  pub weight: f64       /* relative likelihood of holding them, from 0.0 upwards */
+}
+
+#[derive(Debug, Clone)]
+pub struct Range
+{
+  combos: Vec<Combo>
+}
+
+/* a range with no combos yet */
+impl Default for Range
+{
+  fn default() -> Range
+  {
+    Range::new()
+  }
+}
+
+impl Range
+{
+  /* create a new empty range */
+  pub fn new() -> Range
+  {
+    Range { combos: Vec::<Combo>::new() }
+  }
+
+  /* uniform
+     Create a range holding every possible holding with equal weight
+     => size = number of hole cards per holding (2 for hold 'em)
+        dead = cards that can't be in the range, eg, our own hole cards
+     <= Range object */
+  pub fn uniform(size: usize, dead: &[Card]) -> Range
+  {
+    Range::uniform_from(Deck::new(), size, dead)
+  }
+
+  /* uniform_for
+     Create a range holding every hand a player could be dealt in the given game,
+     with equal weight
+     => rules = game being played, which sets the deck and number of hole cards
+        dead = cards that can't be in the range, eg, our own hole cards
+     <= Range object */
+  pub fn uniform_for(rules: &Rules, dead: &[Card]) -> Range
+  {
+    Range::uniform_from(Deck::with_rules(rules), rules.hole_cards(), dead)
+  }
+
+  /* build a uniform range from the cards left in a deck once the dead cards are out */
+  fn uniform_from(mut deck: Deck, size: usize, dead: &[Card]) -> Range
+  {
+    for card in dead.iter()
+    {
+      deck.remove(card);
+    }
+
+    let mut range = Range::new();
+    combinations(deck.cards(), size, |cards| range.add(cards, 1.0));
+    range
+  }
+
+  /* parse
+     Create a hold 'em range from its shorthand, with every holding weighted equally
+     => text = case insensitive, comma-separated list of hands, eg: QQ+, AKs, A5s-A2s, AhKh
+     <= Range object, or None if the text isn't a valid range */
+  pub fn parse(text: &str) -> Option<Range>
+  {
+    let mut range = Range::new();
+    for token in text.split(',')
+    {
+      let token: String = token.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+      for cards in parse_token(&token)?.iter()
+      {
+        if !range.combos.iter().any(|combo| combo.cards.contains(&cards[0]) && combo.cards.contains(&cards[1]))
+        {
+          range.add(cards, 1.0);
+        }
+      }
+    }
+
+    if range.combos.is_empty()
+    {
+      return None;
+    }
+    Some(range)
+  }
+
+  /* add a holding to the range with the given weight */
+  pub fn add(&mut self, cards: &[Card], weight: f64)
+  {
+    self.combos.push(Combo { cards: cards.to_vec(), weight });
+  }
+
+  /* return the list of holdings in the range */
+  pub fn combos(&self) -> &Vec<Combo>
+  {
+    &self.combos
+  }
+
+  /* live
+     List the holdings still possible once some cards are known to be elsewhere
+     => known = cards that can't be in the opponent's hand, eg, ours, the board,
+                and any dead cards seen exposed or mucked
+     <= holdings with a positive weight that share no cards with known */
+  pub fn live(&self, known: &[Card]) -> Vec<&Combo>
+  {
+    self.combos.iter()
+               .filter(|combo| combo.weight > 0.0 &&
+                               !combo.cards.iter().any(|card| known.contains(card)))
+               .collect()
+  }
+}
+
+/* a class of starting hands, as written in range shorthand */
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Class
+{
+  high: u32,          /* higher card value, from 2 to 14 */
+  low: u32,           /* lower card value, the same as high for a pair */
+  suited: Option<bool> /* suited or offsuit only, or None for both */
+}
+
+/* parse_token
+   Turn one hand of range shorthand into the holdings it covers
+   => token = lowercase hand with no whitespace, eg: tt+, a2s-a5s, ahkh
+   <= list of holdings, or None if the token isn't valid */
+fn parse_token(token: &str) -> Option<Vec<[Card; 2]>>
+{
+  let chars: Vec<char> = token.chars().collect();
+
+  /* an exact hand: two cards, value then suit */
+  if chars.len() == 4 && suit_of(chars[1]).is_some() && suit_of(chars[3]).is_some()
+  {
+    let first = Card::new(&token[0..2])?;
+    let second = Card::new(&token[2..4])?;
+    if first == second
+    {
+      return None;
+    }
+    return Some(vec![[first, second]]);
+  }
+
+  let mut classes = Vec::<Class>::new();
+  if let Some(prefix) = token.strip_suffix('+')
+  {
+    /* a pair and every higher pair, or a hand and every one with a better kicker */
+    let class = parse_class(prefix)?;
+    let top = if class.high == class.low { 14 } else { class.high - 1 };
+    for value in class.low..(top + 1)
+    {
+      let high = if class.high == class.low { value } else { class.high };
+      classes.push(Class { high, low: value, suited: class.suited });
+    }
+  }
+  else if let Some((from, to)) = token.split_once('-')
+  {
+    /* everything between two pairs, or two hands with the same top card */
+    let from = parse_class(from)?;
+    let to = parse_class(to)?;
+    let pairs = from.high == from.low && to.high == to.low;
+    if from.suited != to.suited || (!pairs && from.high != to.high)
+    {
+      return None;
+    }
+
+    let (lowest, highest) = if from.low < to.low { (from.low, to.low) } else { (to.low, from.low) };
+    for value in lowest..(highest + 1)
+    {
+      let high = if pairs { value } else { from.high };
+      classes.push(Class { high, low: value, suited: from.suited });
+    }
+  }
+  else
+  {
+    classes.push(parse_class(token)?);
+  }
+
+  let mut holdings = Vec::<[Card; 2]>::new();
+  for class in classes.iter()
+  {
+    for first in 0..4
+    {
+      for second in 0..4
+      {
+        let suited = first == second;
+        if (class.high == class.low && second <= first) || class.suited.is_some_and(|s| s != suited)
+        {
+          continue;
+        }
+        holdings.push([Card { value: Value::from_u32(class.high)?, suit: Suit::from_u32(first)? },
+                       Card { value: Value::from_u32(class.low)?, suit: Suit::from_u32(second)? }]);
+      }
+    }
+  }
+  Some(holdings)
+}
+
+/* parse_class
+   Turn a class of starting hands, such as qq, aks, t9o or kj, into its values
+   => text = lowercase class
+   <= Class object, or None if the text isn't valid */
+fn parse_class(text: &str) -> Option<Class>
+{
+  let chars: Vec<char> = text.chars().collect();
+  if chars.len() < 2 || chars.len() > 3
+  {
+    return None;
+  }
+
+  let first = value_of(chars[0])?;
+  let second = value_of(chars[1])?;
+  let suited = match chars.get(2)
+  {
+    None => None,
+    Some('s') => Some(true),
+    Some('o') => Some(false),
+    Some(_) => return None
+  };
+
+  /* pairs can't be suited, nor offsuit */
+  if first == second && suited.is_some()
+  {
+    return None;
+  }
+
+  let (high, low) = if first > second { (first, second) } else { (second, first) };
+  Some(Class { high, low, suited })
+}
+
+/* the value of a card value character, from 2 to 14 */
+fn value_of(c: char) -> Option<u32>
+{
+  match c
+  {
+    '2'..='9' => c.to_digit(10),
+    't' => Some(10),
+    'j' => Some(11),
+    'q' => Some(12),
+    'k' => Some(13),
+    'a' => Some(14),
+    _ => None
+  }
+}
+
+/* the suit of a card suit letter */
+fn suit_of(c: char) -> Option<Suit>
+{
+  match c
+  {
+    'h' => Some(Suit::Heart),
+    'd' => Some(Suit::Diamond),
+    'c' => Some(Suit::Club),
+    's' => Some(Suit::Spade),
+    _ => None
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  /* the values in each holding of a range, higher first */
+  fn values(range: &Range) -> Vec<(u32, u32)>
+  {
+    range.combos().iter().map(|combo|
+    {
+      let (a, b) = (combo.cards[0].value.to_u32(), combo.cards[1].value.to_u32());
+      if a > b { (a, b) } else { (b, a) }
+    }).collect()
+  }
+
+  #[test]
+  fn pairs_and_better()
+  {
+    let range = Range::parse("TT+").unwrap();
+    assert_eq!(range.combos().len(), 5 * 6);
+    assert!(values(&range).iter().all(|(high, low)| high == low && *low >= 10));
+  }
+
+  #[test]
+  fn suited_kickers_and_better()
+  {
+    let range = Range::parse("A2s+").unwrap();
+    assert_eq!(range.combos().len(), 12 * 4);
+    assert!(range.combos().iter().all(|combo| combo.cards[0].suit == combo.cards[1].suit));
+    assert!(values(&range).iter().all(|(high, low)| *high == 14 && *low >= 2 && *low <= 13));
+  }
+
+  #[test]
+  fn offsuit_span()
+  {
+    let range = Range::parse("K9o-KJo").unwrap();
+    assert_eq!(range.combos().len(), 3 * 12);
+    assert!(range.combos().iter().all(|combo| combo.cards[0].suit != combo.cards[1].suit));
+    assert!(values(&range).iter().all(|(high, low)| *high == 13 && *low >= 9 && *low <= 11));
+  }
+
+  #[test]
+  fn exact_hands_and_overlaps()
+  {
+    let range = Range::parse("AhKh").unwrap();
+    assert_eq!(range.combos().len(), 1);
+    assert_eq!(range.combos()[0].cards, vec![Card::new("ah").unwrap(), Card::new("kh").unwrap()]);
+
+    /* a holding listed twice only counts once */
+    assert_eq!(Range::parse("tt+, AhKh, aks").unwrap().combos().len(), 30 + 4);
+  }
+
+  #[test]
+  fn invalid_ranges()
+  {
+    for text in ["AAs", "AKx", "K9o-QJo", "22-AKs", "AhAh", ""].iter()
+    {
+      assert!(Range::parse(text).is_none(), "{}", text);
+    }
+  }
+}
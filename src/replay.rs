@@ -0,0 +1,117 @@
+/* replay.rs
+ * Replay a hold 'em hand street by street for review once it's over: given our hole
+ * cards, the opponent's cards or range, and the board as it fell, work out where we
+ * stood on each street, and which card dealt did the most to change that.
+ *
+ * Equity before the flop is estimated from a sample of deals, as there are too many
+ * boards to run through. From the flop on, it's exact.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use equity;
+use equity::Equity;
+use hand::Hand;
+use range::Range;
+use rules::Rules;
+
+/* where we stood on one street */
+#[derive(Debug, Clone)]
+pub struct Street
+{
+  pub name: &'static str,        /* preflop, flop, turn or river */
+  pub board: Vec<Card>,          /* community cards out by this street */
+  pub hand: Hand,                /* our best hand on this street */
+  pub equity: Equity,            /* our equity against the opponent's range */
+  pub ahead: usize,              /* opponent holdings beating us on this street */
+  pub live: usize,               /* opponent holdings still possible on this street */
+  pub swing: Option<(Card, f64)> /* the card dealt on this street that moved our equity most, and by how much */
+}
+
+/* replay
+   Work out where we stood on each street of a hold 'em hand
+   => hole = our two hole cards
+      range = opponent's cards or range
+      board = community cards as they fell: none, the flop, or up to the river
+      dead = other cards known to be out of play
+      deals = number of random deals to sample for equity where needed
+      seed = starting point for the random numbers
+   <= each street in turn, up to the last card on the board, or None if the hand isn't
+      valid, or the opponent's range has no holdings left in it */
+pub fn replay(hole: &[Card], range: &Range, board: &[Card], dead: &[Card], deals: usize, seed: u64)
+  -> Option<Vec<Street>>
+{
+  if hole.len() != 2 || (!board.is_empty() && board.len() < 3) || board.len() > 5
+  {
+    return None;
+  }
+
+  let rules = Rules::holdem();
+  let mut streets = Vec::<Street>::new();
+  for (name, size) in [("preflop", 0), ("flop", 3), ("turn", 4), ("river", 5)].iter().cloned()
+  {
+    if size > board.len()
+    {
+      break;
+    }
+
+    let so_far = &board[0..size];
+    let equity = street_equity(&rules, hole, so_far, dead, range, deals, seed)?;
+    let hand = rules.hand(hole, so_far);
+
+    /* count the holdings that beat us now */
+    let mut out = hole.to_vec();
+    out.extend_from_slice(so_far);
+    out.extend_from_slice(dead);
+    let opponents = range.live(&out);
+    let ahead = opponents.iter().filter(|combo| rules.score(&combo.cards, so_far) > hand.score()).count();
+
+    /* on the turn and river, the new card made all the difference. on the flop, judge
+       each card by how much our equity would differ had it not come yet */
+    let swing = match streets.last()
+    {
+      None => None,
+      Some(last) if size > 3 => Some((board[size - 1], equity.equity - last.equity.equity)),
+      Some(_) =>
+      {
+        let mut biggest: Option<(Card, f64)> = None;
+        for card in so_far.iter()
+        {
+          let others: Vec<Card> = so_far.iter().filter(|c| *c != card).cloned().collect();
+          let without = street_equity(&rules, hole, &others, dead, range, deals, seed)?;
+          let change = equity.equity - without.equity;
+          if biggest.is_none_or(|(_, most)| change.abs() > most.abs())
+          {
+            biggest = Some((*card, change));
+          }
+        }
+        biggest
+      }
+    };
+
+    streets.push(Street
+    {
+      name,
+      board: so_far.to_vec(),
+      hand,
+      equity,
+      ahead,
+      live: opponents.len(),
+      swing
+    });
+  }
+  Some(streets)
+}
+
+/* work out our equity with part of the board out, running through every runout
+   from the flop on, and sampling before that */
+fn street_equity(rules: &Rules, hole: &[Card], board: &[Card], dead: &[Card], range: &Range,
+                 deals: usize, seed: u64) -> Option<Equity>
+{
+  match board.len()
+  {
+    0..=2 => equity::sample(rules, hole, board, dead, range, deals, seed),
+    _ => equity::versus_range(rules, hole, board, dead, range)
+  }
+}
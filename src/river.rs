@@ -0,0 +1,649 @@
+/* river.rs
+ * Solve heads-up river spots with counterfactual regret minimization (CFR), or its
+ * faster-converging variant CFR+. Given both players' ranges, the board, the pot and
+ * the stacks behind, and the bet and raise sizes allowed, it builds the tree of
+ * every way the betting can go and plays it against itself until each player's
+ * strategy, combo by combo, settles towards the equilibrium.
+ *
+ * Player 0 is out of position and acts first, player 1 is in position. Each iteration
+ * works through the whole tree for every combo at once, first for one player and then
+ * the other. Showdowns are valued by sorting both ranges by hand strength, using the
+ * scores worked out up front, and sweeping through them, taking out opponent combos
+ * that share a card with ours, so each showdown costs little more than the size of the
+ * ranges rather than their product.
+ *
+ * Values are in chips: what a player ends up taking from the pot, less what they put
+ * in on the river, so the two players' values add up to the starting pot.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use equity;
+use range::Range;
+use rules::Game;
+use rules::Rules;
+use table::Action;
+
+use std::cell::RefCell;
+
+/* the bets and raises allowed, as fractions of the pot */
+#[derive(Debug, Clone, PartialEq)]
+pub struct BetSizes
+{
+  pub bets: Vec<f64>,     /* sizes of the first bet, eg 0.5 for half the pot */
+  pub raises: Vec<f64>,   /* sizes of raises, as fractions of the pot once the bet's called */
+  pub max_raises: usize,  /* most raises allowed after the first bet */
+  pub all_in: bool        /* true to always allow betting or raising all in as well */
+}
+
+/* a point in the tree where a player decides, with their strategy there */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision
+{
+  pub line: Vec<Action>,       /* actions taken on the river to get here */
+  pub player: usize,           /* 0 out of position or 1 in position */
+  pub actions: Vec<Action>,    /* what the player can do. bets and raises are to a total for the river */
+  pub strategy: Vec<Vec<f64>>  /* for each of the player's combos, how often it takes each action */
+}
+
+/* what happens at a point in the tree */
+#[derive(Debug, Clone)]
+enum Kind
+{
+  Decision { player: usize, actions: Vec<Action>, children: Vec<usize> },
+  Fold { folder: usize },
+  Showdown
+}
+
+#[derive(Debug, Clone)]
+struct Node
+{
+  kind: Kind,
+  committed: [u64; 2],          /* chips each player has put in on the river */
+  line: Vec<Action>,            /* actions taken to get here */
+  regrets: RefCell<Vec<f64>>,   /* regret for each action and combo, action by action, at decisions */
+  totals: RefCell<Vec<f64>>     /* each action's share of the average strategy for each combo, likewise */
+}
+
+/* how to work through the tree */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Walk
+{
+  Update { plus: bool, weight: f64 }, /* one iteration of CFR or CFR+ */
+  Average,                            /* play the average strategies */
+  BestResponse                        /* the traversing player plays the best reply to the other's average */
+}
+
+pub struct River
+{
+  pot: u64,                      /* chips in the pot at the start of the river */
+  combos: [Vec<[Card; 2]>; 2],   /* each player's combos that don't clash with the board */
+  weights: [Vec<f64>; 2],        /* how likely each combo is, from the ranges */
+  scores: [Vec<u32>; 2],         /* each combo's hand strength on the board */
+  order: [Vec<usize>; 2],        /* each player's combos from weakest to strongest */
+  same: [Vec<Option<usize>>; 2], /* the other player's combo with the same two cards, if any */
+  nodes: Vec<Node>,              /* the betting tree, starting with player 0's first decision */
+  iterations: usize              /* iterations run so far */
+}
+
+impl River
+{
+  /* new
+     Set up a river spot to solve
+     => rules = game being played, which must be hold 'em played for high
+        ranges = the ranges of the player out of position and the player in position
+        board = the five community cards
+        pot = chips in the pot before the river's betting
+        stack = chips each player has behind, or the smaller stack if they differ
+        sizes = bets and raises allowed
+     <= River object, or None if the game isn't supported, the board isn't five different
+        cards, the pot is empty, or either range has nothing left once the board's out */
+  pub fn new(rules: &Rules, ranges: [&Range; 2], board: &[Card], pot: u64, stack: u64, sizes: &BetSizes) -> Option<River>
+  {
+    if rules.game != Game::Holdem || rules.hilo || rules.wilds.any() || board.len() != 5 ||
+       equity::has_duplicates(board) || pot == 0
+    {
+      return None;
+    }
+
+    let mut river = River
+    {
+      pot,
+      combos: [Vec::new(), Vec::new()],
+      weights: [Vec::new(), Vec::new()],
+      scores: [Vec::new(), Vec::new()],
+      order: [Vec::new(), Vec::new()],
+      same: [Vec::new(), Vec::new()],
+      nodes: Vec::new(),
+      iterations: 0
+    };
+
+    for (player, range) in ranges.iter().enumerate()
+    {
+      for combo in range.live(board).iter()
+      {
+        if combo.cards.len() != 2 || combo.cards[0] == combo.cards[1]
+        {
+          return None;
+        }
+
+        /* a combo written twice in a range only counts once */
+        let cards = [combo.cards[0], combo.cards[1]];
+        if river.combos[player].iter().any(|other| mask(other) == mask(&cards))
+        {
+          continue;
+        }
+        river.combos[player].push(cards);
+        river.weights[player].push(combo.weight);
+        river.scores[player].push(rules.score(&cards, board));
+      }
+      if river.combos[player].is_empty()
+      {
+        return None;
+      }
+
+      let mut order: Vec<usize> = (0..river.combos[player].len()).collect();
+      order.sort_by_key(|combo| river.scores[player][*combo]);
+      river.order[player] = order;
+    }
+    for player in 0..2
+    {
+      river.same[player] = river.combos[player].iter()
+                                               .map(|cards| river.combos[1 - player].iter()
+                                                                                    .position(|other| mask(other) == mask(cards)))
+                                               .collect();
+    }
+
+    river.build(sizes, stack, [0, 0], 0, 0, Vec::new());
+    Some(river)
+  }
+
+  /* solve
+     Run more iterations, carrying on from any run before
+     => iterations = number of iterations to run
+        plus = true for CFR+, which floors regrets at zero and weights later iterations
+               more in the average strategy, or false for plain CFR */
+  pub fn solve(&mut self, iterations: usize, plus: bool)
+  {
+    for _ in 0..iterations
+    {
+      self.iterations += 1;
+      let weight = if plus { self.iterations as f64 } else { 1.0 };
+      for traverser in 0..2
+      {
+        self.walk(0, traverser, &self.weights[1 - traverser], Walk::Update { plus, weight });
+      }
+    }
+  }
+
+  /* return the combos in a player's range, 0 out of position or 1 in position, in the order
+     strategies and values are given */
+  pub fn combos(&self, player: usize) -> &[[Card; 2]]
+  {
+    &self.combos[player]
+  }
+
+  /* return every decision in the tree, in the order they're reached, with the average
+     strategy so far at each */
+  pub fn decisions(&self) -> Vec<Decision>
+  {
+    let mut decisions = Vec::<Decision>::new();
+    for node in self.nodes.iter()
+    {
+      if let Kind::Decision { player, ref actions, .. } = node.kind
+      {
+        let count = self.combos[player].len();
+        let average = self.average(node, actions.len(), count);
+        let strategy = (0..count).map(|combo| (0..actions.len()).map(|action| average[action * count + combo]).collect())
+                                 .collect();
+        decisions.push(Decision { line: node.line.clone(), player, actions: actions.clone(), strategy });
+      }
+    }
+    decisions
+  }
+
+  /* ev
+     Work out what each of a player's combos is worth when both play their average strategies
+     => player = 0 out of position or 1 in position
+     <= value of each combo in chips, or None for combos every opposing combo clashes with */
+  pub fn ev(&self, player: usize) -> Vec<Option<f64>>
+  {
+    let values = self.walk(0, player, &self.weights[1 - player], Walk::Average);
+    let matched = self.matched(player);
+    values.iter().zip(matched.iter())
+                 .map(|(value, matched)| if *matched > 0.0 { Some(value / matched) } else { None })
+                 .collect()
+  }
+
+  /* exploitability
+     Work out how far the average strategies are from the equilibrium: how much, on average,
+     each player could gain by switching to the best reply to the other's strategy
+     <= chips per hand, zero at the equilibrium */
+  pub fn exploitability(&self) -> f64
+  {
+    let mut best = 0.0;
+    for player in 0..2
+    {
+      let values = self.walk(0, player, &self.weights[1 - player], Walk::BestResponse);
+      let matched = self.matched(player);
+      let won: f64 = values.iter().zip(self.weights[player].iter()).map(|(value, weight)| value * weight).sum();
+      let deals: f64 = matched.iter().zip(self.weights[player].iter()).map(|(matched, weight)| matched * weight).sum();
+      best += won / deals;
+    }
+    (best - self.pot as f64) / 2.0
+  }
+
+  /* build
+     Add a point in the betting tree, and everything after it, returning its index
+     => sizes = bets and raises allowed
+        stack = chips each player had behind at the start of the river
+        committed = chips each player's put in so far
+        player = whose turn it is
+        raises = raises made so far, not counting the first bet
+        line = actions taken to get here */
+  fn build(&mut self, sizes: &BetSizes, stack: u64, committed: [u64; 2], player: usize, raises: usize,
+           line: Vec<Action>) -> usize
+  {
+    let index = self.nodes.len();
+    self.nodes.push(Node { kind: Kind::Showdown, committed, line: line.clone(), regrets: RefCell::new(Vec::new()),
+                           totals: RefCell::new(Vec::new()) });
+
+    let other = 1 - player;
+    let facing = committed[other] > committed[player];
+    let mut actions = Vec::<Action>::new();
+    if facing
+    {
+      actions.push(Action::Fold);
+      actions.push(Action::Call);
+
+      /* raises are at least the size of the last bet or raise, and can't be made by or against
+         a player who's all in */
+      let current = committed[other];
+      let least = current + (current - committed[player]).max(1);
+      if raises < sizes.max_raises && current < stack
+      {
+        let after_call = self.pot + 2 * current;
+        let mut amounts: Vec<u64> = sizes.raises.iter()
+                                                .map(|fraction| current + (fraction * after_call as f64).round() as u64)
+                                                .collect();
+        if sizes.all_in
+        {
+          amounts.push(stack);
+        }
+        for amount in ordered(amounts, least, stack)
+        {
+          actions.push(Action::Raise(amount));
+        }
+      }
+    }
+    else
+    {
+      actions.push(Action::Check);
+      let mut amounts: Vec<u64> = sizes.bets.iter().map(|fraction| (fraction * self.pot as f64).round() as u64).collect();
+      if sizes.all_in
+      {
+        amounts.push(stack);
+      }
+      for amount in ordered(amounts, 1, stack)
+      {
+        actions.push(Action::Bet(amount));
+      }
+    }
+
+    let mut children = Vec::<usize>::new();
+    for action in actions.iter()
+    {
+      let mut next = line.clone();
+      next.push(*action);
+      let child = match *action
+      {
+        Action::Fold => self.leaf(Kind::Fold { folder: player }, committed, next),
+        Action::Call => self.leaf(Kind::Showdown, [committed[other]; 2], next),
+        Action::Check if player == 1 => self.leaf(Kind::Showdown, committed, next),
+        Action::Check => self.build(sizes, stack, committed, other, raises, next),
+        Action::Bet(amount) | Action::Raise(amount) =>
+        {
+          let mut after = committed;
+          after[player] = amount;
+          let raised = if facing { raises + 1 } else { raises };
+          self.build(sizes, stack, after, other, raised, next)
+        },
+        Action::AllIn => continue
+      };
+      children.push(child);
+    }
+
+    let count = actions.len() * self.combos[player].len();
+    self.nodes[index].regrets = RefCell::new(vec![0.0; count]);
+    self.nodes[index].totals = RefCell::new(vec![0.0; count]);
+    self.nodes[index].kind = Kind::Decision { player, actions, children };
+    index
+  }
+
+  /* add the end of a line of betting to the tree, returning its index */
+  fn leaf(&mut self, kind: Kind, committed: [u64; 2], line: Vec<Action>) -> usize
+  {
+    self.nodes.push(Node { kind, committed, line, regrets: RefCell::new(Vec::new()),
+                           totals: RefCell::new(Vec::new()) });
+    self.nodes.len() - 1
+  }
+
+  /* walk
+     Work through the tree from a point, for every one of a player's combos at once
+     => node = index of the point in the tree
+        traverser = player whose combos are being valued
+        reach = chance of the other player's combos getting here, including their weights
+        walk = whether to update the strategies, play the average strategies, or find the
+               traverser's best reply to the other's average strategy
+     <= value of each of the traverser's combos, each weighted by the chance of the other
+        player's combos it doesn't clash with getting here */
+  fn walk(&self, node: usize, traverser: usize, reach: &[f64], walk: Walk) -> Vec<f64>
+  {
+    let point = &self.nodes[node];
+    let (player, children) = match point.kind
+    {
+      Kind::Fold { folder } => return self.fold(point.committed, folder, traverser, reach),
+      Kind::Showdown => return self.showdown(point.committed, traverser, reach),
+      Kind::Decision { player, ref children, .. } => (player, children)
+    };
+
+    let count = self.combos[player].len();
+    let strategy = match walk
+    {
+      Walk::Update { .. } => current(&point.regrets.borrow(), children.len(), count),
+      _ => self.average(point, children.len(), count)
+    };
+    let mine = self.combos[traverser].len();
+
+    /* the traverser's decision: value each action, then score the regret of not taking it */
+    if player == traverser
+    {
+      let values: Vec<Vec<f64>> = children.iter().map(|child| self.walk(*child, traverser, reach, walk)).collect();
+      let result: Vec<f64> = (0..mine).map(|combo| match walk
+      {
+        Walk::BestResponse => values.iter().map(|value| value[combo]).fold(f64::MIN, f64::max),
+        _ => (0..children.len()).map(|action| strategy[action * count + combo] * values[action][combo]).sum()
+      }).collect();
+
+      if let Walk::Update { plus, .. } = walk
+      {
+        let mut regrets = point.regrets.borrow_mut();
+        for (action, value) in values.iter().enumerate()
+        {
+          for combo in 0..mine
+          {
+            let regret = regrets[action * count + combo] + value[combo] - result[combo];
+            regrets[action * count + combo] = if plus { regret.max(0.0) } else { regret };
+          }
+        }
+      }
+      return result;
+    }
+
+    /* the other player's decision: follow each action with the combos that take it */
+    let mut result = vec![0.0; mine];
+    for (action, child) in children.iter().enumerate()
+    {
+      let onward: Vec<f64> = (0..count).map(|combo| reach[combo] * strategy[action * count + combo]).collect();
+      let values = self.walk(*child, traverser, &onward, walk);
+      for combo in 0..mine
+      {
+        result[combo] += values[combo];
+      }
+    }
+
+    /* the average strategy is weighted by how often each combo gets here */
+    if let Walk::Update { weight, .. } = walk
+    {
+      let mut totals = point.totals.borrow_mut();
+      for (cell, share) in strategy.iter().enumerate()
+      {
+        totals[cell] += weight * reach[cell % count] * share;
+      }
+    }
+    result
+  }
+
+  /* return the average strategy so far at a decision, action by action, with combos that have
+     never got here spread evenly over the actions */
+  fn average(&self, node: &Node, actions: usize, count: usize) -> Vec<f64>
+  {
+    let totals = node.totals.borrow();
+    let mut strategy = vec![1.0 / actions as f64; actions * count];
+    for combo in 0..count
+    {
+      let sum: f64 = (0..actions).map(|action| totals[action * count + combo]).sum();
+      if sum > 0.0
+      {
+        for action in 0..actions
+        {
+          strategy[action * count + combo] = totals[action * count + combo] / sum;
+        }
+      }
+    }
+    strategy
+  }
+
+  /* clashes
+     Total up the other player's reach, overall and by each card, so the combos sharing a card
+     with one of ours can be taken out
+     => player = the other player
+        reach = chance of each of their combos getting here
+     <= total reach, and the reach of combos holding each card */
+  fn clashes(&self, player: usize, reach: &[f64]) -> (f64, [f64; 52])
+  {
+    let mut total = 0.0;
+    let mut by_card = [0.0; 52];
+    for (cards, chance) in self.combos[player].iter().zip(reach.iter())
+    {
+      total += chance;
+      for card in cards.iter()
+      {
+        by_card[slot(card)] = by_card[slot(card)] + chance;
+      }
+    }
+    (total, by_card)
+  }
+
+  /* return the other player's reach that doesn't clash with each of a player's combos */
+  fn matching(&self, player: usize, reach: &[f64]) -> Vec<f64>
+  {
+    let (total, by_card) = self.clashes(1 - player, reach);
+    self.combos[player].iter().zip(self.same[player].iter())
+               .map(|(cards, same)| total - by_card[slot(&cards[0])] - by_card[slot(&cards[1])] +
+                                    same.map_or(0.0, |other| reach[other]))
+               .collect()
+  }
+
+  /* return the weight of the other player's range that doesn't clash with each of a player's combos */
+  fn matched(&self, player: usize) -> Vec<f64>
+  {
+    self.matching(player, &self.weights[1 - player])
+  }
+
+  /* value a player's combos when someone folds: the other player takes the pot */
+  fn fold(&self, committed: [u64; 2], folder: usize, traverser: usize, reach: &[f64]) -> Vec<f64>
+  {
+    let pot = (self.pot + committed[0] + committed[1]) as f64;
+    let payoff = match folder == traverser
+    {
+      true => -(committed[traverser] as f64),
+      false => pot - committed[traverser] as f64
+    };
+    self.matching(traverser, reach).iter().map(|matched| matched * payoff).collect()
+  }
+
+  /* showdown
+     Value a player's combos at showdown, sweeping up through both ranges in order of strength
+     to total the other player's reach below each of ours, then down to total it above
+     => committed = chips each player put in on the river
+        traverser = player whose combos are being valued
+        reach = chance of each of the other player's combos getting here
+     <= value of each of the traverser's combos */
+  fn showdown(&self, committed: [u64; 2], traverser: usize, reach: &[f64]) -> Vec<f64>
+  {
+    let other = 1 - traverser;
+    let pot = (self.pot + committed[0] + committed[1]) as f64;
+    let cost = committed[traverser] as f64;
+    let (mine, theirs) = (&self.order[traverser], &self.order[other]);
+    let (my_scores, their_scores) = (&self.scores[traverser], &self.scores[other]);
+
+    /* reach of the other player's weaker hands, less those sharing a card. a combo with both
+       our cards would have the same score, so it's never among them */
+    let mut beaten = vec![0.0; mine.len()];
+    let (mut total, mut by_card) = (0.0, [0.0; 52]);
+    let mut next = 0;
+    for combo in mine.iter()
+    {
+      while next < theirs.len() && their_scores[theirs[next]] < my_scores[*combo]
+      {
+        let cards = &self.combos[other][theirs[next]];
+        total += reach[theirs[next]];
+        by_card[slot(&cards[0])] = by_card[slot(&cards[0])] + reach[theirs[next]];
+        by_card[slot(&cards[1])] = by_card[slot(&cards[1])] + reach[theirs[next]];
+        next += 1;
+      }
+      let cards = &self.combos[traverser][*combo];
+      beaten[*combo] = total - by_card[slot(&cards[0])] - by_card[slot(&cards[1])];
+    }
+
+    /* and of their stronger hands */
+    let mut beating = vec![0.0; mine.len()];
+    let (mut total, mut by_card) = (0.0, [0.0; 52]);
+    let mut next = 0;
+    for combo in mine.iter().rev()
+    {
+      while next < theirs.len() && their_scores[theirs[theirs.len() - 1 - next]] > my_scores[*combo]
+      {
+        let index = theirs[theirs.len() - 1 - next];
+        let cards = &self.combos[other][index];
+        total += reach[index];
+        by_card[slot(&cards[0])] = by_card[slot(&cards[0])] + reach[index];
+        by_card[slot(&cards[1])] = by_card[slot(&cards[1])] + reach[index];
+        next += 1;
+      }
+      let cards = &self.combos[traverser][*combo];
+      beating[*combo] = total - by_card[slot(&cards[0])] - by_card[slot(&cards[1])];
+    }
+
+    /* ties split the pot, so each hand's share is half of what's left after wins and losses */
+    let matched = self.matching(traverser, reach);
+    (0..mine.len()).map(|combo| pot * (matched[combo] + beaten[combo] - beating[combo]) / 2.0 - cost * matched[combo])
+                          .collect()
+  }
+}
+
+/* work out the strategy to play next from the regrets so far, action by action: each action
+   in proportion to its positive regret, or all evenly if none has any */
+fn current(regrets: &[f64], actions: usize, count: usize) -> Vec<f64>
+{
+  let mut strategy = vec![1.0 / actions as f64; actions * count];
+  for combo in 0..count
+  {
+    let sum: f64 = (0..actions).map(|action| regrets[action * count + combo].max(0.0)).sum();
+    if sum > 0.0
+    {
+      for action in 0..actions
+      {
+        strategy[action * count + combo] = regrets[action * count + combo].max(0.0) / sum;
+      }
+    }
+  }
+  strategy
+}
+
+/* sort bet sizes, bringing them up to the smallest allowed and down to all in, without repeats */
+fn ordered(amounts: Vec<u64>, least: u64, most: u64) -> Vec<u64>
+{
+  let mut amounts: Vec<u64> = amounts.iter().map(|amount| (*amount).max(least).min(most)).collect();
+  amounts.sort();
+  amounts.dedup();
+  amounts
+}
+
+/* return a card's place among the 52, for totalling by card */
+fn slot(card: &Card) -> usize
+{
+  (card.suit.to_u32() * 13 + card.value.to_u32() - 2) as usize
+}
+
+/* return a bit for each card, to spot combos holding the same two cards */
+fn mask(cards: &[Card]) -> u64
+{
+  cards.iter().fold(0, |mask, card| mask | 1 << slot(card))
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  fn river(ranges: [&str; 2], board: &str, sizes: &BetSizes) -> River
+  {
+    let (oop, ip) = (Range::parse(ranges[0]).unwrap(), Range::parse(ranges[1]).unwrap());
+    River::new(&Rules::holdem(), [&oop, &ip], &cards(board), 100, 100, sizes).unwrap()
+  }
+
+  #[test]
+  fn polarised_versus_bluff_catcher()
+  {
+    /* kings are the nuts and five-four misses everything, while queens beat only the bluffs.
+       facing a pot-sized bet, queens should call half the time so the bluffs break even */
+    let sizes = BetSizes { bets: vec![1.0], raises: Vec::new(), max_raises: 0, all_in: false };
+    let mut river = river(["kk, 54s", "qq"], "kh 9s 7h 3d 2c", &sizes);
+
+    river.solve(10, true);
+    let early = river.exploitability();
+    river.solve(990, true);
+    let late = river.exploitability();
+    assert!(late < early / 10.0);
+    assert!(late > -1e-9 && late < 0.5);
+
+    let facing = river.decisions().into_iter().find(|decision| decision.line == vec![Action::Bet(100)]).unwrap();
+    assert_eq!(facing.actions, vec![Action::Fold, Action::Call]);
+    let calls: f64 = facing.strategy.iter().map(|strategy| strategy[1]).sum::<f64>() / facing.strategy.len() as f64;
+    assert!((calls - 0.5).abs() < 0.05);
+  }
+
+  #[test]
+  fn showdowns_match_brute_force()
+  {
+    /* ace-king of hearts is in both ranges, and most combos share a card with one opposite */
+    let sizes = BetSizes { bets: vec![0.5], raises: Vec::new(), max_raises: 0, all_in: false };
+    let river = river(["AhKh, AhQh, KdQd, AdQs, 77, 9h8h", "AhKh, AhKd, KhQh, AsQd, QdJd, 77, 99"],
+                      "qc 7d 9s 4h 2s", &sizes);
+
+    for traverser in 0..2
+    {
+      let other = 1 - traverser;
+      let reach: Vec<f64> = (0..river.combos[other].len()).map(|combo| 0.1 + (combo as f64 * 0.37) % 1.0).collect();
+      for committed in [[0, 0], [50, 50]].iter()
+      {
+        let values = river.showdown(*committed, traverser, &reach);
+        let pot = (100 + committed[0] + committed[1]) as f64;
+        for (combo, value) in values.iter().enumerate()
+        {
+          let mine = &river.combos[traverser][combo];
+          let mut expected = 0.0;
+          for (index, theirs) in river.combos[other].iter().enumerate()
+          {
+            if theirs.iter().any(|card| mine.contains(card))
+            {
+              continue;
+            }
+            let (ours, score) = (river.scores[traverser][combo], river.scores[other][index]);
+            let share = if ours > score { 1.0 } else if ours == score { 0.5 } else { 0.0 };
+            expected += reach[index] * (pot * share - committed[traverser] as f64);
+          }
+          assert!((value - expected).abs() < 1e-9, "{:?} {} {}", mine, value, expected);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,55 @@
+/* rng.rs
+ * A small, seedable pseudo-random number generator (SplitMix64) so that sampled
+ * results and shuffles can be repeated exactly from their seed
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+#[derive(Debug, Clone)]
+pub struct Rng
+{
+  state: u64
+}
+
+impl Rng
+{
+  /* create a generator from a seed. the same seed always gives the same numbers */
+  pub fn new(seed: u64) -> Rng
+  {
+    Rng { state: seed }
+  }
+
+  /* return the next 64 random bits */
+  pub fn next_u64(&mut self) -> u64
+  {
+    self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+  }
+
+  /* below
+     => limit = one more than the largest number wanted, above zero
+     <= random number from 0 to limit - 1 */
+  pub fn below(&mut self, limit: usize) -> usize
+  {
+    ((self.next_u64() as u128 * limit as u128) >> 64) as usize
+  }
+
+  /* return a random number from 0.0 up to, but not including, 1.0 */
+  pub fn fraction(&mut self) -> f64
+  {
+    (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+  }
+
+  /* shuffle a list into a random order */
+  pub fn shuffle<T>(&mut self, items: &mut [T])
+  {
+    for i in (1..items.len()).rev()
+    {
+      let j = self.below(i + 1);
+      items.swap(i, j);
+    }
+  }
+}
@@ -0,0 +1,357 @@
+/* rules.rs
+ * Describe the game being played, and so how many hole cards each player gets and
+ * how they combine with the board to make a hand
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use deck::combinations;
+use hand::Hand;
+use hand::Ranking;
+use hand::Wilds;
+use low;
+use low::Lowball;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Game
+{
+  Holdem,      /* two hole cards, play the best five of hole and board cards */
+  Omaha(usize), /* four or more hole cards, play exactly two of them with exactly three from the board */
+  Stud,         /* seven cards each, some face up, with no board: play the best five of them */
+  Draw(usize),  /* five cards each with no board, and this many chances to swap some for new ones */
+  Pineapple(bool) /* three hole cards, one thrown before the flop, or after it in crazy pineapple */
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rules
+{
+  pub game: Game,
+  pub hilo: bool,               /* split the pot between the best high hand and the best eight or better low */
+  pub ranking: Ranking,         /* how hands rank against each other, and so which deck is used */
+  pub lowball: Option<Lowball>, /* the lowest hand wins the whole pot, instead of the highest */
+  pub wilds: Wilds              /* which cards, if any, are wild */
+}
+
+impl Rules
+{
+  /* create rules for a game played for high, with a standard deck and no wild cards */
+  fn plain(game: Game) -> Rules
+  {
+    Rules { game, hilo: false, ranking: Ranking::Standard, lowball: None, wilds: Wilds::none() }
+  }
+
+  /* create rules for Texas hold 'em */
+  pub fn holdem() -> Rules
+  {
+    Rules::plain(Game::Holdem)
+  }
+
+  /* create rules for Omaha with the given number of hole cards, usually four,
+     or five or six for big O and six-card Omaha */
+  pub fn omaha(hole_cards: usize) -> Rules
+  {
+    Rules::plain(Game::Omaha(hole_cards))
+  }
+
+  /* create rules for Omaha hi-lo, eight or better, with the given number of hole cards */
+  pub fn omaha_hilo(hole_cards: usize) -> Rules
+  {
+    Rules { hilo: true, ..Rules::plain(Game::Omaha(hole_cards)) }
+  }
+
+  /* create rules for short deck (6+) hold 'em, played with the twos to fives taken
+     out of the deck. some games also rank three of a kind above straights */
+  pub fn short_deck(trips_beat_straights: bool) -> Rules
+  {
+    let ranking = Ranking::ShortDeck { trips_beat_straights };
+    Rules { ranking, ..Rules::plain(Game::Holdem) }
+  }
+
+  /* create rules for seven-card stud */
+  pub fn stud() -> Rules
+  {
+    Rules::plain(Game::Stud)
+  }
+
+  /* create rules for razz: seven-card stud where the best ace-to-five low wins */
+  pub fn razz() -> Rules
+  {
+    Rules { lowball: Some(Lowball::AceToFive), ..Rules::plain(Game::Stud) }
+  }
+
+  /* create rules for a draw game with the given number of draws, and optionally
+     played for low, eg five-card draw, or deuce-to-seven triple draw */
+  pub fn draw(draws: usize, lowball: Option<Lowball>) -> Rules
+  {
+    Rules { lowball, ..Rules::plain(Game::Draw(draws)) }
+  }
+
+  /* create rules for pineapple, where the discard is made before the flop, or
+     crazy pineapple, where it's made after the flop */
+  pub fn pineapple(crazy: bool) -> Rules
+  {
+    Rules::plain(Game::Pineapple(crazy))
+  }
+
+  /* from_name
+     Look up a game by name, as given on the command line
+     => name = holdem, omaha (or plo), omaha5 (or plo5), omaha6 (or plo6),
+               omaha8 (or plo8), for Omaha hi-lo eight or better,
+               shortdeck (or 6plus) and shortdeck-trips (or 6plus-trips), for short
+               deck with three of a kind ranked above straights, stud, for seven-card
+               stud, and razz, draw, for five-card draw, 27draw and 27triple, for
+               deuce-to-seven single and triple draw, and a5triple, for ace-to-five
+               triple draw, pineapple and crazy-pineapple
+     <= Rules object, or None if the name isn't recognized */
+  pub fn from_name(name: &str) -> Option<Rules>
+  {
+    match name
+    {
+      "holdem"            => Some(Rules::holdem()),
+      "omaha" | "plo"     => Some(Rules::omaha(4)),
+      "omaha5" | "plo5"   => Some(Rules::omaha(5)),
+      "omaha6" | "plo6"   => Some(Rules::omaha(6)),
+      "omaha8" | "plo8"   => Some(Rules::omaha_hilo(4)),
+      "shortdeck" | "6plus" => Some(Rules::short_deck(false)),
+      "shortdeck-trips" | "6plus-trips" => Some(Rules::short_deck(true)),
+      "stud"              => Some(Rules::stud()),
+      "razz"              => Some(Rules::razz()),
+      "draw"              => Some(Rules::draw(1, None)),
+      "27draw"            => Some(Rules::draw(1, Some(Lowball::DeuceToSeven))),
+      "27triple"          => Some(Rules::draw(3, Some(Lowball::DeuceToSeven))),
+      "a5triple"          => Some(Rules::draw(3, Some(Lowball::AceToFive))),
+      "pineapple"         => Some(Rules::pineapple(false)),
+      "crazy-pineapple"   => Some(Rules::pineapple(true)),
+      _ => None
+    }
+  }
+
+  /* return the number of hole cards each player is dealt */
+  pub fn hole_cards(&self) -> usize
+  {
+    match self.game
+    {
+      Game::Holdem => 2,
+      Game::Omaha(cards) => cards,
+      Game::Stud => 7,
+      Game::Draw(_) => 5,
+      Game::Pineapple(_) => 3
+    }
+  }
+
+  /* score
+     Score the best hand a player can make under these rules
+     => hole = player's hole cards, or all of their cards so far in stud and draw games
+        board = community cards dealt so far
+     <= score, comparable with any other score under the same rules from
+        the same number of cards */
+  pub fn score(&self, hole: &[Card], board: &[Card]) -> u32
+  {
+    match self.game
+    {
+      Game::Holdem | Game::Stud | Game::Draw(_) | Game::Pineapple(_) =>
+      {
+        let mut cards = [hole[0]; 10];
+        let count = hole.len() + board.len();
+        cards[0..hole.len()].copy_from_slice(hole);
+        cards[hole.len()..count].copy_from_slice(board);
+        self.score_cards(&cards[0..count])
+      },
+
+      Game::Omaha(_) =>
+      {
+        let mut best = 0;
+        omaha_hands(hole, board, |cards|
+        {
+          let score = self.score_cards(cards);
+          if score > best
+          {
+            best = score;
+          }
+        });
+        best
+      }
+    }
+  }
+
+  /* low_score
+     Score the best eight or better low a player can make, in hi-lo games
+     => hole = player's hole cards
+        board = community cards dealt so far
+     <= low score, higher is better, or None if the game isn't hi-lo or
+        the player has no qualifying low */
+  pub fn low_score(&self, hole: &[Card], board: &[Card]) -> Option<u32>
+  {
+    if !self.hilo
+    {
+      return None;
+    }
+
+    match self.game
+    {
+      Game::Holdem | Game::Stud | Game::Draw(_) | Game::Pineapple(_) =>
+      {
+        let mut cards = hole.to_vec();
+        cards.extend_from_slice(board);
+        low::ace_to_five(&cards, Some(low::EIGHT_OR_BETTER))
+      },
+
+      Game::Omaha(_) =>
+      {
+        let mut best = None;
+        omaha_hands(hole, board, |cards|
+        {
+          let score = low::ace_to_five(cards, Some(low::EIGHT_OR_BETTER));
+          if score > best
+          {
+            best = score;
+          }
+        });
+        best
+      }
+    }
+  }
+
+  /* hand
+     Pick out the best hand a player can make under these rules
+     => hole = player's hole cards
+        board = community cards dealt so far
+     <= calculated Hand object holding the cards that make up the best hand */
+  pub fn hand(&self, hole: &[Card], board: &[Card]) -> Hand
+  {
+    let mut best = Hand::new();
+    match self.game
+    {
+      Game::Holdem | Game::Stud | Game::Draw(_) | Game::Pineapple(_) =>
+      {
+        for card in hole.iter().chain(board.iter())
+        {
+          best.add(*card);
+        }
+        best.calc_wild(&self.ranking, &self.wilds);
+      },
+
+      Game::Omaha(_) =>
+      {
+        omaha_hands(hole, board, |cards|
+        {
+          let mut hand = Hand::new();
+          for card in cards.iter()
+          {
+            hand.add(*card);
+          }
+          hand.calc_wild(&self.ranking, &self.wilds);
+          if hand.score() > best.score()
+          {
+            best = hand;
+          }
+        });
+      }
+    }
+    best
+  }
+
+  /* describe
+     Describe the best hand a player can make under these rules, with their low
+     in hi-lo games
+     => hole = player's hole cards, or all of their cards so far in stud and draw games
+        board = community cards dealt so far
+     <= string describing the hand */
+  pub fn describe(&self, hole: &[Card], board: &[Card]) -> String
+  {
+    if let Some(lowball) = self.lowball
+    {
+      return lowball.describe(self.score(hole, board));
+    }
+
+    let high = self.hand(hole, board).describe();
+    if !self.hilo
+    {
+      return high;
+    }
+
+    match self.low_score(hole, board)
+    {
+      Some(low_score) => format!("{}, {}", high, low::describe(low_score)),
+      None => format!("{}, no low", high)
+    }
+  }
+
+  /* score a set of cards as a hand on its own, high or low as the game is played */
+  fn score_cards(&self, cards: &[Card]) -> u32
+  {
+    match self.lowball
+    {
+      Some(lowball) => lowball.score(cards),
+      None if self.wilds.any() => Hand::score_cards_wild(cards, &self.ranking, &self.wilds),
+      None => Hand::score_cards_with(cards, &self.ranking)
+    }
+  }
+}
+
+/* omaha_hands
+   Run through every hand an Omaha player could make: two hole cards with three
+   board cards, or with all of the board if fewer than three are out
+   => hole = player's hole cards
+      board = community cards dealt so far
+      f = closure called with each hand's cards */
+fn omaha_hands<F>(hole: &[Card], board: &[Card], mut f: F) where F: FnMut(&[Card])
+{
+  let from_board = if board.len() < 3 { board.len() } else { 3 };
+  let mut cards = [hole[0]; 5];
+  for first in 0..hole.len()
+  {
+    for second in (first + 1)..hole.len()
+    {
+      cards[0] = hole[first];
+      cards[1] = hole[second];
+      combinations(board, from_board, |picked|
+      {
+        cards[2..2 + from_board].copy_from_slice(picked);
+        f(&cards[0..2 + from_board]);
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use hand::Strength;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  fn strength(rules: &Rules, hole: &str, board: &str) -> Strength
+  {
+    rules.hand(&cards(hole), &cards(board)).strength()
+  }
+
+  #[test]
+  fn omaha_plays_exactly_two_hole_cards()
+  {
+    /* the same hands padded out to four, five and six hole cards with blanks */
+    for (hole_cards, blanks) in [(4, ""), (5, " ks"), (6, " ks qc")].iter()
+    {
+      let rules = Rules::omaha(*hole_cards);
+      let hand = |hole: &str| format!("{}{}", hole, blanks);
+
+      /* four hearts on the board need two more in the hand, not one */
+      assert_eq!(strength(&rules, &hand("qh 9c 9d 8s"), "ah kh 7h 4h 2c"), Strength::Pair);
+      assert_eq!(strength(&rules, &hand("qh jh 9c 8s"), "ah kh 7h 4h 2c"), Strength::Flush);
+
+      /* and a straight on the board doesn't play at all */
+      assert_eq!(strength(&rules, &hand("kc kd qs 2s"), "9h 8d 7c 6s 5h"), Strength::Pair);
+      assert_eq!(strength(&rules, &hand("th kc kd 2s"), "9h 8d 7c 6s 2h"), Strength::Pair);
+      assert_eq!(strength(&rules, &hand("th jc kd 2s"), "9h 8d 7c 6s 2h"), Strength::Straight);
+
+      /* which the score agrees with */
+      assert!(rules.score(&cards(&hand("qh 9c 9d 8s")), &cards("ah kh 7h 4h 2c")) <
+              rules.score(&cards(&hand("qh jh 9c 8s")), &cards("ah kh 7h 4h 2c")));
+    }
+  }
+}
@@ -0,0 +1,204 @@
+/* showdown.rs
+ * Settle a pot at showdown: given what each player put in, their hole cards, and the
+ * board, work out exactly how many chips each player wins. Players all in for less can
+ * only win what they could match from each opponent, so the chips are split into a main
+ * pot and side pots, each going to the best hand among those who put in enough for it.
+ *
+ * Tied hands split a pot evenly. Any odd chips left over go one at a time to the tied
+ * winners closest to the left of the button. In hi-lo games, each pot is halved between
+ * the best high hand and the best qualifying low, with any odd chip going to the high
+ * half, and the high hand takes it all if no low qualifies.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use equity;
+use rules::Rules;
+
+/* the main pot or a side pot */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pot
+{
+  pub size: u64,           /* chips in the pot */
+  pub eligible: Vec<usize>, /* players who can win it */
+  pub winners: Vec<usize>  /* players who won some of it */
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payout
+{
+  pub won: Vec<u64>, /* chips each player wins, in the order given */
+  pub pots: Vec<Pot> /* the main pot first, then each side pot */
+}
+
+/* resolve
+   Share out the chips at showdown
+   => rules = game being played, which must have a board
+      contributions = chips each player put in over the hand, including antes, blinds,
+                      and any bets of their own that no one called
+      hole = each player's hole cards, or None if they folded
+      board = the community cards
+      button = index of the player on the button, for odd chips
+   <= Payout object, or None if the lists differ in length, everyone folded, a card
+      appears twice, or the board isn't complete when two or more players are left */
+pub fn resolve(rules: &Rules, contributions: &[u64], hole: &[Option<Vec<Card>>], board: &[Card], button: usize)
+  -> Option<Payout>
+{
+  let count = contributions.len();
+  if hole.len() != count || button >= count
+  {
+    return None;
+  }
+
+  /* players still in are ranked by their hands, unless they're the only one left */
+  let contenders: Vec<usize> = (0..count).filter(|player| hole[*player].is_some()).collect();
+  let mut high = vec![0; count];
+  let mut low: Vec<Option<u32>> = vec![None; count];
+  match contenders.len()
+  {
+    0 => return None,
+    1 => (),
+    _ =>
+    {
+      let mut known = board.to_vec();
+      for cards in hole.iter().flatten()
+      {
+        known.extend_from_slice(cards);
+      }
+      if board.len() != 5 || equity::has_duplicates(&known)
+      {
+        return None;
+      }
+
+      for player in contenders.iter()
+      {
+        let cards = hole[*player].as_ref()?;
+        high[*player] = rules.score(cards, board);
+        low[*player] = rules.low_score(cards, board);
+      }
+    }
+  }
+
+  /* each pot holds what everyone put in up to the next contender's all-in, with the top pot
+     taking anything over that, eg, chips put in by players who later folded */
+  let mut levels: Vec<u64> = contenders.iter().map(|player| contributions[*player]).collect();
+  levels.sort();
+  levels.dedup();
+
+  /* odd chips go to the winners first to the left of the button */
+  let order: Vec<usize> = (1..(count + 1)).map(|offset| (button + offset) % count).collect();
+  let mut payout = Payout { won: vec![0; count], pots: Vec::new() };
+  let mut below = 0;
+  for (step, level) in levels.iter().enumerate()
+  {
+    let top = step == levels.len() - 1;
+    let size: u64 = contributions.iter()
+                                 .map(|chips| match top
+                                 {
+                                   true => chips - chips.min(&below),
+                                   false => chips.min(level) - chips.min(&below)
+                                 })
+                                 .sum();
+    below = *level;
+
+    let eligible: Vec<usize> = order.iter().cloned().filter(|player| contenders.contains(player) &&
+                                                                     contributions[*player] >= *level).collect();
+    let best_low = eligible.iter().filter_map(|player| low[*player]).max();
+    let (high_share, low_share) = match best_low
+    {
+      Some(_) => (size - size / 2, size / 2),
+      None => (size, 0)
+    };
+
+    let best_high = eligible.iter().map(|player| high[*player]).max().unwrap_or(0);
+    let high_winners: Vec<usize> = eligible.iter().cloned().filter(|player| high[*player] == best_high).collect();
+    let low_winners: Vec<usize> = eligible.iter().cloned().filter(|player| best_low.is_some() && low[*player] == best_low)
+                                          .collect();
+    split(high_share, &high_winners, &mut payout.won);
+    split(low_share, &low_winners, &mut payout.won);
+
+    let winners = eligible.iter().cloned().filter(|player| high_winners.contains(player) || low_winners.contains(player))
+                                 .collect();
+    payout.pots.push(Pot { size, eligible, winners });
+  }
+  Some(payout)
+}
+
+/* share chips evenly among winners listed in order from the left of the button, with the
+   odd chips going one each to the first of them */
+fn split(chips: u64, winners: &[usize], won: &mut [u64])
+{
+  if winners.is_empty()
+  {
+    return;
+  }
+
+  let share = chips / winners.len() as u64;
+  let odd = (chips % winners.len() as u64) as usize;
+  for (place, winner) in winners.iter().enumerate()
+  {
+    won[*winner] = won[*winner] + share + if place < odd { 1 } else { 0 };
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  #[test]
+  fn side_pots_go_to_different_winners()
+  {
+    /* aces all in for 100, kings for 300, queens and fours put in 600 */
+    let hole = vec![Some(cards("ah ad")), Some(cards("kh kd")), Some(cards("qh qd")), Some(cards("4h 4d"))];
+    let payout = resolve(&Rules::holdem(), &[100, 300, 600, 600], &hole, &cards("2c 7d 9s jh 3c"), 3).unwrap();
+
+    assert_eq!(payout.won, vec![400, 600, 600, 0]);
+    let pots: Vec<(u64, Vec<usize>)> = payout.pots.iter().map(|pot| (pot.size, pot.winners.clone())).collect();
+    assert_eq!(pots, vec![(400, vec![0]), (600, vec![1]), (600, vec![2])]);
+    assert_eq!(payout.pots[2].eligible, vec![2, 3]);
+  }
+
+  #[test]
+  fn odd_chip_goes_left_of_the_button()
+  {
+    /* the board plays, so the two players left split 21 chips */
+    let hole = vec![None, Some(cards("2c 3d")), Some(cards("4c 5d"))];
+    let board = cards("as ks qd jc th");
+
+    let payout = resolve(&Rules::holdem(), &[1, 10, 10], &hole, &board, 1).unwrap();
+    assert_eq!(payout.won, vec![0, 10, 11]);
+    let payout = resolve(&Rules::holdem(), &[1, 10, 10], &hole, &board, 2).unwrap();
+    assert_eq!(payout.won, vec![0, 11, 10]);
+  }
+
+  #[test]
+  fn high_hand_scoops_without_a_low()
+  {
+    /* only one card eight or lower on the board, so no low qualifies */
+    let hole = vec![Some(cards("as ad 3c 4c")), Some(cards("ks kd 5c 6c"))];
+    let payout = resolve(&Rules::omaha_hilo(4), &[50, 50], &hole, &cards("kh qd 9s tc 2h"), 0).unwrap();
+
+    assert_eq!(payout.won, vec![0, 100]);
+    assert_eq!(payout.pots, vec![Pot { size: 100, eligible: vec![1, 0], winners: vec![1] }]);
+  }
+
+  #[test]
+  fn folded_chips_go_to_the_pots_they_reached()
+  {
+    /* the folder's 200 is 100 towards the main pot and 100 towards the side pot */
+    let hole = vec![Some(cards("ah ad")), None, Some(cards("kh kd")), Some(cards("4h 4d"))];
+    let payout = resolve(&Rules::holdem(), &[100, 200, 500, 500], &hole, &cards("2c 7d 9s jh 3c"), 3).unwrap();
+
+    assert_eq!(payout.won, vec![400, 0, 900, 0]);
+    let sizes: Vec<u64> = payout.pots.iter().map(|pot| pot.size).collect();
+    assert_eq!(sizes, vec![400, 900]);
+    assert_eq!(payout.pots[1].eligible, vec![2, 3]);
+  }
+}
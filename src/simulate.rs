@@ -0,0 +1,214 @@
+/* simulate.rs
+ * Pit bots against each other over many hands on the betting engine and measure how
+ * much each wins, in big blinds per hundred hands, with a confidence interval so we can
+ * tell skill from luck.
+ *
+ * Every hand starts with everyone back on the same stack, and the button moves round
+ * one seat a hand. The deck for each hand is shuffled from the seed and the hand's
+ * number, and the hands are split across threads in fixed pieces, with each piece
+ * getting its own freshly made bots, so the results depend only on the seed.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use bot::Bot;
+use deck::Deck;
+use parallel;
+use rng::Rng;
+use rules::Rules;
+use table::Action;
+use table::Blinds;
+use table::Table;
+
+/* hands handed to each thread at a time, each with a new set of bots */
+const HANDS_PER_PIECE: usize = 1000;
+
+/* how many standard errors either side of the win rate to cover 95% of outcomes */
+const CONFIDENCE: f64 = 1.96;
+
+/* makes a bot from a seed, for each piece of the simulation */
+pub type Maker = Box<dyn Fn(u64) -> Box<dyn Bot> + Sync>;
+
+/* how one bot did over the simulation */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing
+{
+  pub name: String,
+  pub hands: usize,     /* hands played */
+  pub won: f64,         /* big blinds won overall, or lost if negative */
+  pub bb_per_100: f64,  /* big blinds won per hundred hands */
+  pub interval: f64     /* 95% confidence interval either side of the win rate, in bb/100 */
+}
+
+pub struct Simulator
+{
+  rules: Rules,
+  blinds: Blinds,
+  stack: u64,                    /* chips everyone starts each hand with */
+  entrants: Vec<(String, Maker)> /* each bot's name and how to make it */
+}
+
+impl Simulator
+{
+  /* new
+     Set up a simulation with no bots yet
+     => rules = game to play, hold 'em or Omaha
+        blinds = forced bets
+        stack = chips everyone starts each hand with
+     <= Simulator object */
+  pub fn new(rules: Rules, blinds: Blinds, stack: u64) -> Simulator
+  {
+    Simulator { rules, blinds, stack, entrants: Vec::new() }
+  }
+
+  /* seat a bot in the next seat, given its name and how to make one from a seed */
+  pub fn seat(&mut self, name: &str, make: Maker)
+  {
+    self.entrants.push((name.to_string(), make));
+  }
+
+  /* run
+     Play the bots against each other
+     => hands = number of hands to play
+        seed = starting point for the shuffles and the bots' random numbers
+     <= how each bot did, in seat order, or None if there are fewer than two bots, no
+        hands to play, or the game isn't one the betting engine plays */
+  pub fn run(&self, hands: usize, seed: u64) -> Option<Vec<Standing>>
+  {
+    let seats = self.entrants.len();
+    let mut table = Table::new(self.rules, self.blinds);
+    for (name, _) in self.entrants.iter()
+    {
+      table.sit(name, self.stack);
+    }
+    if hands == 0 || table.deal(Deck::with_rules(&self.rules).cards().clone()).is_none()
+    {
+      return None;
+    }
+
+    /* each piece totals up each bot's winnings, and their squares, in big blinds */
+    let pieces = parallel::chunks(hands, HANDS_PER_PIECE);
+    let results = parallel::map(pieces, |piece|
+    {
+      let piece_seed = seed ^ (piece as u64).wrapping_mul(0x2545f4914f6cdd1d);
+      let mut bots: Vec<Box<dyn Bot>> = self.entrants.iter().enumerate()
+                                            .map(|(seat, (_, make))| make(piece_seed.wrapping_add(seat as u64 + 1)))
+                                            .collect();
+      let mut sums = vec![(0.0, 0.0); seats];
+
+      let first = piece * HANDS_PER_PIECE;
+      let last = if first + HANDS_PER_PIECE < hands { first + HANDS_PER_PIECE } else { hands };
+      for hand in first..last
+      {
+        let mut deck = Deck::with_rules(&self.rules).cards().clone();
+        Rng::new(seed ^ (hand as u64).wrapping_mul(0x9e3779b97f4a7c15)).shuffle(&mut deck);
+        let mut table = table.clone();
+        table.button = hand % seats;
+
+        let mut state = table.deal(deck).expect("a simulated table always has players and cards");
+        while let Some(player) = state.to_act
+        {
+          let legal = state.legal().expect("there's always something to do on a player's turn");
+          let action = bots[player].choose(&state, player, &legal);
+          if state.act(action).is_err()
+          {
+            state.act(if legal.check { Action::Check } else { Action::Fold }).expect("checking or folding is always allowed");
+          }
+          for (me, bot) in bots.iter_mut().enumerate()
+          {
+            bot.observe(&state, me);
+          }
+        }
+
+        for (player, sum) in state.players.iter().zip(sums.iter_mut())
+        {
+          let won = (player.won as f64 - player.invested as f64) / self.blinds.big as f64;
+          *sum = (sum.0 + won, sum.1 + won * won);
+        }
+      }
+      sums
+    });
+
+    let mut totals = vec![(0.0, 0.0); seats];
+    for sums in results.iter()
+    {
+      for (total, sum) in totals.iter_mut().zip(sums.iter())
+      {
+        *total = (total.0 + sum.0, total.1 + sum.1);
+      }
+    }
+
+    let count = hands as f64;
+    Some(self.entrants.iter().zip(totals.iter()).map(|((name, _), (sum, squares))|
+    {
+      let mean = sum / count;
+      let variance = if hands > 1 { (squares - sum * mean) / (count - 1.0) } else { 0.0 };
+      Standing
+      {
+        name: name.clone(),
+        hands,
+        won: *sum,
+        bb_per_100: mean * 100.0,
+        interval: CONFIDENCE * (variance.max(0.0) / count).sqrt() * 100.0
+      }
+    }).collect())
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use bot::CallingStation;
+  use bot::EquityBot;
+  use bot::RandomBot;
+
+  /* the equity bot is slow, so it's only seated for short runs */
+  fn simulator(rules: Rules, equity: bool) -> Simulator
+  {
+    let mut simulator = Simulator::new(rules, Blinds { small: 50, big: 100, ante: 10 }, 10000);
+    simulator.seat("random", Box::new(|seed| Box::new(RandomBot::new(seed))));
+    simulator.seat("station", Box::new(|_| Box::new(CallingStation)));
+    match equity
+    {
+      true => simulator.seat("equity", Box::new(|seed| Box::new(EquityBot::new(0.4, 0.6, 20, seed)))),
+      false => simulator.seat("random 2", Box::new(|seed| Box::new(RandomBot::new(seed))))
+    }
+    simulator
+  }
+
+  #[test]
+  fn same_seed_same_standings()
+  {
+    /* enough hands for more than one piece, with the last one short */
+    let quick = simulator(Rules::holdem(), false);
+    let standings = quick.run(2100, 7).unwrap();
+    assert_eq!(quick.run(2100, 7), Some(standings.clone()));
+    assert!(quick.run(2100, 8) != Some(standings));
+
+    let slow = simulator(Rules::holdem(), true);
+    assert_eq!(slow.run(50, 7), slow.run(50, 7));
+  }
+
+  #[test]
+  fn winnings_sum_to_zero()
+  {
+    for (rules, equity, hands) in [(Rules::holdem(), false, 1500), (Rules::omaha(4), false, 1500),
+                                   (Rules::holdem(), true, 50)].iter()
+    {
+      let standings = simulator(*rules, *equity).run(*hands, 1).unwrap();
+      assert!(standings.iter().all(|standing| standing.hands == *hands));
+      assert!(standings.iter().any(|standing| standing.won != 0.0));
+      assert!(standings.iter().map(|standing| standing.won).sum::<f64>().abs() < 1e-6);
+    }
+  }
+
+  #[test]
+  fn needs_two_bots_and_some_hands()
+  {
+    let mut lonely = Simulator::new(Rules::holdem(), Blinds { small: 1, big: 2, ante: 0 }, 200);
+    lonely.seat("station", Box::new(|_| Box::new(CallingStation)));
+    assert!(lonely.run(100, 1).is_none());
+    assert!(simulator(Rules::holdem(), false).run(0, 1).is_none());
+  }
+}
@@ -0,0 +1,300 @@
+/* stats.rs
+ * Profile players from their hand histories with the usual statistics a heads-up display
+ * would show: how often they play a hand, raise first in, three-bet, fold to three-bets,
+ * continuation bet, go to showdown, and win when they get there.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use history::Act;
+use history::HandHistory;
+use history::Street;
+use json::Json;
+
+/* how often a player did something out of the times they had the chance to */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ratio
+{
+  pub times: usize,  /* times they did it */
+  pub chances: usize /* times they could have */
+}
+
+impl Ratio
+{
+  /* return how often they did it, as a percentage, or None if they never had the chance */
+  pub fn percent(&self) -> Option<f64>
+  {
+    match self.chances
+    {
+      0 => None,
+      _ => Some(self.times as f64 * 100.0 / self.chances as f64)
+    }
+  }
+
+  /* add one chance, and one time if they took it */
+  fn count(&mut self, took: bool)
+  {
+    self.chances += 1;
+    if took
+    {
+      self.times += 1;
+    }
+  }
+
+  fn to_json(self) -> Json
+  {
+    Json::object(vec![
+      ("times", Json::Number(self.times as f64)),
+      ("chances", Json::Number(self.chances as f64))
+    ])
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats
+{
+  pub player: String,
+  pub hands: usize,            /* hands dealt in */
+  pub vpip: Ratio,             /* hands they voluntarily put money in preflop */
+  pub pfr: Ratio,              /* hands they raised preflop */
+  pub three_bet: Ratio,        /* re-raised when facing a single raise preflop */
+  pub fold_to_three_bet: Ratio, /* folded when their raise was re-raised preflop */
+  pub cbet: Ratio,             /* bet the flop, when first to bet after making the last raise preflop */
+  pub wtsd: Ratio,             /* went to showdown after seeing the flop */
+  pub wsd: Ratio               /* won money at showdown */
+}
+
+impl Stats
+{
+  /* create an empty set of statistics for a player */
+  fn new(player: &str) -> Stats
+  {
+    let none = Ratio { times: 0, chances: 0 };
+    Stats
+    {
+      player: player.to_string(),
+      hands: 0,
+      vpip: none,
+      pfr: none,
+      three_bet: none,
+      fold_to_three_bet: none,
+      cbet: none,
+      wtsd: none,
+      wsd: none
+    }
+  }
+
+  /* list each statistic's short name alongside it, in the order trackers show them */
+  pub fn ratios(&self) -> [(&'static str, Ratio); 7]
+  {
+    [("VPIP", self.vpip), ("PFR", self.pfr), ("3Bet", self.three_bet), ("F3Bet", self.fold_to_three_bet),
+     ("CBet", self.cbet), ("WTSD", self.wtsd), ("W$SD", self.wsd)]
+  }
+
+  /* to_json
+     Describe the statistics as JSON: an object holding the player's name, the number of
+     hands, and for each of vpip, pfr, three_bet, fold_to_three_bet, cbet, wtsd and wsd,
+     an object holding the times the player did it and the chances they had to
+     <= Json object */
+  pub fn to_json(&self) -> Json
+  {
+    Json::object(vec![
+      ("player", Json::string(&self.player)),
+      ("hands", Json::Number(self.hands as f64)),
+      ("vpip", self.vpip.to_json()),
+      ("pfr", self.pfr.to_json()),
+      ("three_bet", self.three_bet.to_json()),
+      ("fold_to_three_bet", self.fold_to_three_bet.to_json()),
+      ("cbet", self.cbet.to_json()),
+      ("wtsd", self.wtsd.to_json()),
+      ("wsd", self.wsd.to_json())
+    ])
+  }
+
+  /* add in what the player did in one hand */
+  fn add(&mut self, hand: &HandHistory)
+  {
+    let name = self.player.clone();
+    let player = name.as_str();
+    self.hands += 1;
+
+    /* run through the preflop betting, counting raises to spot three-bet chances. the
+       blinds don't count as a raise */
+    let mut raises = 0;
+    let mut opener: Option<&str> = None;
+    let mut aggressor: Option<&str> = None;
+    let (mut voluntary, mut raised) = (false, false);
+    let (mut three_bet_chance, mut three_bet_facing) = (false, false);
+    for action in hand.actions.iter().filter(|action| action.street == Street::Preflop)
+    {
+      let is_raise = matches!(action.act, Act::Raise { .. } | Act::Bet(_));
+      let voluntary_act = is_raise || matches!(action.act, Act::Call(_));
+      if action.player == player && (voluntary_act || matches!(action.act, Act::Fold | Act::Check))
+      {
+        if raises == 1 && opener != Some(player) && !three_bet_chance
+        {
+          three_bet_chance = true;
+          self.three_bet.count(is_raise);
+        }
+        if raises == 2 && opener == Some(player) && !three_bet_facing
+        {
+          three_bet_facing = true;
+          self.fold_to_three_bet.count(action.act == Act::Fold);
+        }
+        voluntary = voluntary || voluntary_act;
+        raised = raised || is_raise;
+      }
+
+      if is_raise
+      {
+        raises += 1;
+        if raises == 1
+        {
+          opener = Some(action.player.as_str());
+        }
+        aggressor = Some(action.player.as_str());
+      }
+    }
+    self.vpip.count(voluntary);
+    self.pfr.count(raised);
+
+    /* the preflop aggressor gets the chance to continuation bet if no one bets before them */
+    if aggressor == Some(player)
+    {
+      let mut bet = false;
+      for action in hand.actions.iter().filter(|action| action.street == Street::Flop)
+      {
+        if action.player == player
+        {
+          if !bet
+          {
+            self.cbet.count(matches!(action.act, Act::Bet(_)));
+          }
+          break;
+        }
+        bet = bet || matches!(action.act, Act::Bet(_) | Act::Raise { .. });
+      }
+    }
+
+    /* anyone who didn't fold before the flop saw it, all in or not */
+    let folded_preflop = hand.actions.iter().any(|action| action.player == player && action.street == Street::Preflop &&
+                                                          action.act == Act::Fold);
+    if hand.board.len() >= 3 && !folded_preflop
+    {
+      let showdown = hand.showdown && !hand.folded(player);
+      self.wtsd.count(showdown);
+      if showdown
+      {
+        self.wsd.count(hand.won(player) > 0.0);
+      }
+    }
+  }
+}
+
+/* collect
+   Work out every player's statistics over a set of hands
+   => hands = hand histories to go through
+   <= statistics for each player who was dealt in, most hands played first */
+pub fn collect(hands: &[HandHistory]) -> Vec<Stats>
+{
+  let mut players = Vec::<Stats>::new();
+  for hand in hands.iter()
+  {
+    /* players sitting out are listed in their seats, but never act */
+    for seat in hand.seats.iter().filter(|seat| hand.actions.iter().any(|action| action.player == seat.player))
+    {
+      let index = match players.iter().position(|stats| stats.player == seat.player)
+      {
+        Some(index) => index,
+        None =>
+        {
+          players.push(Stats::new(&seat.player));
+          players.len() - 1
+        }
+      };
+      players[index].add(hand);
+    }
+  }
+
+  players.sort_by(|a, b| b.hands.cmp(&a.hands).then(a.player.cmp(&b.player)));
+  players
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use history;
+
+  /* Alice opens and folds to Bob's three-bet, then Bob opens and continuation bets */
+  const SAMPLE: &str = "\
+PokerStars Hand #184759600001:  Hold'em No Limit ($0.05/$0.10 USD) - 2018/04/14 21:05:31 CET [2018/04/14 15:05:31 ET]
+Table 'Aaltje II' 6-max Seat #1 is the button
+Seat 1: Alice ($10 in chips)
+Seat 2: Bob ($10 in chips)
+Seat 3: Cat ($10 in chips)
+Bob: posts small blind $0.05
+Cat: posts big blind $0.10
+*** HOLE CARDS ***
+Alice: raises $0.20 to $0.30
+Bob: raises $0.70 to $1
+Cat: folds
+Alice: folds
+Uncalled bet ($0.70) returned to Bob
+Bob collected $0.70 from pot
+*** SUMMARY ***
+Total pot $0.70 | Rake $0
+Seat 1: Alice (button) folded before Flop
+Seat 2: Bob (small blind) collected ($0.70)
+Seat 3: Cat (big blind) folded before Flop
+
+PokerStars Hand #184759600002:  Hold'em No Limit ($0.05/$0.10 USD) - 2018/04/14 21:06:02 CET [2018/04/14 15:06:02 ET]
+Table 'Aaltje II' 6-max Seat #2 is the button
+Seat 1: Alice ($9.70 in chips)
+Seat 2: Bob ($10.40 in chips)
+Seat 3: Cat ($9.90 in chips)
+Cat: posts small blind $0.05
+Alice: posts big blind $0.10
+*** HOLE CARDS ***
+Bob: raises $0.20 to $0.30
+Cat: calls $0.25
+Alice: folds
+*** FLOP *** [2c 7d 9s]
+Cat: checks
+Bob: bets $0.50
+Cat: folds
+Uncalled bet ($0.50) returned to Bob
+Bob collected $0.70 from pot
+*** SUMMARY ***
+Total pot $0.70 | Rake $0
+Board [2c 7d 9s]
+Seat 1: Alice (big blind) folded before Flop
+Seat 2: Bob (button) collected ($0.70)
+Seat 3: Cat (small blind) folded on the Flop
+";
+
+  fn ratio(times: usize, chances: usize) -> Ratio
+  {
+    Ratio { times, chances }
+  }
+
+  #[test]
+  fn preflop_stats()
+  {
+    let hands: Vec<HandHistory> = history::parse_all(SAMPLE).into_iter().map(|hand| hand.unwrap()).collect();
+    let stats = collect(&hands);
+    let names: Vec<&str> = stats.iter().map(|stats| stats.player.as_str()).collect();
+    assert_eq!(names, vec!["Alice", "Bob", "Cat"]);
+    assert!(stats.iter().all(|stats| stats.hands == 2));
+
+    /* a big blind check or fold isn't voluntary, and only facing a single raise is a three-bet chance */
+    let (alice, bob, cat) = (&stats[0], &stats[1], &stats[2]);
+    assert_eq!((alice.vpip, alice.pfr, alice.three_bet), (ratio(1, 2), ratio(1, 2), ratio(0, 1)));
+    assert_eq!((bob.vpip, bob.pfr, bob.three_bet), (ratio(2, 2), ratio(2, 2), ratio(1, 1)));
+    assert_eq!((cat.vpip, cat.pfr, cat.three_bet), (ratio(1, 2), ratio(0, 2), ratio(0, 1)));
+
+    assert_eq!(alice.fold_to_three_bet, ratio(1, 1));
+    assert_eq!(bob.cbet, ratio(1, 1));
+    assert_eq!(cat.wtsd, ratio(0, 1));
+  }
+}
@@ -0,0 +1,194 @@
+/* stud.rs
+ * Analyse a hand of seven-card stud, or razz, where there's no board but each player's
+ * upcards are on show. We know our own cards, every opponent's upcards and any other
+ * cards that have been folded or seen, and work out how likely each opponent's hidden
+ * cards are to beat us right now, and our share of the pot by seventh street.
+ *
+ * Players are dealt two cards down, then four up one at a time, then the last down.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use deck::Deck;
+use deck::combinations;
+use equity;
+use equity::Equity;
+use parallel;
+use rng::Rng;
+use rules::Game;
+use rules::Rules;
+
+/* cards each player ends up with */
+const CARDS: usize = 7;
+
+/* sampled deals handed to each thread at a time */
+const SAMPLES_PER_PIECE: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct Stud
+{
+  pub beaten: Vec<f64>, /* for each opponent, the chance their hidden cards beat us now */
+  pub equity: Equity    /* our share of the pot by seventh street against all of them */
+}
+
+impl Stud
+{
+  /* new
+     Analyse a stud hand against opponents we can only partly see
+     => rules = stud or razz rules
+        hero = our cards in the order dealt: two down, up to four up, then one down
+        upcards = each opponent's upcards, as many as we have showing
+        dead = other cards known to be out of play, eg, folded upcards
+        deals = number of random deals to sample to seventh street
+        seed = starting point for the random numbers
+     <= Stud object, or None if the game isn't stud, the number of cards is wrong, a card
+        appears twice, or there aren't enough cards left to deal everyone out */
+  pub fn new(rules: &Rules, hero: &[Card], upcards: &[Vec<Card>], dead: &[Card],
+             deals: usize, seed: u64) -> Option<Stud>
+  {
+    if rules.game != Game::Stud || hero.len() < 3 || hero.len() > CARDS ||
+       upcards.is_empty() || deals == 0
+    {
+      return None;
+    }
+
+    /* every opponent has as many cards as us, and shows as many */
+    let showing = if hero.len() == CARDS { 4 } else { hero.len() - 2 };
+    let hidden = hero.len() - showing;
+    if upcards.iter().any(|up| up.len() != showing)
+    {
+      return None;
+    }
+
+    let mut known = hero.to_vec();
+    known.extend_from_slice(dead);
+    for up in upcards.iter()
+    {
+      known.extend_from_slice(up);
+    }
+    if equity::has_duplicates(&known)
+    {
+      return None;
+    }
+
+    let mut deck = Deck::with_rules(rules);
+    for card in known.iter()
+    {
+      deck.remove(card);
+    }
+    let unseen = deck.cards().clone();
+
+    let to_come = CARDS - hero.len();
+    if unseen.len() < upcards.len() * (hidden + to_come) + to_come
+    {
+      return None;
+    }
+
+    /* run each opponent's upcards with every set of hidden cards they could hold */
+    let score = rules.score(hero, &[]);
+    let mut beaten = Vec::<f64>::new();
+    for up in upcards.iter()
+    {
+      let mut cards = up.clone();
+      let mut ahead = 0;
+      let mut total = 0;
+      combinations(&unseen, hidden, |down|
+      {
+        cards.truncate(showing);
+        cards.extend_from_slice(down);
+        if rules.score(&cards, &[]) > score
+        {
+          ahead += 1;
+        }
+        total += 1;
+      });
+      beaten.push(ahead as f64 / total as f64);
+    }
+
+    /* deal out everyone's hidden cards and the cards to come at random, as many
+       times as asked. each piece has its own generator, seeded from its number,
+       so the answer doesn't depend on the number of threads */
+    let pieces = parallel::chunks(deals, SAMPLES_PER_PIECE);
+    let results = parallel::map(pieces, |piece|
+    {
+      let mut rng = Rng::new(seed ^ (piece as u64).wrapping_mul(0x2545f4914f6cdd1d));
+      let first = piece * SAMPLES_PER_PIECE;
+      let last = if first + SAMPLES_PER_PIECE < deals { first + SAMPLES_PER_PIECE } else { deals };
+
+      let mut tally = Equity::new();
+      let mut hands = Vec::<Vec<Card>>::with_capacity(upcards.len() + 1);
+      for _ in first..last
+      {
+        let mut deck = unseen.clone();
+        hands.clear();
+        hands.push(hero.to_vec());
+        hands.extend(upcards.iter().cloned());
+        for (player, hand) in hands.iter_mut().enumerate()
+        {
+          let wanted = if player == 0 { to_come } else { hidden + to_come };
+          for _ in 0..wanted
+          {
+            hand.push(deck.swap_remove(rng.below(deck.len())));
+          }
+        }
+
+        let players: Vec<&[Card]> = hands.iter().map(|hand| hand.as_slice()).collect();
+        let mut tallies = vec![Equity::new(); players.len()];
+        equity::award(rules, &players, &[], &mut tallies);
+        tally.add(&tallies[0], 1.0);
+      }
+      tally
+    });
+
+    let mut equity = Equity::new();
+    for tally in results.iter()
+    {
+      equity.add(tally, 1.0);
+    }
+    equity.scale(deals as f64);
+
+    Some(Stud { beaten, equity })
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn cards(text: &str) -> Vec<Card>
+  {
+    text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect()
+  }
+
+  #[test]
+  fn razz_ranks_the_wheel_over_six_four()
+  {
+    let razz = Rules::razz();
+    let wheel = razz.score(&cards("ah 2c 3d 4s 5h kc kd"), &[]);
+    let six_four = razz.score(&cards("6h 4c 3d 2s ah kc kd"), &[]);
+    let six_five = razz.score(&cards("6h 5c 3d 2s ah kc kd"), &[]);
+    let paired = razz.score(&cards("ah ac 2d 3s 4h kc kd"), &[]);
+    assert!(wheel > six_four && six_four > six_five && six_five > paired);
+
+    /* straights and flushes don't count against a razz hand */
+    assert_eq!(wheel, razz.score(&cards("ah 2h 3h 4h 5h kc kd"), &[]));
+  }
+
+  #[test]
+  fn upcards_on_show_are_dead()
+  {
+    /* with the other two aces showing, no one can have aces up with a better kicker */
+    let hero = cards("ah ad 7c");
+    let stud = Stud::new(&Rules::stud(), &hero, &[cards("as"), cards("ac")], &[], 1000, 1).unwrap();
+    assert_eq!(stud.beaten, vec![0.0, 0.0]);
+
+    /* but with the last ace unseen, the player showing the spade one can */
+    let stud = Stud::new(&Rules::stud(), &hero, &[cards("as")], &[], 1000, 1).unwrap();
+    assert!(stud.beaten[0] > 0.0);
+
+    /* and an upcard can't be shown twice */
+    assert!(Stud::new(&Rules::stud(), &hero, &[cards("as"), cards("as")], &[], 1000, 1).is_none());
+  }
+}
@@ -0,0 +1,580 @@
+/* table.rs
+ * A no-limit betting engine: seat players at a Table, deal a hand, and the GameState
+ * takes it from there, posting antes and blinds, working out whose turn it is and what
+ * they may do, enforcing minimum raises, dealing each street, and at the end splitting
+ * the pot, and any side pots, among the best hands with the showdown module.
+ *
+ * Chips are whole numbers. A raise must be by at least the size of the last full bet or
+ * raise, and at least the big blind. A player can always go all in for less, but an
+ * all-in raise short of a full raise doesn't reopen the betting for players who have
+ * already acted.
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use history::Street;
+use rules::Game;
+use rules::Rules;
+use showdown;
+
+/* forced bets, in chips */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blinds
+{
+  pub small: u64,
+  pub big: u64,
+  pub ante: u64 /* posted by everyone dealt in, or 0 for none */
+}
+
+/* a player sitting at the table between hands */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seat
+{
+  pub name: String,
+  pub stack: u64
+}
+
+#[derive(Debug, Clone)]
+pub struct Table
+{
+  pub rules: Rules,     /* game being played, hold 'em or Omaha, high or hi-lo */
+  pub blinds: Blinds,
+  pub seats: Vec<Seat>, /* players in order around the table */
+  pub button: usize     /* seat with the dealer button */
+}
+
+/* something a player can choose to do on their turn. bets and raises are to a total for
+   the street, not counting any ante, so a raise to 300 after posting a big blind of 100
+   puts in 200 more */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action
+{
+  Fold,
+  Check,
+  Call,
+  Bet(u64),
+  Raise(u64),
+  AllIn
+}
+
+/* what the player whose turn it is may do */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Legal
+{
+  pub check: bool,              /* true if there's nothing to call */
+  pub call: Option<u64>,        /* chips needed to call, which may be all of a short stack */
+  pub raise: Option<(u64, u64)> /* smallest and largest total a bet or raise can be to */
+}
+
+/* something that happened in a hand, with the chips involved */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Move
+{
+  Ante(u64),
+  SmallBlind(u64),
+  BigBlind(u64),
+  Fold,
+  Check,
+  Call(u64),
+  Bet(u64),
+  Raise { by: u64, to: u64 }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event
+{
+  pub player: usize,  /* index of the player in the hand */
+  pub street: Street, /* betting round it happened in */
+  pub made: Move,     /* what they did */
+  pub all_in: bool    /* true if it put the rest of their chips in */
+}
+
+/* a player dealt into a hand */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player
+{
+  pub seat: usize,     /* their seat at the table */
+  pub name: String,
+  pub stack: u64,      /* chips still in front of them */
+  pub hole: Vec<Card>, /* their hole cards */
+  pub bet: u64,        /* chips put in on this street, not counting any ante */
+  pub invested: u64,   /* chips put in over the whole hand */
+  pub won: u64,        /* chips won from the pot, once the hand is over */
+  pub folded: bool,
+  pub all_in: bool,
+  acted: bool,         /* true once they've acted on this street */
+  faced: u64           /* size of the last full bet when they last acted */
+}
+
+/* a hand in progress */
+#[derive(Debug, Clone)]
+pub struct GameState
+{
+  pub rules: Rules,
+  pub blinds: Blinds,
+  pub players: Vec<Player>,  /* everyone dealt in, in seat order */
+  pub button: usize,         /* index of the player on the button */
+  pub street: Street,        /* current betting round */
+  pub board: Vec<Card>,      /* community cards dealt so far */
+  pub current_bet: u64,      /* most anyone has bet on this street */
+  pub min_raise: u64,        /* smallest a full raise can raise by */
+  pub to_act: Option<usize>, /* whose turn it is, or None once the hand is over */
+  pub log: Vec<Event>,       /* everything that's happened, in order */
+  full_bet: u64,             /* largest bet or raise on this street that reopened the betting */
+  deck: Vec<Card>            /* cards left to deal, next first */
+}
+
+impl Table
+{
+  /* new
+     Set up an empty table
+     => rules = game being played, hold 'em or Omaha, high or hi-lo
+        blinds = forced bets
+     <= Table object */
+  pub fn new(rules: Rules, blinds: Blinds) -> Table
+  {
+    Table { rules, blinds, seats: Vec::new(), button: 0 }
+  }
+
+  /* seat a player in the next seat with the given chips */
+  pub fn sit(&mut self, name: &str, stack: u64)
+  {
+    self.seats.push(Seat { name: name.to_string(), stack });
+  }
+
+  /* deal
+     Deal a hand to everyone with chips, posting antes and blinds
+     => deck = cards to deal from, next first, eg, a shuffled deck
+     <= GameState object with the first player to act, or None if fewer than two players
+        have chips, there aren't enough cards, or the game isn't one the engine plays */
+  pub fn deal(&self, deck: Vec<Card>) -> Option<GameState>
+  {
+    let dealt: Vec<usize> = (0..self.seats.len()).filter(|seat| self.seats[*seat].stack > 0).collect();
+    let hole_cards = self.rules.hole_cards();
+    let board_game = matches!(self.rules.game, Game::Holdem | Game::Omaha(_));
+    if dealt.len() < 2 || deck.len() < dealt.len() * hole_cards + 5 || !board_game ||
+       self.rules.lowball.is_some() || self.blinds.big == 0
+    {
+      return None;
+    }
+
+    /* the button stays put if its seat is empty, so it moves to the next player in */
+    let button = dealt.iter().position(|seat| *seat >= self.button).unwrap_or(0);
+    let players = dealt.iter().map(|seat| Player
+    {
+      seat: *seat,
+      name: self.seats[*seat].name.clone(),
+      stack: self.seats[*seat].stack,
+      hole: Vec::new(),
+      bet: 0,
+      invested: 0,
+      won: 0,
+      folded: false,
+      all_in: false,
+      acted: false,
+      faced: 0
+    }).collect();
+
+    let mut state = GameState
+    {
+      rules: self.rules,
+      blinds: self.blinds,
+      players,
+      button,
+      street: Street::Preflop,
+      board: Vec::new(),
+      current_bet: 0,
+      min_raise: self.blinds.big,
+      to_act: None,
+      log: Vec::new(),
+      full_bet: 0,
+      deck
+    };
+
+    /* deal the hole cards a card at a time, starting left of the button */
+    let count = state.players.len();
+    for _ in 0..hole_cards
+    {
+      for offset in 1..(count + 1)
+      {
+        let card = state.deck.remove(0);
+        state.players[(button + offset) % count].hole.push(card);
+      }
+    }
+
+    /* heads up, the button posts the small blind and acts first before the flop */
+    let small = if count == 2 { button } else { (button + 1) % count };
+    let big = (small + 1) % count;
+    if self.blinds.ante > 0
+    {
+      for player in 0..count
+      {
+        let ante = state.post(player, self.blinds.ante, false);
+        state.record(player, Move::Ante(ante));
+      }
+    }
+    let posted = state.post(small, self.blinds.small, true);
+    state.record(small, Move::SmallBlind(posted));
+    let posted = state.post(big, self.blinds.big, true);
+    state.record(big, Move::BigBlind(posted));
+    state.current_bet = self.blinds.big;
+    state.full_bet = self.blinds.big;
+
+    state.to_act = state.next_to_act(big);
+    if state.to_act.is_none()
+    {
+      state.finish_street();
+    }
+    Some(state)
+  }
+
+  /* settle
+     Bring the table up to date after a hand: everyone's chips, and the button moves on
+     => state = the hand, once it's over */
+  pub fn settle(&mut self, state: &GameState)
+  {
+    for player in state.players.iter()
+    {
+      self.seats[player.seat].stack = player.stack;
+    }
+
+    let seats = self.seats.len();
+    let button = state.players[state.button].seat;
+    self.button = (1..(seats + 1)).map(|offset| (button + offset) % seats)
+                                  .find(|seat| self.seats[*seat].stack > 0)
+                                  .unwrap_or(button);
+  }
+}
+
+impl GameState
+{
+  /* legal
+     Work out what the player whose turn it is may do. They can always fold
+     <= Legal object, or None if the hand is over */
+  pub fn legal(&self) -> Option<Legal>
+  {
+    let index = self.to_act?;
+    let player = &self.players[index];
+    let owed = self.current_bet - player.bet;
+    let most = player.bet + player.stack;
+
+    /* there's no raising once the betting's closed to us, or if no one could call it */
+    let reopened = !player.acted || self.full_bet > player.faced;
+    let opponents = self.players.iter().enumerate()
+                                .any(|(other_index, other)| other_index != index && !other.folded && !other.all_in);
+    let raise = match most > self.current_bet && reopened && opponents
+    {
+      true =>
+      {
+        let least = if self.current_bet == 0 { self.blinds.big } else { self.current_bet + self.min_raise };
+        Some((least.min(most), most))
+      },
+      false => None
+    };
+
+    Some(Legal
+    {
+      check: owed == 0,
+      call: if owed > 0 { Some(owed.min(player.stack)) } else { None },
+      raise
+    })
+  }
+
+  /* act
+     Carry out the action of the player whose turn it is, and move the hand on
+     => action = what they do
+     <= Ok, or a description of why the action isn't allowed */
+  pub fn act(&mut self, action: Action) -> Result<(), String>
+  {
+    let index = self.to_act.ok_or("the hand is over")?;
+    let legal = self.legal().ok_or("the hand is over")?;
+    let (bet, stack) = (self.players[index].bet, self.players[index].stack);
+
+    /* going all in is a call, bet or raise of everything */
+    let action = match action
+    {
+      Action::AllIn if bet + stack <= self.current_bet => Action::Call,
+      Action::AllIn if self.current_bet == 0 => Action::Bet(bet + stack),
+      Action::AllIn => Action::Raise(bet + stack),
+      other => other
+    };
+
+    match action
+    {
+      Action::Fold =>
+      {
+        self.players[index].folded = true;
+        self.record(index, Move::Fold);
+      },
+      Action::Check =>
+      {
+        if !legal.check
+        {
+          return Err(String::from("can't check facing a bet"));
+        }
+        self.record(index, Move::Check);
+      },
+      Action::Call =>
+      {
+        let owed = legal.call.ok_or("nothing to call")?;
+        let called = self.post(index, owed, true);
+        self.record(index, Move::Call(called));
+      },
+      Action::Bet(to) | Action::Raise(to) =>
+      {
+        let (least, most) = legal.raise.ok_or("raising isn't allowed")?;
+        if matches!(action, Action::Bet(_)) != (self.current_bet == 0)
+        {
+          return Err(String::from(if self.current_bet == 0 { "nothing to raise, so bet" } else { "facing a bet, so raise" }));
+        }
+        if to < least || to > most
+        {
+          return Err(format!("must be to between {} and {}", least, most));
+        }
+
+        /* a raise short of a full raise, all in, doesn't reopen the betting */
+        let by = to - self.current_bet;
+        if by >= self.min_raise || self.current_bet == 0
+        {
+          self.min_raise = by.max(self.blinds.big);
+          self.full_bet = to;
+        }
+        self.current_bet = to;
+        self.post(index, to - bet, true);
+        self.record(index, match action
+        {
+          Action::Bet(_) => Move::Bet(to),
+          _ => Move::Raise { by, to }
+        });
+      },
+      Action::AllIn => ()
+    }
+
+    let full_bet = self.full_bet;
+    let player = &mut self.players[index];
+    player.acted = true;
+    player.faced = full_bet;
+
+    if self.players.iter().filter(|player| !player.folded).count() == 1
+    {
+      self.award();
+      return Ok(());
+    }
+    self.to_act = self.next_to_act(index);
+    if self.to_act.is_none()
+    {
+      self.finish_street();
+    }
+    Ok(())
+  }
+
+  /* return the total chips in the pot, including this street's bets */
+  pub fn pot(&self) -> u64
+  {
+    self.players.iter().map(|player| player.invested).sum()
+  }
+
+  /* return true once the hand is over and the pot awarded */
+  pub fn finished(&self) -> bool
+  {
+    self.to_act.is_none()
+  }
+
+  /* move chips from a player's stack into the pot, as much as they have, returning how many */
+  fn post(&mut self, index: usize, chips: u64, counts_as_bet: bool) -> u64
+  {
+    let player = &mut self.players[index];
+    let chips = chips.min(player.stack);
+    player.stack -= chips;
+    player.invested += chips;
+    if counts_as_bet
+    {
+      player.bet += chips;
+    }
+    if player.stack == 0
+    {
+      player.all_in = true;
+    }
+    chips
+  }
+
+  /* note down what a player did */
+  fn record(&mut self, player: usize, made: Move)
+  {
+    let all_in = self.players[player].all_in && made != Move::Fold && made != Move::Check;
+    self.log.push(Event { player, street: self.street, made, all_in });
+  }
+
+  /* find who acts next after the given player, or None if the street's betting is over */
+  fn next_to_act(&self, after: usize) -> Option<usize>
+  {
+    let count = self.players.len();
+    let able: Vec<usize> = (1..(count + 1)).map(|offset| (after + offset) % count)
+                                         .filter(|index| !self.players[*index].folded && !self.players[*index].all_in)
+                                         .collect();
+
+    /* with everyone else all in, the last player only needs to call what they've bet */
+    if able.len() == 1 && self.players[able[0]].bet >= self.current_bet &&
+       self.players.iter().any(|player| !player.folded && player.all_in)
+    {
+      return None;
+    }
+    able.into_iter().find(|index| !self.players[*index].acted || self.players[*index].bet < self.current_bet)
+  }
+
+  /* end a street's betting, then deal the next, running out the board if no one's left to
+     bet, until there's a player to act or it's time for the showdown */
+  fn finish_street(&mut self)
+  {
+    loop
+    {
+      for player in self.players.iter_mut()
+      {
+        player.bet = 0;
+        player.acted = false;
+        player.faced = 0;
+      }
+      self.current_bet = 0;
+      self.full_bet = 0;
+      self.min_raise = self.blinds.big;
+
+      self.street = match self.street
+      {
+        Street::Preflop => Street::Flop,
+        Street::Flop => Street::Turn,
+        Street::Turn => Street::River,
+        Street::River =>
+        {
+          self.award();
+          return;
+        }
+      };
+      let dealt = self.street.board_cards() - self.board.len();
+      self.board.extend(self.deck.drain(0..dealt));
+
+      /* after the flop, the first player left of the button still in acts first */
+      self.to_act = self.next_to_act(self.button);
+      if self.to_act.is_some()
+      {
+        return;
+      }
+    }
+  }
+
+  /* share out the pot and any side pots among the best hands still in, or to the last
+     player standing, and end the hand */
+  fn award(&mut self)
+  {
+    self.to_act = None;
+    let contributions: Vec<u64> = self.players.iter().map(|player| player.invested).collect();
+    let hole: Vec<Option<Vec<Card>>> = self.players.iter()
+                                           .map(|player| if player.folded { None } else { Some(player.hole.clone()) })
+                                           .collect();
+    let payout = showdown::resolve(&self.rules, &contributions, &hole, &self.board, self.button)
+                   .expect("a hand always ends with someone left in and a full board if contested");
+    for (player, won) in self.players.iter_mut().zip(payout.won.iter())
+    {
+      player.won = *won;
+      player.stack += won;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use deck::Deck;
+
+  /* deal a hand to players with the given stacks, the first on the button */
+  fn deal(stacks: &[u64], ante: u64) -> GameState
+  {
+    let mut table = Table::new(Rules::holdem(), Blinds { small: 50, big: 100, ante });
+    for (seat, stack) in stacks.iter().enumerate()
+    {
+      table.sit(&format!("Player {}", seat + 1), *stack);
+    }
+    table.deal(Deck::new().cards().clone()).unwrap()
+  }
+
+  #[test]
+  fn raises_must_be_by_the_last_full_raise()
+  {
+    let mut state = deal(&[10000, 10000, 10000], 0);
+    assert_eq!(state.to_act, Some(0));
+    assert_eq!(state.legal().unwrap().raise, Some((200, 10000)));
+    assert!(state.act(Action::Raise(150)).is_err());
+    assert!(state.act(Action::Raise(300)).is_ok());
+
+    /* raised by 200, so the next raise must be to at least 500 */
+    assert_eq!(state.min_raise, 200);
+    assert_eq!(state.legal().unwrap().raise, Some((500, 10000)));
+    assert!(state.act(Action::Raise(450)).is_err());
+    assert!(state.act(Action::Raise(500)).is_ok());
+  }
+
+  #[test]
+  fn short_all_in_does_not_reopen_the_betting()
+  {
+    let mut state = deal(&[10000, 10000, 350], 0);
+    state.act(Action::Raise(300)).unwrap();
+    state.act(Action::Call).unwrap();
+
+    /* the big blind's all in raises by only 50, less than the 200 raise before it */
+    state.act(Action::AllIn).unwrap();
+    assert_eq!(state.current_bet, 350);
+    assert_eq!(state.min_raise, 200);
+
+    for player in [0, 1].iter()
+    {
+      assert_eq!(state.to_act, Some(*player));
+      let legal = state.legal().unwrap();
+      assert_eq!(legal.call, Some(50));
+      assert_eq!(legal.raise, None);
+      assert!(state.act(Action::Raise(550)).is_err());
+      state.act(Action::Call).unwrap();
+    }
+    assert_eq!(state.street, Street::Flop);
+  }
+
+  #[test]
+  fn antes_then_blinds_then_streets_in_order()
+  {
+    let mut state = deal(&[10000, 10000, 10000], 10);
+    let posted: Vec<(usize, Move)> = state.log.iter().map(|event| (event.player, event.made)).collect();
+    assert_eq!(posted, vec![(0, Move::Ante(10)), (1, Move::Ante(10)), (2, Move::Ante(10)),
+                            (1, Move::SmallBlind(50)), (2, Move::BigBlind(100))]);
+    assert_eq!(state.pot(), 180);
+
+    /* preflop starts left of the big blind, and each later street left of the button */
+    assert_eq!(state.to_act, Some(0));
+    state.act(Action::Call).unwrap();
+    state.act(Action::Call).unwrap();
+    state.act(Action::Check).unwrap();
+    for (street, cards) in [(Street::Flop, 3), (Street::Turn, 4), (Street::River, 5)].iter()
+    {
+      assert_eq!((state.street, state.board.len()), (*street, *cards));
+      for player in [1, 2, 0].iter()
+      {
+        assert_eq!(state.to_act, Some(*player));
+        state.act(Action::Check).unwrap();
+      }
+    }
+    assert!(state.finished());
+    assert_eq!(state.players.iter().map(|player| player.won).sum::<u64>(), 330);
+  }
+
+  #[test]
+  fn heads_up_button_posts_the_small_blind_and_acts_first()
+  {
+    let mut state = deal(&[10000, 10000], 0);
+    assert_eq!(state.players[0].bet, 50);
+    assert_eq!(state.players[1].bet, 100);
+    assert_eq!(state.to_act, Some(0));
+    state.act(Action::Call).unwrap();
+    state.act(Action::Check).unwrap();
+    assert_eq!(state.street, Street::Flop);
+    assert_eq!(state.to_act, Some(1));
+  }
+}
@@ -0,0 +1,270 @@
+/* texture.rs
+ * Classify a flop, turn or river board by how suited, paired and connected it is,
+ * and how high its cards run, to describe boards as "monotone, paired, connected"
+ *
+ * (c) Chris Williams, 2018. Open-source software: see LICENSE
+ */
+
+use card::Card;
+use card::Value;
+use hand::Hand;
+
+/* how many cards share the board's most common suit */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suitedness
+{
+  Rainbow,    /* no two cards share a suit */
+  TwoTone,    /* at most two cards share a suit: flush draws possible */
+  ThreeFlush, /* three cards of a turn or river share a suit: a flush needs two suited hole cards */
+  FourFlush,  /* four cards of a river share a suit: a flush needs one */
+  Monotone    /* every card shares a suit */
+}
+
+/* how the board's card values pair up */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pairedness
+{
+  Unpaired,
+  Paired,
+  DoublePaired,
+  Trips,
+  FullHouse,
+  Quads
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Texture
+{
+  pub suitedness: Suitedness,
+  pub pairedness: Pairedness,
+  pub straights: usize,       /* number of different straights hole cards could make */
+  pub straight_combos: usize, /* number of hole card value pairs, of 91, that make a straight */
+  pub high: Value,            /* highest card on the board */
+  pub broadway: usize,        /* number of board cards ten or higher */
+  pub low: usize              /* number of board cards eight or lower, counting aces */
+}
+
+impl Texture
+{
+  /* new
+     Analyze a board's texture
+     => board = three to five community cards
+     <= Texture object, or None if the board isn't three to five cards */
+  pub fn new(board: &[Card]) -> Option<Texture>
+  {
+    if board.len() < 3 || board.len() > 5
+    {
+      return None;
+    }
+
+    let mut hand = Hand::new();
+    for card in board.iter()
+    {
+      hand.add(*card);
+    }
+
+    /* the largest suit decides how suited the board is */
+    let suitedness = match hand.count_suits().first().unwrap().total
+    {
+      1 => Suitedness::Rainbow,
+      2 => Suitedness::TwoTone,
+      suited if suited == board.len() => Suitedness::Monotone,
+      3 => Suitedness::ThreeFlush,
+      _ => Suitedness::FourFlush
+    };
+
+    /* values come sorted by total, so the first two decide pairedness */
+    let values = hand.count_values();
+    let second = if values.len() > 1 { values[1].total } else { 0 };
+    let pairedness = match (values[0].total, second)
+    {
+      (4, _) => Pairedness::Quads,
+      (3, 2) => Pairedness::FullHouse,
+      (3, _) => Pairedness::Trips,
+      (2, 2) => Pairedness::DoublePaired,
+      (2, _) => Pairedness::Paired,
+      _      => Pairedness::Unpaired
+    };
+
+    /* build a mask of values present, with bit 1 standing in for a low ace */
+    let mut mask = 0u32;
+    let mut high = Value::Two;
+    let mut broadway = 0;
+    let mut low = 0;
+    for value in values.iter()
+    {
+      mask |= value_bits(value.value);
+      if value.value.to_u32() > high.to_u32()
+      {
+        high = value.value;
+      }
+      if value.value.to_u32() >= 10
+      {
+        broadway += value.total;
+      }
+      if value.value.to_u32() <= 8 || value.value == Value::Ace
+      {
+        low += value.total;
+      }
+    }
+
+    /* a straight is possible in any run of five values holding three or more board cards */
+    let mut straights = 0;
+    for bottom in 1..11
+    {
+      if (mask & (0x1f << bottom)).count_ones() >= 3
+      {
+        straights += 1;
+      }
+    }
+
+    /* count up the hole card value pairs that complete a straight */
+    let mut straight_combos = 0;
+    for first in 2..15
+    {
+      for second in first..15
+      {
+        let hole = value_bits(Value::from_u32(first).unwrap()) |
+                   value_bits(Value::from_u32(second).unwrap());
+        if has_straight(mask | hole)
+        {
+          straight_combos += 1;
+        }
+      }
+    }
+
+    Some(Texture
+    {
+      suitedness,
+      pairedness,
+      straights,
+      straight_combos,
+      high,
+      broadway,
+      low
+    })
+  }
+
+  /* return a string describing the board texture */
+  pub fn describe(&self) -> String
+  {
+    let suitedness = match self.suitedness
+    {
+      Suitedness::Rainbow    => "rainbow",
+      Suitedness::TwoTone    => "two-tone",
+      Suitedness::ThreeFlush => "three-flush",
+      Suitedness::FourFlush  => "four-flush",
+      Suitedness::Monotone   => "monotone"
+    };
+
+    let pairedness = match self.pairedness
+    {
+      Pairedness::Unpaired     => "unpaired",
+      Pairedness::Paired       => "paired",
+      Pairedness::DoublePaired => "double-paired",
+      Pairedness::Trips        => "trips",
+      Pairedness::FullHouse    => "full house",
+      Pairedness::Quads        => "quads"
+    };
+
+    let connectedness = match self.straights
+    {
+      0 => "disconnected".to_string(),
+      _ => format!("connected ({} straight{}, {} value combo{})",
+                   self.straights, if self.straights == 1 { "" } else { "s" },
+                   self.straight_combos, if self.straight_combos == 1 { "" } else { "s" })
+    };
+
+    format!("{}, {}, {}, {} high with {} broadway and {} low",
+            suitedness, pairedness, connectedness, self.high.to_str(), self.broadway, self.low)
+  }
+}
+
+/* bits set for a card value in a value mask: bit N for value N, plus bit 1 for an ace */
+fn value_bits(value: Value) -> u32
+{
+  match value
+  {
+    Value::Ace => (1 << 14) | (1 << 1),
+    _ => 1 << value.to_u32()
+  }
+}
+
+/* does a value mask contain five values in a row? */
+fn has_straight(mask: u32) -> bool
+{
+  for bottom in 1..11
+  {
+    if mask & (0x1f << bottom) == 0x1f << bottom
+    {
+      return true;
+    }
+  }
+  false
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn texture(text: &str) -> Texture
+  {
+    let board: Vec<Card> = text.split_whitespace().map(|desc| Card::new(desc).unwrap()).collect();
+    Texture::new(&board).unwrap()
+  }
+
+  #[test]
+  fn suitedness()
+  {
+    assert_eq!(texture("qh 7c 2d").suitedness, Suitedness::Rainbow);
+    assert_eq!(texture("qh 7c 2d 3s").suitedness, Suitedness::Rainbow);
+    assert_eq!(texture("qh 7h 2d").suitedness, Suitedness::TwoTone);
+    assert_eq!(texture("qh 7h 2d 3d 9c").suitedness, Suitedness::TwoTone);
+    assert_eq!(texture("qh jh th 2c").suitedness, Suitedness::ThreeFlush);
+    assert_eq!(texture("qh jh th 2c 3d").suitedness, Suitedness::ThreeFlush);
+    assert_eq!(texture("qh jh th 2h 3d").suitedness, Suitedness::FourFlush);
+    assert_eq!(texture("qh jh th").suitedness, Suitedness::Monotone);
+    assert_eq!(texture("qh jh th 2h").suitedness, Suitedness::Monotone);
+    assert_eq!(texture("qh jh th 2h 3h").suitedness, Suitedness::Monotone);
+  }
+
+  #[test]
+  fn pairedness()
+  {
+    assert_eq!(texture("qh 7c 2d").pairedness, Pairedness::Unpaired);
+    assert_eq!(texture("qh qc 2d").pairedness, Pairedness::Paired);
+    assert_eq!(texture("qh qc 2d 2s").pairedness, Pairedness::DoublePaired);
+    assert_eq!(texture("qh qc qd").pairedness, Pairedness::Trips);
+    assert_eq!(texture("qh qc qd 2s 2c").pairedness, Pairedness::FullHouse);
+    assert_eq!(texture("qh qc qd qs").pairedness, Pairedness::Quads);
+  }
+
+  #[test]
+  fn connectedness()
+  {
+    /* K-7-2 has no three values within five of each other */
+    let board = texture("kh 7c 2d");
+    assert_eq!((board.straights, board.straight_combos), (0, 0));
+
+    /* 9-8-7 sits in the 5-9, 6-T, 7-J runs, completed by 65, T6 and JT */
+    let board = texture("9h 8c 7d");
+    assert_eq!((board.straights, board.straight_combos), (3, 3));
+
+    /* A-2-3 only makes the wheel, with 45 */
+    let board = texture("ah 2c 3d");
+    assert_eq!((board.straights, board.straight_combos), (1, 1));
+  }
+
+  #[test]
+  fn broadway_and_low_count_cards_alike()
+  {
+    let board = texture("kh kd 3c 3s 8h");
+    assert_eq!((board.high, board.broadway, board.low), (Value::King, 2, 3));
+
+    /* aces play high and low, so count as both */
+    let board = texture("ah ad 5c");
+    assert_eq!((board.high, board.broadway, board.low), (Value::Ace, 2, 3));
+    assert_eq!(board.describe(), "rainbow, paired, disconnected, ace high with 2 broadway and 3 low");
+  }
+}